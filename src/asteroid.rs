@@ -1,37 +1,45 @@
+mod generator;
+
 use bevy::{
     app::{App, Plugin, Startup, Update},
-    asset::{Assets, Handle},
+    asset::{AssetServer, Assets, Handle},
     ecs::{
         bundle::Bundle,
         component::Component,
         entity::Entity,
         event::{Event, EventReader},
-        schedule::{IntoSystemConfigs, SystemSet},
+        schedule::{
+            common_conditions::{not, resource_exists},
+            Condition, IntoSystemConfigs, SystemSet,
+        },
         system::{Command, Commands, EntityCommand, EntityCommands, Query, Res, ResMut, Resource},
         world::Mut,
     },
-    log::info,
-    math::{primitives::RegularPolygon, Vec2, Vec3},
+    log::{info, warn},
+    math::{Vec2, Vec3},
     prelude::World,
-    render::{
-        color::Color,
-        mesh::{Mesh, VertexAttributeValues},
-    },
+    render::{color::Color, mesh::Mesh},
     sprite::{ColorMaterial, MaterialMesh2dBundle, Mesh2dHandle},
     transform::components::Transform,
     utils::default,
 };
+use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_rapier2d::{
     dynamics::{ReadMassProperties, RigidBody, Sleeping, Velocity},
     geometry::{Collider, CollisionGroups, Group, Restitution},
 };
+use generator::AsteroidPresets;
 use itertools::Itertools;
-use rand::{rngs::ThreadRng, Rng};
+use rand::Rng;
 
 use crate::{
     edge_wrap::{Bounds, Duplicable},
+    mesh_cut::{circle_polygon, cut_mesh_with_polygon},
     mesh_utils::calculate_mesh_area,
-    shatter::spawn_shattered_mesh_batch,
+    outline::Outlined,
+    rng::GameRng,
+    rollback::RollbackAppExt,
+    shatter::{spawn_shattered_mesh_batch, Fracture},
     split_mesh::{split_mesh, trim_mesh},
     utils::mesh_to_collider,
 };
@@ -41,7 +49,19 @@ pub struct AsteroidPlugin;
 impl Plugin for AsteroidPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SplitAsteroidEvent>()
-            .add_systems(Startup, load_asteroid_material)
+            .init_resource::<AsteroidDamageSettings>()
+            .rollback_component::<Asteroid>()
+            .add_plugins(RonAssetPlugin::<AsteroidPresets>::new(&[
+                "asteroid_presets.ron",
+            ]))
+            .add_systems(Startup, (load_asteroid_material, load_asteroid_presets))
+            .add_systems(
+                Update,
+                set_asteroid_presets_resource.run_if(
+                    resource_exists::<AsteroidPresetsHandle>
+                        .and_then(not(resource_exists::<AsteroidPresets>)),
+                ),
+            )
             .add_systems(Update, split_asteroid_event.in_set(AsteroidSet));
     }
 }
@@ -58,25 +78,42 @@ fn load_asteroid_material(mut commands: Commands, mut materials: ResMut<Assets<C
     ));
 }
 
-#[derive(Component)]
+#[derive(Resource)]
+struct AsteroidPresetsHandle(Handle<AsteroidPresets>);
+
+fn load_asteroid_presets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AsteroidPresetsHandle(
+        asset_server.load("asteroid_presets.ron"),
+    ));
+}
+
+fn set_asteroid_presets_resource(
+    mut commands: Commands,
+    asteroid_presets_handle: Res<AsteroidPresetsHandle>,
+    asteroid_presets_assets: Res<Assets<AsteroidPresets>>,
+) {
+    if let Some(asteroid_presets) = asteroid_presets_assets.get(asteroid_presets_handle.0.clone()) {
+        commands.insert_resource(asteroid_presets.clone());
+    }
+}
+
+#[derive(Component, Clone)]
 pub struct Asteroid;
 
-const ASTEROID_MAX_VERTICES: usize = 14;
-const ASTEROID_MAX_VERTICE_DRIFT: f32 = 8.;
 const ASTEROID_MAX_SPAWN_LIN_VELOCITY: f32 = 50.;
 const ASTEROID_MAX_SPAWN_ANG_VELOCITY: f32 = 1.;
 const ASTEROID_SPAWN_CIRCUMRADIUS: f32 = 50.;
 
 pub const ASTEROID_GROUP: Group = Group::GROUP_3;
 
-pub fn spawn_asteroids(mut commands: Commands, bounds: Res<Bounds>) {
+pub fn spawn_asteroids(mut commands: Commands, bounds: Res<Bounds>, mut rng: ResMut<GameRng>) {
     // Divide bounds area by approximate asteroid area to get a rough estimate of how many asteroids to spawn
     let asteroid_spawn_count = (((bounds.0.x * bounds.0.y) as usize
         / (ASTEROID_SPAWN_CIRCUMRADIUS * ASTEROID_SPAWN_CIRCUMRADIUS) as usize)
         / 10)
         .clamp(2, 5);
     info!(bounds= ?bounds, number= ?asteroid_spawn_count, "Spawning asteroids");
-    let mut rng = rand::thread_rng();
+    let rng = &mut rng.0;
     let asteroid_positions: Vec<Vec2> = (0..asteroid_spawn_count)
         .map(|_| {
             Vec2::new(
@@ -100,9 +137,9 @@ struct SpawnAsteroid {
 
 impl EntityCommand for SpawnAsteroid {
     fn apply(self, entity: Entity, world: &mut World) {
-        let mut rng = ThreadRng::default();
-
-        let asteroid_bundle = create_random_asteroid(&mut rng, world, self.position);
+        let asteroid_bundle = world.resource_scope(|world, mut rng: Mut<GameRng>| {
+            create_random_asteroid(&mut rng.0, world, self.position)
+        });
         world.entity_mut(entity).insert(asteroid_bundle);
     }
 }
@@ -113,19 +150,19 @@ struct SpawnAsteroidBatch {
 
 impl Command for SpawnAsteroidBatch {
     fn apply(self, world: &mut World) {
-        let mut rng = ThreadRng::default();
-        let asteroid_bundles = self
-            .positions
-            .iter()
-            .map(|position| create_random_asteroid(&mut rng, world, *position))
-            .collect_vec();
+        let asteroid_bundles = world.resource_scope(|world, mut rng: Mut<GameRng>| {
+            self.positions
+                .iter()
+                .map(|position| create_random_asteroid(&mut rng.0, world, *position))
+                .collect_vec()
+        });
 
         world.spawn_batch(asteroid_bundles);
     }
 }
 
 fn create_random_asteroid(
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     world: &mut World,
     asteroid_pos: Vec2,
 ) -> impl Bundle {
@@ -136,8 +173,14 @@ fn create_random_asteroid(
     let asteroid_angular_velocity =
         rng.gen_range(-ASTEROID_MAX_SPAWN_ANG_VELOCITY..ASTEROID_MAX_SPAWN_ANG_VELOCITY);
     let (asteroid_mesh_handle, collider) =
-        world.resource_scope(|_world, mut meshes: Mut<Assets<Mesh>>| {
-            create_asteroid_mesh_and_collider(rng, &mut meshes)
+        world.resource_scope(|world, mut meshes: Mut<Assets<Mesh>>| {
+            // The presets might not have finished loading yet
+            match world.get_resource::<AsteroidPresets>() {
+                Some(presets) => create_asteroid_mesh_and_collider(rng, presets, &mut meshes),
+                None => {
+                    create_asteroid_mesh_and_collider(rng, &AsteroidPresets::default(), &mut meshes)
+                }
+            }
         });
 
     let material_handle = world.resource::<AsteroidMaterial>().0.clone();
@@ -157,33 +200,18 @@ fn create_random_asteroid(
 }
 
 fn create_asteroid_mesh_and_collider(
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
+    presets: &AsteroidPresets,
     meshes: &mut Assets<Mesh>,
 ) -> (Handle<Mesh>, Collider) {
-    let mut mesh = Mesh::from(RegularPolygon::new(
-        ASTEROID_SPAWN_CIRCUMRADIUS,
-        ASTEROID_MAX_VERTICES,
-    ));
-
-    let pos_attributes = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION).expect(
-        "Mesh does not have a position attribute. This should not happen as we just created the mesh",
-        );
-
-    let VertexAttributeValues::Float32x3(pos_attr_vec3) = pos_attributes else {
-        panic!("Position attribute is not a Float32x3");
-    };
-
-    pos_attr_vec3.iter_mut().for_each(|v| {
-        // Translate vertice randomly
-        v[0] += rng.gen_range(-ASTEROID_MAX_VERTICE_DRIFT..ASTEROID_MAX_VERTICE_DRIFT);
-        v[1] += rng.gen_range(-ASTEROID_MAX_VERTICE_DRIFT..ASTEROID_MAX_VERTICE_DRIFT);
-    });
+    let size = presets.sizes.choose(rng);
+    let mesh = presets.generate(rng, size);
 
     let collider = mesh_to_collider(&mesh).expect("Failed to create collider");
     (meshes.add(mesh), collider)
 }
 
-fn create_asteroid_bundle(
+pub fn create_asteroid_bundle(
     transform: Transform,
     asteroid_mesh_handle: Handle<Mesh>,
     material_handle: Handle<ColorMaterial>,
@@ -200,6 +228,7 @@ fn create_asteroid_bundle(
         },
         collider,
         Duplicable,
+        Outlined,
         CollisionGroups::new(ASTEROID_GROUP, Group::ALL),
         RigidBody::Dynamic,
         ReadMassProperties::default(),
@@ -233,28 +262,112 @@ impl<'w, 's> AsteroidSpawnParamExt for Commands<'w, 's> {
     }
 }
 
+/// An asteroid got hit hard enough to break.
+///
+/// Depending on the [`AsteroidDamageSettings`] and the impact the asteroid is either split in two
+/// or has a crater blasted out of it.
 #[derive(Event)]
 pub struct SplitAsteroidEvent {
     pub asteroid_entity: Entity,
     pub collision_direction: Vec2,
     pub collision_position: Vec2,
+    /// Point of impact in the local space of the asteroid.
+    pub impact_point: Vec2,
+    pub impact_speed: f32,
+}
+
+/// How asteroids break when they get hit.
+#[derive(Resource, Debug, Clone)]
+pub struct AsteroidDamageSettings {
+    /// Whether hits that would only blast a small crater erode the asteroid instead of splitting
+    /// it.
+    pub craters_enabled: bool,
+}
+
+impl Default for AsteroidDamageSettings {
+    fn default() -> Self {
+        Self {
+            craters_enabled: true,
+        }
+    }
 }
 
 const ASTEROID_MIN_AREA: f32 = 500.;
+const ASTEROID_CRATER_RADIUS_PER_IMPACT_SPEED: f32 = 0.025;
+const ASTEROID_CRATER_MIN_RADIUS: f32 = 4.;
+const ASTEROID_CRATER_MAX_RADIUS: f32 = 20.;
+/// Hits whose crater would take away more than this fraction of the asteroid split it instead.
+const ASTEROID_CRATER_MAX_AREA_FRACTION: f32 = 0.2;
+const ASTEROID_CRATER_SEGMENTS: usize = 10;
+/// Random variation of the crater outline, relative to the crater radius.
+const ASTEROID_CRATER_ROUGHNESS: f32 = 0.3;
 
 fn split_asteroid_event(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     asteroid_material: Res<AsteroidMaterial>,
+    damage_settings: Res<AsteroidDamageSettings>,
     mut asteroid_query: Query<(&Transform, &Velocity, &mut Mesh2dHandle)>,
     mut split_asteroid_events: EventReader<SplitAsteroidEvent>,
+    mut rng: ResMut<GameRng>,
 ) {
+    let mut hit_asteroids = Vec::new();
+
     for event in split_asteroid_events.read() {
-        let (transform, velocity, mesh_handle) = asteroid_query
-            .get_mut(event.asteroid_entity)
-            .expect("Asteroid entity not found");
+        // The asteroid is only replaced once the commands are applied
+        if hit_asteroids.contains(&event.asteroid_entity) {
+            continue;
+        }
+        hit_asteroids.push(event.asteroid_entity);
+
+        // The asteroid may have left the bounds and been despawned since the hit
+        let Ok((transform, velocity, mut mesh_handle)) =
+            asteroid_query.get_mut(event.asteroid_entity)
+        else {
+            continue;
+        };
+
+        let crater_radius = (event.impact_speed * ASTEROID_CRATER_RADIUS_PER_IMPACT_SPEED)
+            .clamp(ASTEROID_CRATER_MIN_RADIUS, ASTEROID_CRATER_MAX_RADIUS);
+        let asteroid_area =
+            calculate_mesh_area(meshes.get(&mesh_handle.0).expect("Asteroid mesh not found"));
+        let crater_area = std::f32::consts::PI * crater_radius * crater_radius;
+
+        if damage_settings.craters_enabled
+            && crater_area < asteroid_area * ASTEROID_CRATER_MAX_AREA_FRACTION
+        {
+            let remaining = crater_asteroid(
+                &mut commands,
+                &mut rng.0,
+                &mut mesh_handle,
+                &mut meshes,
+                asteroid_material.0.clone(),
+                transform,
+                *velocity,
+                event.impact_point,
+                crater_radius,
+            );
+
+            match remaining {
+                Some((collider, offset)) => {
+                    // The remaining asteroid is recentered, move it so it stays in place
+                    let translation = transform.transform_point(offset.extend(0.));
+                    commands
+                        .entity(event.asteroid_entity)
+                        .insert((collider, transform.with_translation(translation)));
+                    info!("Asteroid cratered");
+                }
+                None => {
+                    info!("Asteroid eroded away");
+                    commands.entity(event.asteroid_entity).despawn();
+                }
+            }
+            continue;
+        }
+
         split_asteroid(
             &mut commands,
+            &mut rng.0,
             &mesh_handle.0,
             &mut meshes,
             asteroid_material.0.clone(),
@@ -262,6 +375,7 @@ fn split_asteroid_event(
             *velocity,
             event.collision_direction,
             event.collision_position,
+            event.impact_point,
         );
 
         info!("Asteroid split");
@@ -269,8 +383,118 @@ fn split_asteroid_event(
     }
 }
 
+/// Blasts a crater around `impact_point` out of the asteroid, emitting the removed material as
+/// debris.
+///
+/// The largest remaining piece replaces the asteroid mesh, its collider and offset are returned.
+/// Other pieces the crater broke off become new asteroids or debris, depending on their size.
+/// Returns `None` if nothing large enough to stay an asteroid is left.
+fn crater_asteroid(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    mesh_handle: &mut Mesh2dHandle,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    material_handle: Handle<ColorMaterial>,
+    transform: &Transform,
+    velocity: Velocity,
+    impact_point: Vec2,
+    crater_radius: f32,
+) -> Option<(Collider, Vec2)> {
+    let crater = circle_polygon(impact_point, crater_radius, ASTEROID_CRATER_SEGMENTS)
+        .into_iter()
+        .map(|point| {
+            let roughness = rng.gen_range(-ASTEROID_CRATER_ROUGHNESS..ASTEROID_CRATER_ROUGHNESS);
+            point + (point - impact_point) * roughness
+        })
+        .collect_vec();
+
+    let mesh = meshes.get(&mesh_handle.0).expect("Asteroid mesh not found");
+    let [removed, mut remaining] = cut_mesh_with_polygon(mesh, &crater);
+
+    remaining.sort_by(|(a, _), (b, _)| calculate_mesh_area(a).total_cmp(&calculate_mesh_area(b)));
+    let largest = remaining.pop();
+
+    let mut debris = removed;
+    for (piece_mesh, piece_offset) in remaining {
+        if calculate_mesh_area(&piece_mesh) > ASTEROID_MIN_AREA {
+            let translation = transform.transform_point(piece_offset.extend(0.));
+            spawn_asteroid_split(
+                commands,
+                transform.with_translation(translation),
+                velocity,
+                meshes,
+                material_handle.clone(),
+                &piece_mesh,
+            );
+        } else {
+            debris.push((piece_mesh, piece_offset));
+        }
+    }
+
+    let remainder = match largest {
+        Some((largest_mesh, largest_offset))
+            if calculate_mesh_area(&largest_mesh) > ASTEROID_MIN_AREA =>
+        {
+            match mesh_to_collider(&largest_mesh) {
+                Ok(collider) => Some((collider, largest_mesh, largest_offset)),
+                Err(error) => {
+                    warn!(%error, "Failed to create a collider for the cratered asteroid");
+                    debris.push((largest_mesh, largest_offset));
+                    None
+                }
+            }
+        }
+        too_small => {
+            // Whatever is left of the asteroid crumbles into debris
+            debris.extend(too_small);
+            None
+        }
+    };
+
+    spawn_crater_debris(
+        commands,
+        rng,
+        meshes,
+        material_handle,
+        transform,
+        velocity,
+        impact_point,
+        debris,
+    );
+
+    let (collider, largest_mesh, largest_offset) = remainder?;
+    mesh_handle.0 = meshes.add(largest_mesh);
+
+    Some((collider, largest_offset))
+}
+
+fn spawn_crater_debris(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    material_handle: Handle<ColorMaterial>,
+    transform: &Transform,
+    velocity: Velocity,
+    impact_point: Vec2,
+    debris: Vec<(Mesh, Vec2)>,
+) {
+    let fracture = Fracture::Voronoi { impact_point };
+    let debris = debris.into_iter().map(|(mesh, offset)| {
+        let translation = transform.transform_point(offset.extend(0.));
+        (
+            transform.with_translation(translation),
+            velocity,
+            mesh,
+            fracture.for_piece(offset),
+        )
+    });
+
+    spawn_shattered_mesh_batch(commands, material_handle, debris, meshes, rng);
+}
+
 fn split_asteroid(
     commands: &mut Commands,
+    rng: &mut impl Rng,
     original_mesh: &Handle<Mesh>,
     meshes: &mut ResMut<Assets<Mesh>>,
     material_handle: Handle<ColorMaterial>,
@@ -278,8 +502,10 @@ fn split_asteroid(
     velocity: Velocity,
     collision_direction: Vec2,
     collision_position: Vec2,
+    impact_point: Vec2,
 ) {
     let mesh = meshes.get(original_mesh).expect("Original mesh not found");
+    let fracture = Fracture::Voronoi { impact_point };
 
     // Rotate the collision direction by the rotation of the asteroid
     // to get the collision direction in the asteroid's local space.
@@ -295,7 +521,7 @@ fn split_asteroid(
     let mut debris = Vec::new();
 
     for (half_mesh, half_offset) in halves.into_iter().flatten() {
-        let ((trimmed_mesh, trimmed_offset), trimmings) = trim_mesh(half_mesh);
+        let ((trimmed_mesh, trimmed_offset), trimmings) = trim_mesh(half_mesh, rng);
         let translation = transform.transform_point((half_offset + trimmed_offset).extend(0.));
         let main_transform =
             Transform::from_translation(translation).with_rotation(transform.rotation);
@@ -309,7 +535,6 @@ fn split_asteroid(
             angvel: velocity.angvel,
         };
         let mesh_area = calculate_mesh_area(&trimmed_mesh);
-        debug_assert!(mesh_area >= 0.);
         if mesh_area > ASTEROID_MIN_AREA {
             spawn_asteroid_split(
                 commands,
@@ -319,19 +544,29 @@ fn split_asteroid(
                 material_handle.clone(),
                 &trimmed_mesh,
             );
-        } else if mesh_area > 0. && mesh_area < ASTEROID_MIN_AREA {
-            debris.push((main_transform, velocity, trimmed_mesh))
+        } else if mesh_area > 0. {
+            debris.push((
+                main_transform,
+                velocity,
+                trimmed_mesh,
+                fracture.for_piece(half_offset + trimmed_offset),
+            ))
         }
 
         debris.extend(trimmings.into_iter().map(|(mesh, trimmed_offset)| {
             let translation = transform.transform_point((half_offset + trimmed_offset).extend(0.));
             let transform =
                 Transform::from_translation(translation).with_rotation(main_transform.rotation);
-            (transform, velocity, mesh)
+            (
+                transform,
+                velocity,
+                mesh,
+                fracture.for_piece(half_offset + trimmed_offset),
+            )
         }));
     }
 
-    spawn_shattered_mesh_batch(commands, material_handle, debris.into_iter(), meshes);
+    spawn_shattered_mesh_batch(commands, material_handle, debris.into_iter(), meshes, rng);
 }
 
 fn spawn_asteroid_split(
@@ -342,7 +577,13 @@ fn spawn_asteroid_split(
     material_handle: Handle<ColorMaterial>,
     mesh: &Mesh,
 ) {
-    let collider = mesh_to_collider(mesh).expect("Failed to create collider");
+    let collider = match mesh_to_collider(mesh) {
+        Ok(collider) => collider,
+        Err(error) => {
+            warn!(%error, "Skipping an asteroid split without a collider");
+            return;
+        }
+    };
 
     let mesh_handle = meshes.add(mesh.clone());
 
@@ -363,7 +604,7 @@ mod tests {
         math::{primitives::Rectangle, Quat},
     };
 
-    use crate::asteroid::split_asteroid;
+    use crate::{asteroid::split_asteroid, shatter::Debris};
 
     use super::*;
 
@@ -388,6 +629,7 @@ mod tests {
 
                 split_asteroid(
                     &mut commands,
+                    &mut rand::thread_rng(),
                     &mesh_handle,
                     &mut meshes,
                     material_handle,
@@ -395,6 +637,7 @@ mod tests {
                     Velocity::zero(),
                     Vec2::new(0., 1.),
                     Vec2::ZERO,
+                    Vec2::ZERO,
                 );
             },
         );
@@ -438,6 +681,7 @@ mod tests {
 
                 split_asteroid(
                     &mut commands,
+                    &mut rand::thread_rng(),
                     &mesh_handle,
                     &mut meshes,
                     material_handle,
@@ -445,6 +689,7 @@ mod tests {
                     Velocity::zero(),
                     Vec2::new(0., 1.),
                     Vec2::ZERO,
+                    Vec2::ZERO,
                 );
             },
         );
@@ -465,4 +710,48 @@ mod tests {
                 assert_eq!(translation.x, 0.);
             });
     }
+
+    #[test]
+    fn test_crater_asteroid_rectangle() {
+        let mut app = App::new();
+
+        app.insert_resource(Assets::<Mesh>::default())
+            .insert_resource(Assets::<ColorMaterial>::default());
+
+        app.add_systems(
+            Startup,
+            |mut commands: Commands,
+             mut meshes: ResMut<Assets<Mesh>>,
+             mut materials: ResMut<Assets<ColorMaterial>>| {
+                let rectangle_shape = Rectangle::from_size(Vec2::new(100., 100.));
+                let mut mesh_handle = Mesh2dHandle(meshes.add(Mesh::from(rectangle_shape)));
+                let material_handle = materials.add(ColorMaterial::from(Color::WHITE));
+
+                let remaining = crater_asteroid(
+                    &mut commands,
+                    &mut rand::thread_rng(),
+                    &mut mesh_handle,
+                    &mut meshes,
+                    material_handle,
+                    &Transform::default(),
+                    Velocity::zero(),
+                    Vec2::new(50., 0.),
+                    10.,
+                );
+
+                assert!(
+                    remaining.is_some(),
+                    "Asteroid should survive a small crater"
+                );
+                let area = calculate_mesh_area(meshes.get(&mesh_handle.0).unwrap());
+                assert!(area < 10000. && area > 9000., "Unexpected area {area}");
+            },
+        );
+
+        app.update();
+
+        // The crater doesn't break off any new asteroids, only debris
+        assert_eq!(app.world.query::<&Asteroid>().iter(&app.world).len(), 0);
+        assert!(app.world.query::<&Debris>().iter(&app.world).len() > 0);
+    }
 }
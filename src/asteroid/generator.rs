@@ -0,0 +1,304 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    asset::Asset, ecs::system::Resource, math::Vec2, reflect::TypePath, render::mesh::Mesh,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::split_mesh::create_mesh_2d;
+
+/// The noise never pulls the outline closer to the center than this fraction of the radius.
+const MIN_RADIUS_FACTOR: f32 = 0.3;
+/// Fewer vertices could leave gaps of more than half a turn between neighbours.
+const MIN_VERTICES: usize = 5;
+/// Maximum shift of a vertex from its evenly spaced angle, relative to the spacing.
+const MAX_ANGLE_JITTER: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AsteroidSize {
+    Large,
+    Medium,
+    Small,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SizeClass {
+    pub radius: f32,
+    pub min_vertices: usize,
+    pub max_vertices: usize,
+    /// Relative chance of spawning an asteroid of this size at the start of a round.
+    pub spawn_weight: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SizeClasses {
+    pub large: SizeClass,
+    pub medium: SizeClass,
+    pub small: SizeClass,
+}
+
+impl SizeClasses {
+    pub fn get(&self, size: AsteroidSize) -> &SizeClass {
+        match size {
+            AsteroidSize::Large => &self.large,
+            AsteroidSize::Medium => &self.medium,
+            AsteroidSize::Small => &self.small,
+        }
+    }
+
+    pub fn choose(&self, rng: &mut impl Rng) -> AsteroidSize {
+        let sizes = [
+            AsteroidSize::Large,
+            AsteroidSize::Medium,
+            AsteroidSize::Small,
+        ];
+        let total_weight: f32 = sizes.iter().map(|size| self.get(*size).spawn_weight).sum();
+
+        let mut pick = rng.gen_range(0.0..total_weight.max(f32::EPSILON));
+        for size in sizes {
+            pick -= self.get(size).spawn_weight;
+            if pick < 0. {
+                return size;
+            }
+        }
+        AsteroidSize::Large
+    }
+}
+
+/// Periodic value noise around the outline of the asteroid, summed over octaves.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RadialNoise {
+    pub octaves: u32,
+    /// Number of bumps around the outline in the first octave.
+    pub frequency: u32,
+    /// Amplitude of the first octave, relative to the radius.
+    pub amplitude: f32,
+    /// Amplitude falloff for every further octave.
+    pub persistence: f32,
+}
+
+impl RadialNoise {
+    fn sample(&self, rng: &mut impl Rng, angles: &[f32]) -> Vec<f32> {
+        let mut offsets = vec![0.; angles.len()];
+
+        for octave in 0..self.octaves {
+            let lattice_size = (self.frequency.max(1) << octave) as usize;
+            let lattice = (0..lattice_size)
+                .map(|_| rng.gen_range(-1.0..=1.0f32))
+                .collect::<Vec<_>>();
+            let amplitude = self.amplitude * self.persistence.powi(octave as i32);
+
+            for (offset, angle) in offsets.iter_mut().zip(angles) {
+                let position = angle.rem_euclid(TAU) / TAU * lattice_size as f32;
+                let index = position.floor() as usize % lattice_size;
+                let t = position.fract();
+                let t = t * t * (3. - 2. * t);
+                let value = lattice[index] * (1. - t) + lattice[(index + 1) % lattice_size] * t;
+                *offset += value * amplitude;
+            }
+        }
+
+        offsets
+    }
+}
+
+/// A family of asteroid shapes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AsteroidPreset {
+    pub name: String,
+    pub noise: RadialNoise,
+    /// Maximum number of dents pressed into the outline.
+    pub max_concavities: u32,
+    /// Depth of the dents, relative to the radius.
+    pub concavity_depth: f32,
+}
+
+#[derive(Resource, Debug, Clone, Deserialize, Asset, TypePath)]
+pub struct AsteroidPresets {
+    pub sizes: SizeClasses,
+    pub presets: Vec<AsteroidPreset>,
+}
+
+impl Default for AsteroidPresets {
+    fn default() -> Self {
+        Self {
+            sizes: SizeClasses {
+                large: SizeClass {
+                    radius: 50.,
+                    min_vertices: 12,
+                    max_vertices: 16,
+                    spawn_weight: 1.,
+                },
+                medium: SizeClass {
+                    radius: 35.,
+                    min_vertices: 10,
+                    max_vertices: 14,
+                    spawn_weight: 0.,
+                },
+                small: SizeClass {
+                    radius: 20.,
+                    min_vertices: 8,
+                    max_vertices: 10,
+                    spawn_weight: 0.,
+                },
+            },
+            presets: vec![AsteroidPreset {
+                name: "rough".to_string(),
+                noise: RadialNoise {
+                    octaves: 2,
+                    frequency: 4,
+                    amplitude: 0.15,
+                    persistence: 0.5,
+                },
+                max_concavities: 0,
+                concavity_depth: 0.,
+            }],
+        }
+    }
+}
+
+impl AsteroidPresets {
+    /// Generates an asteroid of the given size from a random preset.
+    pub fn generate(&self, rng: &mut impl Rng, size: AsteroidSize) -> Mesh {
+        let Some(preset) = self.presets.choose(rng) else {
+            return AsteroidPresets::default().generate(rng, size);
+        };
+
+        debug!(preset = preset.name, ?size, "Generating asteroid");
+        generate_asteroid_mesh(preset, self.sizes.get(size), rng.gen())
+    }
+}
+
+/// Generates the outline of an asteroid as counter-clockwise points around the origin.
+///
+/// Every point lies in a different direction from the origin, so the outline is always
+/// star-shaped around it.
+pub fn generate_asteroid_outline(
+    preset: &AsteroidPreset,
+    size: &SizeClass,
+    seed: u64,
+) -> Vec<Vec2> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let min_vertices = size.min_vertices.max(MIN_VERTICES);
+    let vertex_count = rng.gen_range(min_vertices..=size.max_vertices.max(min_vertices));
+    let spacing = TAU / vertex_count as f32;
+    let angles = (0..vertex_count)
+        .map(|i| i as f32 * spacing + rng.gen_range(-MAX_ANGLE_JITTER..=MAX_ANGLE_JITTER) * spacing)
+        .collect::<Vec<_>>();
+
+    let mut radius_factors = preset
+        .noise
+        .sample(&mut rng, &angles)
+        .into_iter()
+        .map(|offset| 1. + offset)
+        .collect::<Vec<_>>();
+
+    let concavities = rng.gen_range(0..=preset.max_concavities);
+    for _ in 0..concavities {
+        let center = rng.gen_range(0.0..TAU);
+        let width = rng.gen_range(0.2..0.6f32);
+        for (factor, angle) in radius_factors.iter_mut().zip(&angles) {
+            let distance = (angle - center + TAU / 2.).rem_euclid(TAU) - TAU / 2.;
+            *factor -= preset.concavity_depth * (-(distance / width).powi(2)).exp();
+        }
+    }
+
+    angles
+        .into_iter()
+        .zip(radius_factors)
+        .map(|(angle, factor)| {
+            Vec2::from_angle(angle) * size.radius * factor.max(MIN_RADIUS_FACTOR)
+        })
+        .collect()
+}
+
+/// Generates an asteroid mesh, triangulated as a fan around the origin.
+pub fn generate_asteroid_mesh(preset: &AsteroidPreset, size: &SizeClass, seed: u64) -> Mesh {
+    let outline = generate_asteroid_outline(preset, size, seed);
+
+    let vertices = std::iter::once(Vec2::ZERO)
+        .chain(outline.iter().copied())
+        .collect::<Vec<_>>();
+    let indices = (1..=outline.len())
+        .map(|i| [0, i, i % outline.len() + 1])
+        .collect::<Vec<_>>();
+
+    create_mesh_2d(&vertices, &indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    use crate::mesh_utils::{calculate_mesh_area, is_ccw_winded, valid_mesh};
+
+    use super::*;
+
+    fn preset(octaves: u32, amplitude: f32, max_concavities: u32, depth: f32) -> AsteroidPreset {
+        AsteroidPreset {
+            name: "test".to_string(),
+            noise: RadialNoise {
+                octaves,
+                frequency: 3,
+                amplitude,
+                persistence: 0.5,
+            },
+            max_concavities,
+            concavity_depth: depth,
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_asteroid() {
+        let presets = AsteroidPresets::default();
+        let size = presets.sizes.get(AsteroidSize::Large);
+
+        let a = generate_asteroid_outline(&presets.presets[0], size, 42);
+        let b = generate_asteroid_outline(&presets.presets[0], size, 42);
+
+        assert_eq!(a, b);
+    }
+
+    proptest! {
+        #[test]
+        fn test_generated_mesh_is_valid(
+            seed in any::<u64>(),
+            octaves in 0u32..5,
+            amplitude in 0.0..1.0f32,
+            max_concavities in 0u32..4,
+            depth in 0.0..1.0f32,
+            min_vertices in 0usize..20,
+            extra_vertices in 0usize..10,
+            radius in 1.0..100.0f32,
+        ) {
+            let preset = preset(octaves, amplitude, max_concavities, depth);
+            let size = SizeClass {
+                radius,
+                min_vertices,
+                max_vertices: min_vertices + extra_vertices,
+                spawn_weight: 1.,
+            };
+
+            let mesh = generate_asteroid_mesh(&preset, &size, seed);
+
+            prop_assert!(valid_mesh(&mesh));
+            prop_assert!(calculate_mesh_area(&mesh) > 0.);
+
+            let vertices = mesh
+                .attribute(Mesh::ATTRIBUTE_POSITION)
+                .unwrap()
+                .as_float3()
+                .unwrap()
+                .iter()
+                .map(|v| Vec2::new(v[0], v[1]))
+                .collect_vec();
+            for (i0, i1, i2) in mesh.indices().unwrap().iter().tuples() {
+                prop_assert!(is_ccw_winded(&vertices, &[i0, i1, i2]));
+            }
+        }
+    }
+}
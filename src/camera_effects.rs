@@ -0,0 +1,245 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    ecs::{
+        component::Component,
+        event::EventReader,
+        query::With,
+        schedule::{IntoSystemConfigs, SystemSet},
+        system::{Query, Res, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    math::{Quat, Vec2},
+    render::camera::OrthographicProjection,
+    time::{Real, Time, Virtual},
+    transform::{components::Transform, TransformSystem},
+};
+use bevy_rapier2d::dynamics::ReadMassProperties;
+
+use crate::{
+    asteroid::{Asteroid, AsteroidSet, SplitAsteroidEvent},
+    explosion::ExplosionEvent,
+    net::Lockstep,
+    projectile::ProjectileSet,
+    ship::{ShipDestroyedEvent, ShipSet},
+};
+
+/// Shakes, zooms and briefly freezes the camera in reaction to gameplay events.
+///
+/// Every effect can be turned off in [`CameraEffectsSettings`].
+pub struct CameraEffectsPlugin;
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraEffectsSettings>()
+            .init_resource::<HitStop>()
+            .add_systems(
+                Update,
+                (toggle_camera_effects, add_camera_trauma)
+                    .chain()
+                    .after(ProjectileSet)
+                    .after(ShipSet)
+                    .before(AsteroidSet)
+                    .in_set(CameraEffectsSet),
+            )
+            .add_systems(
+                PostUpdate,
+                (apply_camera_effects, apply_hit_stop)
+                    .before(TransformSystem::TransformPropagate)
+                    .in_set(CameraEffectsSet),
+            );
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct CameraEffectsSet;
+
+const TRAUMA_PER_EXPLOSION_RADIUS: f32 = 0.015;
+const SHIP_DESTROYED_TRAUMA: f32 = 0.6;
+const SHIP_DESTROYED_ZOOM_PUNCH: f32 = 0.08;
+const SHIP_DESTROYED_HIT_STOP: f32 = 0.15;
+/// Only asteroids at least this heavy make the camera react when they split.
+const HEAVY_ASTEROID_MIN_MASS: f32 = 0.4;
+const HEAVY_SPLIT_TRAUMA: f32 = 0.3;
+const HEAVY_SPLIT_ZOOM_PUNCH: f32 = 0.03;
+const HEAVY_SPLIT_HIT_STOP: f32 = 0.05;
+/// Trauma lost per second.
+const TRAUMA_DECAY: f32 = 1.5;
+/// Zoom punch lost per second.
+const ZOOM_PUNCH_DECAY: f32 = 0.4;
+const MAX_SHAKE_OFFSET: f32 = 20.;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+/// Speed of the game while the hit-stop lasts.
+const HIT_STOP_TIME_SCALE: f32 = 0.05;
+
+/// Accessibility toggles for the camera effects.
+#[derive(Resource, Debug, Clone)]
+pub struct CameraEffectsSettings {
+    pub shake_enabled: bool,
+    pub zoom_punch_enabled: bool,
+    pub hit_stop_enabled: bool,
+}
+
+impl Default for CameraEffectsSettings {
+    fn default() -> Self {
+        Self {
+            shake_enabled: true,
+            zoom_punch_enabled: true,
+            hit_stop_enabled: true,
+        }
+    }
+}
+
+/// State of the effects of a camera.
+///
+/// The effects are applied on top of the transform and projection set by other systems, so the
+/// camera can still be moved and scaled independently.
+#[derive(Component, Debug, Default)]
+pub struct CameraEffects {
+    /// Between 0 and 1, the shake grows with its square.
+    trauma: f32,
+    /// Fraction the camera is zoomed in by.
+    zoom_punch: f32,
+    applied_offset: Vec2,
+    applied_angle: f32,
+    applied_zoom_punch: f32,
+}
+
+/// Remaining real time the game is slowed down for.
+#[derive(Resource, Debug, Default)]
+struct HitStop {
+    remaining: f32,
+}
+
+impl HitStop {
+    fn trigger(&mut self, duration: f32) {
+        self.remaining = self.remaining.max(duration);
+    }
+}
+
+fn toggle_camera_effects(
+    mut settings: ResMut<CameraEffectsSettings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        settings.shake_enabled = !settings.shake_enabled;
+        info!(enabled = settings.shake_enabled, "Screen shake toggled");
+    }
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        settings.zoom_punch_enabled = !settings.zoom_punch_enabled;
+        info!(enabled = settings.zoom_punch_enabled, "Zoom punch toggled");
+    }
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        settings.hit_stop_enabled = !settings.hit_stop_enabled;
+        info!(enabled = settings.hit_stop_enabled, "Hit-stop toggled");
+    }
+}
+
+fn add_camera_trauma(
+    mut camera_query: Query<&mut CameraEffects>,
+    mut explosion_events: EventReader<ExplosionEvent>,
+    mut ship_destroyed_events: EventReader<ShipDestroyedEvent>,
+    mut split_asteroid_events: EventReader<SplitAsteroidEvent>,
+    asteroid_query: Query<&ReadMassProperties, With<Asteroid>>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    let mut trauma = 0.;
+    let mut zoom_punch: f32 = 0.;
+    let mut hit_stop_duration: f32 = 0.;
+
+    for explosion in explosion_events.read() {
+        trauma += explosion.radius * TRAUMA_PER_EXPLOSION_RADIUS;
+    }
+
+    for _ in ship_destroyed_events.read() {
+        trauma += SHIP_DESTROYED_TRAUMA;
+        zoom_punch = zoom_punch.max(SHIP_DESTROYED_ZOOM_PUNCH);
+        hit_stop_duration = hit_stop_duration.max(SHIP_DESTROYED_HIT_STOP);
+    }
+
+    for SplitAsteroidEvent {
+        asteroid_entity, ..
+    } in split_asteroid_events.read()
+    {
+        let heavy = asteroid_query
+            .get(*asteroid_entity)
+            .is_ok_and(|mass_properties| mass_properties.get().mass >= HEAVY_ASTEROID_MIN_MASS);
+        if heavy {
+            trauma += HEAVY_SPLIT_TRAUMA;
+            zoom_punch = zoom_punch.max(HEAVY_SPLIT_ZOOM_PUNCH);
+            hit_stop_duration = hit_stop_duration.max(HEAVY_SPLIT_HIT_STOP);
+        }
+    }
+
+    for mut camera_effects in &mut camera_query {
+        camera_effects.trauma = (camera_effects.trauma + trauma).min(1.);
+        camera_effects.zoom_punch = camera_effects.zoom_punch.max(zoom_punch);
+    }
+    if hit_stop_duration > 0. {
+        hit_stop.trigger(hit_stop_duration);
+    }
+}
+
+fn apply_camera_effects(
+    mut camera_query: Query<(
+        &mut CameraEffects,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+    settings: Res<CameraEffectsSettings>,
+    time: Res<Time<Real>>,
+) {
+    let delta_seconds = time.delta_seconds();
+    let t = time.elapsed_seconds();
+
+    for (mut camera_effects, mut transform, mut projection) in &mut camera_query {
+        camera_effects.trauma = (camera_effects.trauma - TRAUMA_DECAY * delta_seconds).max(0.);
+        camera_effects.zoom_punch =
+            (camera_effects.zoom_punch - ZOOM_PUNCH_DECAY * delta_seconds).max(0.);
+
+        let shake = if settings.shake_enabled {
+            camera_effects.trauma.powi(2)
+        } else {
+            0.
+        };
+        // Cheap smooth noise, every axis with its own frequencies so they don't move in sync
+        let offset = Vec2::new(
+            (t * 41.).sin() * (t * 13.3).cos(),
+            (t * 37.).cos() * (t * 17.9).sin(),
+        ) * MAX_SHAKE_OFFSET
+            * shake;
+        let angle = (t * 29.).sin() * (t * 7.1).cos() * MAX_SHAKE_ANGLE * shake;
+        let zoom_punch = if settings.zoom_punch_enabled {
+            camera_effects.zoom_punch
+        } else {
+            0.
+        };
+
+        transform.translation += (offset - camera_effects.applied_offset).extend(0.);
+        transform.rotation *= Quat::from_rotation_z(angle - camera_effects.applied_angle);
+        projection.scale *= (1. - zoom_punch) / (1. - camera_effects.applied_zoom_punch);
+
+        camera_effects.applied_offset = offset;
+        camera_effects.applied_angle = angle;
+        camera_effects.applied_zoom_punch = zoom_punch;
+    }
+}
+
+fn apply_hit_stop(
+    mut hit_stop: ResMut<HitStop>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+    settings: Res<CameraEffectsSettings>,
+    session: Option<Res<Lockstep>>,
+) {
+    // Slowing down time is a local setting the peer of an online game wouldn't follow
+    if hit_stop.remaining > 0. && settings.hit_stop_enabled && session.is_none() {
+        hit_stop.remaining -= real_time.delta_seconds();
+        virtual_time.set_relative_speed(HIT_STOP_TIME_SCALE);
+    } else {
+        hit_stop.remaining = 0.;
+        if virtual_time.relative_speed() != 1. {
+            virtual_time.set_relative_speed(1.);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Little endian encoding shared by the messages of online games and the saved game.
+
+use bevy::math::{Vec2, Vec3};
+
+use crate::{edge_wrap::BoundaryMode, game_state::GameMode};
+
+pub fn encode_vec2(bytes: &mut Vec<u8>, vector: Vec2) {
+    bytes.extend(vector.x.to_le_bytes());
+    bytes.extend(vector.y.to_le_bytes());
+}
+
+pub fn encode_vec3(bytes: &mut Vec<u8>, vector: Vec3) {
+    bytes.extend(vector.x.to_le_bytes());
+    bytes.extend(vector.y.to_le_bytes());
+    bytes.extend(vector.z.to_le_bytes());
+}
+
+pub fn encode_game_mode(game_mode: GameMode) -> u8 {
+    match game_mode {
+        GameMode::Solo => 0,
+        GameMode::Coop => 1,
+        GameMode::Versus => 2,
+    }
+}
+
+pub fn decode_game_mode(byte: u8) -> Option<GameMode> {
+    match byte {
+        0 => Some(GameMode::Solo),
+        1 => Some(GameMode::Coop),
+        2 => Some(GameMode::Versus),
+        _ => None,
+    }
+}
+
+pub fn encode_boundary_mode(boundary_mode: BoundaryMode) -> u8 {
+    match boundary_mode {
+        BoundaryMode::Wrap => 0,
+        BoundaryMode::Walls => 1,
+        BoundaryMode::Void => 2,
+    }
+}
+
+pub fn decode_boundary_mode(byte: u8) -> Option<BoundaryMode> {
+    match byte {
+        0 => Some(BoundaryMode::Wrap),
+        1 => Some(BoundaryMode::Walls),
+        2 => Some(BoundaryMode::Void),
+        _ => None,
+    }
+}
+
+/// Reads little endian values from the front of a datagram or save.
+pub struct Reader<'a>(pub &'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*bytes)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.bytes::<1>().map(|[byte]| byte)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+
+    pub fn vec2(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.f32()?, self.f32()?))
+    }
+
+    pub fn vec3(&mut self) -> Option<Vec3> {
+        Some(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+}
@@ -1,35 +1,54 @@
+use std::sync::Arc;
+
 use bevy::{
-    app::{App, First, Plugin, Update},
+    app::{App, Plugin, Update},
     asset::Handle,
+    core::Name,
     ecs::{
+        change_detection::Mut,
         component::Component,
-        entity::Entity,
-        query::{Changed, With},
+        entity::{Entity, EntityMapper, MapEntities},
+        event::{Event, EventWriter},
+        query::{Has, With, Without},
         removal_detection::RemovedComponents,
-        schedule::{common_conditions::resource_exists, IntoSystemConfigs, SystemSet},
-        system::{Commands, Query, Res, ResMut, Resource},
+        schedule::{
+            common_conditions::{resource_changed, resource_equals, resource_exists},
+            Condition, IntoSystemConfigs, SystemSet,
+        },
+        system::{Commands, Query, Res, Resource},
     },
     gizmos::gizmos::Gizmos,
-    hierarchy::DespawnRecursiveExt,
+    hierarchy::{BuildChildren, Children, DespawnRecursiveExt},
     log::debug,
-    math::{Quat, Vec2, Vec3, Vec3Swizzles},
-    render::color::Color,
-    sprite::{ColorMaterial, MaterialMesh2dBundle, Mesh2dHandle},
-    transform::components::{GlobalTransform, Transform},
-    window::{PrimaryWindow, Window},
+    math::{EulerRot, Quat, Vec2, Vec3, Vec3Swizzles},
+    render::{color::Color, prelude::SpatialBundle, texture::Image, view::Visibility},
+    sprite::{ColorMaterial, Mesh2dHandle, Sprite},
+    transform::{components::Transform, TransformBundle},
+    utils::default,
 };
 use bevy_rapier2d::{
-    dynamics::RigidBody,
-    geometry::{ActiveEvents, Collider, CollisionGroups},
+    dynamics::{MassProperties, RigidBody},
+    geometry::{Collider, ColliderMassProperties, CollisionGroups, Friction, Group, Restitution},
     na::{Isometry2, Vector2},
+    parry::shape::SharedShape,
 };
 
+use crate::{game_state::GameMode, rollback::RollbackAppExt};
+
 pub struct EdgeWrapPlugin;
 
 impl Plugin for EdgeWrapPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Bounds>()
-            .add_systems(First, sync_bounds_to_window_size)
+            .init_resource::<BoundaryMode>()
+            .init_resource::<BoundaryModeSettings>()
+            .add_event::<LeftBoundsEvent>()
+            .rollback_component::<Duplicable>()
+            .rollback_component::<DamagedByVoid>()
+            .rollback_component::<WrapExtent>()
+            .rollback_component_with_entities::<Wrapped>()
+            .rollback_component_with_entities::<WrapGhost>()
+            .rollback_component_with_entities::<GhostMirror>()
             .add_systems(
                 Update,
                 draw_bounds_gizmos
@@ -39,10 +58,21 @@ impl Plugin for EdgeWrapPlugin {
             .add_systems(
                 Update,
                 (
+                    (
+                        boundary_mode_changed.run_if(resource_changed::<BoundaryMode>),
+                        sync_boundary_walls.run_if(
+                            resource_changed::<BoundaryMode>.or_else(resource_changed::<Bounds>),
+                        ),
+                    ),
                     duplicable_removed,
-                    duplicate_on_map_edge,
-                    sync_duplicate_transforms,
-                    teleport_original_to_swap,
+                    (
+                        teleport_to_opposite_edge,
+                        update_wrap_ghosts,
+                        sync_ghost_mirrors,
+                    )
+                        .chain()
+                        .run_if(resource_equals(BoundaryMode::Wrap)),
+                    leave_void.run_if(resource_equals(BoundaryMode::Void)),
                 )
                     .chain()
                     .in_set(EdgeWrapSet),
@@ -53,6 +83,75 @@ impl Plugin for EdgeWrapPlugin {
 #[derive(SystemSet, PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub struct EdgeWrapSet;
 
+/// Collision group of the [`BoundaryWall`]s.
+pub const WALL_GROUP: Group = Group::GROUP_6;
+
+const WALL_THICKNESS: f32 = 100.;
+const WALL_RESTITUTION: f32 = 0.8;
+
+/// What happens to [`Duplicable`] entities at the edges of the [`Bounds`].
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// Leaving one edge enters at the opposite one.
+    #[default]
+    Wrap,
+    /// Bodies bounce off static walls along the edges.
+    Walls,
+    /// Entities leaving the bounds are despawned, or damaged if they are [`DamagedByVoid`].
+    Void,
+}
+
+impl BoundaryMode {
+    pub fn next(self) -> Self {
+        match self {
+            BoundaryMode::Wrap => BoundaryMode::Walls,
+            BoundaryMode::Walls => BoundaryMode::Void,
+            BoundaryMode::Void => BoundaryMode::Wrap,
+        }
+    }
+}
+
+/// The [`BoundaryMode`] chosen for each [`GameMode`], the active one follows the game mode.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct BoundaryModeSettings {
+    pub solo: BoundaryMode,
+    pub coop: BoundaryMode,
+    pub versus: BoundaryMode,
+}
+
+impl BoundaryModeSettings {
+    pub fn get(&self, game_mode: GameMode) -> BoundaryMode {
+        match game_mode {
+            GameMode::Solo => self.solo,
+            GameMode::Coop => self.coop,
+            GameMode::Versus => self.versus,
+        }
+    }
+
+    pub fn get_mut(&mut self, game_mode: GameMode) -> &mut BoundaryMode {
+        match game_mode {
+            GameMode::Solo => &mut self.solo,
+            GameMode::Coop => &mut self.coop,
+            GameMode::Versus => &mut self.versus,
+        }
+    }
+}
+
+/// Static wall along one edge of the [`Bounds`] in [`BoundaryMode::Walls`].
+#[derive(Component)]
+pub struct BoundaryWall;
+
+/// Keeps a [`Duplicable`] entity alive when it leaves the bounds in [`BoundaryMode::Void`], its
+/// owner handles the [`LeftBoundsEvent`] instead.
+#[derive(Component, Clone)]
+pub struct DamagedByVoid;
+
+/// Sent every frame a [`Duplicable`] entity is outside of the bounds in [`BoundaryMode::Void`].
+#[derive(Event, Debug)]
+pub struct LeftBoundsEvent {
+    pub entity: Entity,
+}
+
 #[derive(Resource)]
 pub struct BoundsDebug;
 
@@ -65,6 +164,41 @@ impl Default for Bounds {
     }
 }
 
+impl Bounds {
+    /// Full width and height of the wrapped world.
+    pub fn size(&self) -> Vec2 {
+        self.0 * 2.
+    }
+
+    /// Shortest displacement from `from` to `to` when leaving one edge enters at the opposite one.
+    pub fn wrapped_displacement(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let displacement = to - from;
+        let size = self.size();
+
+        Vec2::new(
+            wrap_axis(displacement.x, size.x),
+            wrap_axis(displacement.y, size.y),
+        )
+    }
+
+    pub fn wrapped_distance(&self, from: Vec2, to: Vec2) -> f32 {
+        self.wrapped_displacement(from, to).length()
+    }
+
+    /// Maps a position outside of the bounds to the equivalent position inside them.
+    pub fn wrap_position(&self, position: Vec2) -> Vec2 {
+        self.wrapped_displacement(Vec2::ZERO, position)
+    }
+}
+
+fn wrap_axis(displacement: f32, size: f32) -> f32 {
+    if size <= 0. {
+        return displacement;
+    }
+
+    displacement - size * (displacement / size).round()
+}
+
 fn draw_bounds_gizmos(mut gizmos: Gizmos, bounds: Res<Bounds>) {
     gizmos.rect_2d(
         Vec2::ZERO,
@@ -74,171 +208,601 @@ fn draw_bounds_gizmos(mut gizmos: Gizmos, bounds: Res<Bounds>) {
     );
 }
 
-fn sync_bounds_to_window_size(
-    mut bounds: ResMut<Bounds>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-) {
-    let Ok(window) = window_query.get_single() else {
-        *bounds = Bounds::default();
-        return;
-    };
+/// Wraps the entity around the edges of the [`Bounds`].
+///
+/// While the entity reaches over an edge it owns ghosts of itself at the opposite edges: render
+/// instances of it and its children, and extra shapes in its collider if it has one. Collisions
+/// with a ghost are reported for the entity itself.
+///
+/// The extent of the entity is taken from its collider, entities without one need a
+/// [`WrapExtent`].
+#[derive(Component, Clone)]
+pub struct Duplicable;
 
-    bounds.0 = Vec2::new(
-        window.resolution.width() / 2.,
-        window.resolution.height() / 2.,
-    );
+/// Half size of a purely visual [`Duplicable`] entity without a collider, in its own space.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct WrapExtent(pub Vec2);
+
+/// Ghosts of an entity reaching over the edges, shifted along x, y and both.
+#[derive(Component, Debug, Clone)]
+pub struct Wrapped {
+    /// Collider of the entity without the ghost shapes.
+    base_collider: Option<Collider>,
+    /// Collider with the ghost shapes last set on the entity, anything else replaced the base.
+    wrapped_collider: Option<Collider>,
+    /// Mass properties the entity had before wrapping, restored when it stops.
+    own_mass_properties: Option<ColliderMassProperties>,
+    offsets: [Option<Vec2>; 3],
+    rotation: Quat,
+    ghosts: [Option<Entity>; 3],
 }
 
-#[derive(Component)]
-pub struct Duplicable;
+impl MapEntities for Wrapped {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        for ghost in self.ghosts.iter_mut().flatten() {
+            *ghost = entity_mapper.map_entity(*ghost);
+        }
+    }
+}
 
-#[derive(Component, Debug, Clone)]
-struct Original {
-    duplicate_x: Option<Entity>,
-    duplicate_y: Option<Entity>,
-    duplicate_xy: Option<Entity>,
+impl Wrapped {
+    pub fn base_collider(&self) -> Option<&Collider> {
+        self.base_collider.as_ref()
+    }
 }
 
-#[derive(Component, Debug)]
-pub struct Duplicate {
+/// Collider of the entity itself, without the shapes of its ghosts.
+pub fn base_collider<'a>(collider: &'a Collider, opt_wrapped: Option<&'a Wrapped>) -> &'a Collider {
+    opt_wrapped
+        .and_then(Wrapped::base_collider)
+        .unwrap_or(collider)
+}
+
+/// Render instance of a [`Wrapped`] entity at an opposite edge, spawned as its child.
+#[derive(Component, Debug, Clone)]
+pub struct WrapGhost {
     pub original: Entity,
 }
 
-fn duplicate_on_map_edge(
+impl MapEntities for WrapGhost {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.original = entity_mapper.map_entity(self.original);
+    }
+}
+
+/// Copies the look of an entity in the hierarchy of a wrapped entity into a ghost.
+#[derive(Component, Debug, Clone)]
+struct GhostMirror {
+    source: Entity,
+}
+
+impl MapEntities for GhostMirror {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.source = entity_mapper.map_entity(self.source);
+    }
+}
+
+/// Moves entities whose center left the bounds to the opposite edge, where their ghost was.
+fn teleport_to_opposite_edge(
+    mut duplicable_query: Query<(Entity, &mut Transform), With<Duplicable>>,
+    bounds: Res<Bounds>,
+) {
+    for (entity, mut transform) in &mut duplicable_query {
+        let position = transform.translation.xy();
+        let outside = position.abs().cmpgt(bounds.0);
+        if !outside.any() {
+            continue;
+        }
+
+        let offset = Vec2::select(outside, -bounds.size() * position.signum(), Vec2::ZERO);
+        transform.translation += offset.extend(0.);
+
+        debug!(
+            "Teleporting entity {:?} to {:?}",
+            entity, transform.translation
+        );
+    }
+}
+
+/// Half size of the axis aligned box around the entity, from its collider or [`WrapExtent`].
+fn wrap_half_extents(
+    transform: &Transform,
+    opt_collider: Option<&Collider>,
+    opt_extent: Option<&WrapExtent>,
+) -> Option<Vec2> {
+    if let Some(collider) = opt_collider {
+        let (angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        let aabb = collider
+            .raw
+            .compute_aabb(&Isometry2::new(Vector2::zeros(), angle));
+        return Some(Vec2::new(aabb.half_extents().x, aabb.half_extents().y));
+    }
+
+    let WrapExtent(extent) = opt_extent?;
+    let extent = (*extent * transform.scale.xy()).abs();
+    let x_axis = transform.rotation.mul_vec3(Vec3::X).xy().abs();
+    let y_axis = transform.rotation.mul_vec3(Vec3::Y).xy().abs();
+
+    Some(x_axis * extent.x + y_axis * extent.y)
+}
+
+/// Offsets of the ghosts needed at the opposite edges, shifted along x, y and both.
+fn ghost_offsets(position: Vec2, half_extents: Vec2, bounds: &Bounds) -> [Option<Vec2>; 3] {
+    let positions = edge_positions_of_box(position, half_extents, bounds);
+
+    let intersects_x =
+        positions.left == Position::Intersecting || positions.right == Position::Intersecting;
+    let intersects_y =
+        positions.top == Position::Intersecting || positions.bottom == Position::Intersecting;
+    let offset = -bounds.size() * position.signum();
+
+    [
+        intersects_x.then_some(Vec2::new(offset.x, 0.)),
+        intersects_y.then_some(Vec2::new(0., offset.y)),
+        (intersects_x && intersects_y).then_some(offset),
+    ]
+}
+
+/// Compound of the base collider and copies of it at the ghost offsets, in the space of the
+/// entity.
+///
+/// Every copy keeps the parts in the space of the mesh, so contact points on a ghost are in mesh
+/// space just like on the entity itself.
+fn compound_with_ghosts(base_collider: &Collider, local_offsets: &[Vec2]) -> Collider {
+    let parts = match base_collider.raw.as_compound() {
+        Some(compound) => compound.shapes().to_vec(),
+        None => vec![(Isometry2::identity(), base_collider.raw.clone())],
+    };
+
+    let shapes = std::iter::once(Vec2::ZERO)
+        .chain(local_offsets.iter().copied())
+        .flat_map(|offset| {
+            parts.iter().map(move |(isometry, shape)| {
+                let translation = isometry.translation.vector + Vector2::new(offset.x, offset.y);
+                (
+                    Isometry2::from_parts(translation.into(), isometry.rotation),
+                    shape.clone(),
+                )
+            })
+        })
+        .collect();
+
+    Collider::from(SharedShape::compound(shapes))
+}
+
+/// Mass properties of the base collider with the density or mass the entity had on its own, so
+/// the ghost shapes don't add to the mass of the body.
+fn base_mass_properties(
+    base_collider: &Collider,
+    own_mass_properties: ColliderMassProperties,
+) -> ColliderMassProperties {
+    let density = match own_mass_properties {
+        ColliderMassProperties::Density(density) => density,
+        ColliderMassProperties::Mass(mass) => {
+            let area = base_collider.raw.mass_properties(1.).mass();
+            if area > 0. {
+                mass / area
+            } else {
+                0.
+            }
+        }
+        // Explicit mass properties don't depend on the shapes in the first place
+        ColliderMassProperties::MassProperties(_) => return own_mass_properties,
+    };
+    let mass_properties = base_collider.raw.mass_properties(density);
+
+    ColliderMassProperties::MassProperties(MassProperties {
+        local_center_of_mass: Vec2::new(mass_properties.local_com.x, mass_properties.local_com.y),
+        mass: mass_properties.mass(),
+        principal_inertia: mass_properties.principal_inertia(),
+    })
+}
+
+fn update_wrap_ghosts(
     mut commands: Commands,
     mut duplicable_query: Query<
         (
             Entity,
-            &GlobalTransform,
-            &Collider,
-            &mut Mesh2dHandle,
-            &mut Handle<ColorMaterial>,
-            Option<&mut Original>,
-            Option<&CollisionGroups>,
+            &Transform,
+            Option<&mut Collider>,
+            Option<&WrapExtent>,
+            Option<&ColliderMassProperties>,
+            Option<&mut Wrapped>,
         ),
-        (With<Duplicable>, Changed<GlobalTransform>),
+        With<Duplicable>,
     >,
+    mut ghost_query: Query<&mut Transform, (With<WrapGhost>, Without<Duplicable>)>,
     bounds: Res<Bounds>,
 ) {
-    for (
-        entity,
-        transform,
-        collider,
-        mesh_handle,
-        material_handle,
-        mut opt_original,
-        opt_coll_groups,
-    ) in duplicable_query.iter_mut()
+    for (entity, transform, mut opt_collider, opt_extent, opt_mass_properties, opt_wrapped) in
+        &mut duplicable_query
     {
-        let mut spawn_duplicates = |original: &mut Original| {
-            if original.duplicate_x.is_some()
-                && original.duplicate_y.is_some()
-                && original.duplicate_xy.is_some()
-            {
-                return;
+        // The collider may have been replaced, e.g. by a damaged hull, which becomes the new base
+        let base_changed = match (&opt_collider, &opt_wrapped) {
+            (Some(collider), Some(wrapped)) => {
+                wrapped
+                    .wrapped_collider
+                    .as_ref()
+                    .is_none_or(|wrapped_collider| {
+                        !Arc::ptr_eq(&collider.raw.0, &wrapped_collider.raw.0)
+                    })
             }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        let opt_base_collider = match &opt_wrapped {
+            Some(wrapped) if !base_changed => wrapped.base_collider.clone(),
+            _ => opt_collider.as_deref().cloned(),
+        };
 
-            let positions = edge_positions(transform, collider, &bounds);
-
-            let intersects_y = positions.top == Position::Intersecting
-                || positions.bottom == Position::Intersecting;
-            let intersects_x = positions.left == Position::Intersecting
-                || positions.right == Position::Intersecting;
-
-            if intersects_y && original.duplicate_y.is_none() {
-                let offset_y = bounds.0.y * 2. - transform.translation().y.signum();
-                let duplicate_y = spawn_duplicate(
-                    &mut commands,
-                    transform,
-                    &mesh_handle,
-                    &material_handle,
-                    collider,
-                    Vec3::new(0.0, offset_y, 0.0),
-                    entity,
-                    opt_coll_groups.cloned(),
-                );
-                original.duplicate_y = Some(duplicate_y);
-                debug!("Spawning duplicate y for entity {:?}", entity);
+        let Some(half_extents) =
+            wrap_half_extents(transform, opt_base_collider.as_ref(), opt_extent)
+        else {
+            continue;
+        };
+        let offsets = ghost_offsets(transform.translation.xy(), half_extents, &bounds);
+
+        if offsets.iter().all(Option::is_none) {
+            if let Some(wrapped) = opt_wrapped {
+                if let (Some(collider), Some(base_collider), false) =
+                    (opt_collider.as_mut(), opt_base_collider, base_changed)
+                {
+                    **collider = base_collider;
+                }
+                for ghost in wrapped.ghosts.into_iter().flatten() {
+                    commands.entity(ghost).despawn_recursive();
+                }
+                unwrap_entity(&mut commands, entity, &wrapped, None);
+                debug!("Removing ghosts of entity {:?}", entity);
             }
+            continue;
+        }
 
-            if intersects_x && original.duplicate_x.is_none() {
-                let offset_x = bounds.0.x * 2. * -transform.translation().x.signum();
-                let duplicate_x = spawn_duplicate(
-                    &mut commands,
-                    transform,
-                    &mesh_handle,
-                    &material_handle,
-                    collider,
-                    Vec3::new(offset_x, 0.0, 0.0),
-                    entity,
-                    opt_coll_groups.cloned(),
-                );
-                original.duplicate_x = Some(duplicate_x);
-                debug!("Spawning duplicate x for entity {:?}", entity);
-            }
+        let mut new_wrapped = None;
+        let wrapped = match opt_wrapped {
+            Some(wrapped) => wrapped.into_inner(),
+            None => new_wrapped.insert(Wrapped {
+                base_collider: None,
+                wrapped_collider: None,
+                own_mass_properties: opt_mass_properties.copied(),
+                offsets: [None; 3],
+                rotation: transform.rotation,
+                ghosts: [None; 3],
+            }),
+        };
 
-            if intersects_y && intersects_x && original.duplicate_xy.is_none() {
-                let offset_xy = Vec2::new(
-                    bounds.0.x * 2. * -transform.translation().x.signum(),
-                    bounds.0.y * 2. * -transform.translation().y.signum(),
-                );
-                let duplicate_xy = spawn_duplicate(
-                    &mut commands,
-                    transform,
-                    &mesh_handle,
-                    &material_handle,
-                    collider,
-                    Vec3::new(offset_xy.x, offset_xy.y, 0.0),
-                    entity,
-                    opt_coll_groups.cloned(),
-                );
-                original.duplicate_xy = Some(duplicate_xy);
-                debug!("Spawning duplicate xy for entity {:?}", entity);
+        if base_changed {
+            if let Some(base_collider) = &opt_base_collider {
+                let own_mass_properties = wrapped.own_mass_properties.unwrap_or_default();
+                commands
+                    .entity(entity)
+                    .insert(base_mass_properties(base_collider, own_mass_properties));
             }
+            wrapped.base_collider = opt_base_collider.clone();
+        }
+
+        // Children inherit the rotation and scale of the entity, the offsets are along the world
+        // axes
+        let to_local = |offset: &Vec2| {
+            transform.rotation.inverse().mul_vec3(offset.extend(0.)) / transform.scale
         };
 
-        if let Some(original) = opt_original.as_mut() {
-            spawn_duplicates(original);
-        } else {
-            let mut original = Original {
-                duplicate_x: None,
-                duplicate_y: None,
-                duplicate_xy: None,
+        if base_changed || wrapped.offsets != offsets || wrapped.rotation != transform.rotation {
+            if let (Some(collider), Some(base_collider)) =
+                (opt_collider.as_mut(), &opt_base_collider)
+            {
+                let local_offsets = offsets
+                    .iter()
+                    .flatten()
+                    .map(|offset| to_local(offset).xy())
+                    .collect::<Vec<_>>();
+                let wrapped_collider = compound_with_ghosts(base_collider, &local_offsets);
+                **collider = wrapped_collider.clone();
+                wrapped.wrapped_collider = Some(wrapped_collider);
+            }
+            wrapped.offsets = offsets;
+            wrapped.rotation = transform.rotation;
+        }
+
+        for (slot, opt_offset) in offsets.iter().enumerate() {
+            let Some(offset) = opt_offset else {
+                if let Some(ghost) = wrapped.ghosts[slot].take() {
+                    commands.entity(ghost).despawn_recursive();
+                }
+                continue;
             };
 
-            spawn_duplicates(&mut original);
+            let local_transform = Transform::from_translation(to_local(offset));
+
+            match wrapped.ghosts[slot].and_then(|ghost| ghost_query.get_mut(ghost).ok()) {
+                Some(mut ghost_transform) => {
+                    if *ghost_transform != local_transform {
+                        *ghost_transform = local_transform;
+                    }
+                }
+                None => {
+                    // The look of the entity is copied over by `sync_ghost_mirrors`
+                    let ghost = commands
+                        .spawn((
+                            WrapGhost { original: entity },
+                            GhostMirror { source: entity },
+                            SpatialBundle::from_transform(local_transform),
+                        ))
+                        .id();
+                    commands.entity(entity).add_child(ghost);
+                    wrapped.ghosts[slot] = Some(ghost);
+                    debug!("Spawning ghost {} for entity {:?}", slot, entity);
+                }
+            }
+        }
+
+        if let Some(wrapped) = new_wrapped {
+            commands.entity(entity).insert(wrapped);
+        }
+    }
+}
+
+/// Copies the meshes, sprites, visibility and local transforms of a wrapped entity and its
+/// children to its ghosts, mirroring new children and dropping the mirrors of despawned ones.
+fn sync_ghost_mirrors(
+    mut commands: Commands,
+    mut mirror_query: Query<(
+        Entity,
+        &GhostMirror,
+        Has<WrapGhost>,
+        Option<&Children>,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    source_query: Query<(&Transform, Option<&Visibility>, Option<&Children>), Without<GhostMirror>>,
+    source_mesh_query: Query<(&Mesh2dHandle, &Handle<ColorMaterial>), Without<GhostMirror>>,
+    source_sprite_query: Query<(&Sprite, &Handle<Image>), Without<GhostMirror>>,
+    mut mirror_mesh_query: Query<
+        (&mut Mesh2dHandle, &mut Handle<ColorMaterial>),
+        With<GhostMirror>,
+    >,
+    mut mirror_sprite_query: Query<(&mut Sprite, &mut Handle<Image>), With<GhostMirror>>,
+    mirror_source_query: Query<&GhostMirror>,
+) {
+    let insert_look = |commands: &mut Commands, mirror: Entity, source: Entity| {
+        if let Ok((mesh_handle, material_handle)) = source_mesh_query.get(source) {
+            commands
+                .entity(mirror)
+                .insert((mesh_handle.clone(), material_handle.clone()));
+        }
+        if let Ok((sprite, image_handle)) = source_sprite_query.get(source) {
+            commands
+                .entity(mirror)
+                .insert((sprite.clone(), image_handle.clone()));
+        }
+    };
 
-            commands.entity(entity).insert(original);
+    for (
+        mirror,
+        GhostMirror { source },
+        is_ghost,
+        opt_mirror_children,
+        mut transform,
+        mut visibility,
+    ) in &mut mirror_query
+    {
+        let Ok((source_transform, opt_source_visibility, opt_source_children)) =
+            source_query.get(*source)
+        else {
+            commands.entity(mirror).despawn_recursive();
+            continue;
         };
+
+        // The ghost itself is placed at the opposite edge and inherits the visibility of the
+        // entity
+        if !is_ghost {
+            if *transform != *source_transform {
+                *transform = *source_transform;
+            }
+            let source_visibility = opt_source_visibility.copied().unwrap_or_default();
+            if *visibility != source_visibility {
+                *visibility = source_visibility;
+            }
+        }
+
+        match (
+            source_mesh_query.get(*source),
+            mirror_mesh_query.get_mut(mirror),
+        ) {
+            (Ok((mesh_handle, material_handle)), Ok((mut mirror_mesh, mut mirror_material))) => {
+                if mirror_mesh.0 != mesh_handle.0 {
+                    *mirror_mesh = mesh_handle.clone();
+                }
+                if *mirror_material != *material_handle {
+                    *mirror_material = material_handle.clone();
+                }
+            }
+            (Ok((mesh_handle, material_handle)), Err(_)) => {
+                commands
+                    .entity(mirror)
+                    .insert((mesh_handle.clone(), material_handle.clone()));
+            }
+            _ => {}
+        }
+
+        match (
+            source_sprite_query.get(*source),
+            mirror_sprite_query.get_mut(mirror),
+        ) {
+            (Ok((sprite, image_handle)), Ok((mut mirror_sprite, mut mirror_image))) => {
+                *mirror_sprite = sprite.clone();
+                if *mirror_image != *image_handle {
+                    *mirror_image = image_handle.clone();
+                }
+            }
+            (Ok((sprite, image_handle)), Err(_)) => {
+                commands
+                    .entity(mirror)
+                    .insert((sprite.clone(), image_handle.clone()));
+            }
+            _ => {}
+        }
+
+        let Some(source_children) = opt_source_children else {
+            continue;
+        };
+        let mirrored = opt_mirror_children
+            .into_iter()
+            .flatten()
+            .filter_map(|child| mirror_source_query.get(*child).ok())
+            .map(|child_mirror| child_mirror.source)
+            .collect::<Vec<_>>();
+
+        // Sounds and other children without a transform are left out, as are the ghosts
+        for &child in source_children {
+            if mirrored.contains(&child) {
+                continue;
+            }
+            let Ok((child_transform, opt_child_visibility, _)) = source_query.get(child) else {
+                continue;
+            };
+
+            let child_mirror = commands
+                .spawn((
+                    GhostMirror { source: child },
+                    SpatialBundle {
+                        transform: *child_transform,
+                        visibility: opt_child_visibility.copied().unwrap_or_default(),
+                        ..default()
+                    },
+                ))
+                .id();
+            insert_look(&mut commands, child_mirror, child);
+            commands.entity(mirror).add_child(child_mirror);
+        }
     }
 }
 
-fn spawn_duplicate(
+/// Restores the collider and mass properties of the entity without its ghost shapes.
+fn unwrap_entity(
     commands: &mut Commands,
-    transform: &GlobalTransform,
-    mesh_handle: &Mesh2dHandle,
-    material_handle: &Handle<ColorMaterial>,
-    collider: &Collider,
-    offset: Vec3,
-    original: Entity,
-    opt_coll_groups: Option<CollisionGroups>,
-) -> Entity {
-    let mut spawn = commands.spawn((
-        Duplicate { original },
-        MaterialMesh2dBundle {
-            mesh: mesh_handle.clone(),
-            material: material_handle.clone(),
-            transform: Transform::from_translation(transform.translation() + offset),
-            ..Default::default()
-        },
-        collider.clone(),
-        ActiveEvents::COLLISION_EVENTS,
-        RigidBody::KinematicPositionBased,
-    ));
-    if let Some(coll_groups) = opt_coll_groups {
-        spawn.insert(coll_groups);
+    entity: Entity,
+    wrapped: &Wrapped,
+    opt_collider: Option<Mut<Collider>>,
+) {
+    if let (Some(mut collider), Some(base_collider)) = (opt_collider, &wrapped.base_collider) {
+        *collider = base_collider.clone();
+    }
+    let mut entity_commands = commands.entity(entity);
+    entity_commands.remove::<Wrapped>();
+    match wrapped.own_mass_properties {
+        Some(mass_properties) => entity_commands.insert(mass_properties),
+        None => entity_commands.remove::<ColliderMassProperties>(),
+    };
+}
+
+/// Despawns the ghosts of entities that stopped wrapping or were despawned without their
+/// children.
+fn duplicable_removed(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Duplicable>,
+    mut wrapped_query: Query<(&Wrapped, Option<&mut Collider>), Without<Duplicable>>,
+    ghost_query: Query<(Entity, &WrapGhost)>,
+) {
+    for entity in removed.read() {
+        if let Ok((wrapped, opt_collider)) = wrapped_query.get_mut(entity) {
+            unwrap_entity(&mut commands, entity, wrapped, opt_collider);
+        }
+
+        for (ghost_entity, ghost) in &ghost_query {
+            if ghost.original == entity {
+                debug!("Removing ghost entity {:?}", ghost_entity);
+                commands.entity(ghost_entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Drops the ghosts of all entities once the edges stop wrapping.
+fn boundary_mode_changed(
+    mut commands: Commands,
+    boundary_mode: Res<BoundaryMode>,
+    mut wrapped_query: Query<(Entity, &Wrapped, Option<&mut Collider>)>,
+    ghost_query: Query<Entity, With<WrapGhost>>,
+) {
+    if *boundary_mode == BoundaryMode::Wrap {
+        return;
+    }
+
+    for (entity, wrapped, opt_collider) in &mut wrapped_query {
+        unwrap_entity(&mut commands, entity, wrapped, opt_collider);
+    }
+
+    for ghost_entity in &ghost_query {
+        commands.entity(ghost_entity).despawn_recursive();
+    }
+}
+
+fn sync_boundary_walls(
+    mut commands: Commands,
+    boundary_mode: Res<BoundaryMode>,
+    bounds: Res<Bounds>,
+    wall_query: Query<Entity, With<BoundaryWall>>,
+) {
+    for wall_entity in &wall_query {
+        commands.entity(wall_entity).despawn_recursive();
+    }
+
+    if *boundary_mode != BoundaryMode::Walls {
+        return;
+    }
+
+    let half_thickness = WALL_THICKNESS / 2.;
+    let vertical_half_size = Vec2::new(half_thickness, bounds.0.y + WALL_THICKNESS);
+    let horizontal_half_size = Vec2::new(bounds.0.x + WALL_THICKNESS, half_thickness);
+    let walls = [
+        (Vec2::X * (bounds.0.x + half_thickness), vertical_half_size),
+        (
+            Vec2::NEG_X * (bounds.0.x + half_thickness),
+            vertical_half_size,
+        ),
+        (
+            Vec2::Y * (bounds.0.y + half_thickness),
+            horizontal_half_size,
+        ),
+        (
+            Vec2::NEG_Y * (bounds.0.y + half_thickness),
+            horizontal_half_size,
+        ),
+    ];
+
+    for (position, half_size) in walls {
+        commands.spawn((
+            Name::new("Boundary wall"),
+            BoundaryWall,
+            RigidBody::Fixed,
+            Collider::cuboid(half_size.x, half_size.y),
+            Restitution::coefficient(WALL_RESTITUTION),
+            Friction::coefficient(0.),
+            CollisionGroups::new(WALL_GROUP, Group::ALL),
+            TransformBundle::from_transform(Transform::from_translation(position.extend(0.))),
+        ));
+    }
+}
+
+fn leave_void(
+    mut commands: Commands,
+    bounds: Res<Bounds>,
+    duplicable_query: Query<(Entity, &Transform, Has<DamagedByVoid>), With<Duplicable>>,
+    mut left_bounds_events: EventWriter<LeftBoundsEvent>,
+) {
+    for (entity, transform, damaged_by_void) in &duplicable_query {
+        if transform.translation.xy().abs().cmple(bounds.0).all() {
+            continue;
+        }
+
+        left_bounds_events.send(LeftBoundsEvent { entity });
+
+        if !damaged_by_void {
+            debug!("Despawning {:?} outside of the bounds", entity);
+            commands.entity(entity).despawn_recursive();
+        }
     }
-    spawn.id()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -255,25 +819,12 @@ struct EdgePositions {
     right: Position,
 }
 
-fn edge_positions(
-    global_transform: &GlobalTransform,
-    collider: &Collider,
-    bounds: &Bounds,
-) -> EdgePositions {
-    let (_, rot, pos) = global_transform.to_scale_rotation_translation();
-    let pos = pos.xy();
-    let rot = rot.angle_between(Quat::IDENTITY);
-
-    let aabb = collider
-        .as_trimesh()
-        .unwrap()
-        .raw
-        .aabb(&Isometry2::new(Vector2::new(pos.x, pos.y), rot));
-
-    let max_y = pos.y + aabb.half_extents().y;
-    let min_y = pos.y - aabb.half_extents().y;
-    let max_x = pos.x + aabb.half_extents().x;
-    let min_x = pos.x - aabb.half_extents().x;
+/// Where an axis aligned box around `position` is relative to every edge of the bounds.
+fn edge_positions_of_box(position: Vec2, half_extents: Vec2, bounds: &Bounds) -> EdgePositions {
+    let max_y = position.y + half_extents.y;
+    let min_y = position.y - half_extents.y;
+    let max_x = position.x + half_extents.x;
+    let min_x = position.x - half_extents.x;
 
     let determine_position = |min_edge, max_edge, bound| {
         if min_edge > bound {
@@ -298,172 +849,131 @@ fn edge_positions(
     }
 }
 
-fn sync_duplicate_transforms(
-    duplicable_query: Query<(&GlobalTransform, &Original), Changed<GlobalTransform>>,
-    mut transform_query: Query<&mut Transform>,
-    bounds: Res<Bounds>,
-) {
-    for (original_global_transform, original) in &duplicable_query {
-        let original_pos = original_global_transform.translation();
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::{event::Events, world::World},
+        hierarchy::{BuildWorldChildren, HierarchyPlugin, Parent},
+        math::primitives::RegularPolygon,
+        render::mesh::Mesh,
+        sprite::SpriteBundle,
+        transform::{components::GlobalTransform, TransformPlugin},
+    };
+    use proptest::prelude::*;
 
-        let mut update_duplicate_transform = |entity: Entity, duplicate_offset: Vec2| {
-            let mut duplicate_transform = transform_query.get_mut(entity).unwrap();
+    use crate::utils::mesh_to_collider;
 
-            duplicate_transform.translation =
-                original_pos + Vec3::new(duplicate_offset.x, duplicate_offset.y, 0.);
+    use super::*;
 
-            duplicate_transform.rotation =
-                original_global_transform.to_scale_rotation_translation().1;
-        };
+    const TEST_BOUNDS: f32 = 500.;
+    const TEST_TIME_STEP: f32 = 1. / 60.;
 
-        if let Some(duplicate) = original.duplicate_x {
-            update_duplicate_transform(
-                duplicate,
-                Vec2::new(bounds.0.x * 2. * -original_pos.x.signum(), 0.),
-            );
-        }
+    /// Headless app running the edge wrap systems and transform propagation, without physics.
+    fn create_test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((TransformPlugin, HierarchyPlugin, EdgeWrapPlugin))
+            .insert_resource(create_test_bounds(TEST_BOUNDS));
+        app
+    }
 
-        if let Some(duplicate) = original.duplicate_y {
-            update_duplicate_transform(
-                duplicate,
-                Vec2::new(0., bounds.0.y * 2. * -original_pos.y.signum()),
-            );
-        }
+    fn spawn_test_body(world: &mut World, position: Vec2, rotation: f32) -> Entity {
+        world
+            .spawn((
+                Duplicable,
+                create_test_collider(),
+                Mesh2dHandle::default(),
+                Handle::<ColorMaterial>::default(),
+                Transform::from_translation(position.extend(0.))
+                    .with_rotation(Quat::from_rotation_z(rotation)),
+                GlobalTransform::default(),
+            ))
+            .id()
+    }
 
-        if let Some(duplicate) = original.duplicate_xy {
-            update_duplicate_transform(
-                duplicate,
-                Vec2::new(
-                    bounds.0.x * 2. * -original_pos.x.signum(),
-                    bounds.0.y * 2. * -original_pos.y.signum(),
-                ),
-            );
-        }
+    fn shape_count(collider: &Collider) -> usize {
+        collider
+            .raw
+            .as_compound()
+            .map_or(1, |compound| compound.shapes().len())
     }
-}
 
-fn teleport_original_to_swap(
-    mut commands: Commands,
-    mut original_query: Query<
-        (
-            Entity,
-            &GlobalTransform,
-            &mut Transform,
-            &Collider,
-            &Original,
-        ),
-        Changed<GlobalTransform>,
-    >,
-    bounds: Res<Bounds>,
-) {
-    for (original_entity, global_transform, mut transform, collider, original) in
-        &mut original_query
-    {
-        let mut remove_original_and_duplicates = || {
-            commands.entity(original_entity).remove::<Original>();
-            if let Some(duplicate) = original.duplicate_x {
-                commands.entity(duplicate).despawn_recursive();
-            }
-            if let Some(duplicate) = original.duplicate_y {
-                commands.entity(duplicate).despawn_recursive();
-            }
-            if let Some(duplicate) = original.duplicate_xy {
-                commands.entity(duplicate).despawn_recursive();
+    /// Checks the invariants of a wrapped body: a single original inside the bounds, ghosts
+    /// shifted by the size of the bounds towards the opposite edges and a ghost shape for every
+    /// ghost.
+    fn assert_wrap_invariants(world: &mut World, original: Entity) {
+        let bounds = create_test_bounds(TEST_BOUNDS);
+
+        let originals = world
+            .query_filtered::<Entity, With<Duplicable>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+        assert_eq!(originals, vec![original]);
+
+        let original_position = world
+            .get::<GlobalTransform>(original)
+            .unwrap()
+            .translation()
+            .xy();
+        assert!(
+            original_position.abs().cmple(bounds.0).all(),
+            "Original at {original_position} left the bounds"
+        );
+
+        let ghosts = world
+            .query::<(&WrapGhost, &Parent, &GlobalTransform)>()
+            .iter(world)
+            .map(|(ghost, parent, transform)| {
+                (ghost.original, parent.get(), transform.translation().xy())
+            })
+            .collect::<Vec<_>>();
+        for &(ghost_original, parent, position) in &ghosts {
+            assert_eq!(ghost_original, original);
+            assert_eq!(parent, original);
+
+            let offset = position - original_position;
+            let expected = -bounds.size() * original_position.signum();
+            for (offset, expected) in [(offset.x, expected.x), (offset.y, expected.y)] {
+                assert!(
+                    offset.abs() < 0.1 || (offset - expected).abs() < 0.1,
+                    "Ghost offset {offset} is neither 0 nor {expected}"
+                );
             }
-
-            debug!("Removing duplicates of entity {:?}", original_entity);
-        };
-
-        let edge_positions = edge_positions(global_transform, collider, &bounds);
-
-        // Delete duplicates if the original is inside the bounds
-        if edge_positions.top == Position::Inside
-            && edge_positions.bottom == Position::Inside
-            && edge_positions.left == Position::Inside
-            && edge_positions.right == Position::Inside
-        {
-            remove_original_and_duplicates();
-        }
-
-        let original_pos = global_transform.translation().xy();
-
-        let mut teleport_to_duplicate = |offset: Vec2| {
-            transform.translation += Vec3::new(offset.x, offset.y, 0.0);
-
-            debug!(
-                "Teleporting entity {:?} to {:?}",
-                original_entity, transform.translation
-            );
-
-            remove_original_and_duplicates();
-        };
-
-        // Teleport the original to the duplicate on the opposite side if it's outside the bounds
-        if (edge_positions.top == Position::Outside || edge_positions.bottom == Position::Outside)
-            && (edge_positions.left == Position::Outside
-                || edge_positions.right == Position::Outside)
-        {
-            let offset = Vec2::new(
-                bounds.0.x * 2. * -original_pos.x.signum(),
-                bounds.0.y * 2. * -original_pos.y.signum(),
-            );
-
-            teleport_to_duplicate(offset);
+            assert!(offset.length() > 0.1, "Ghost on top of its original");
         }
 
-        if (edge_positions.top == Position::Outside || edge_positions.bottom == Position::Outside)
-            && edge_positions.left == Position::Inside
-            && edge_positions.right == Position::Inside
-        {
-            let offset = Vec2::new(0., bounds.0.y * 2. * -original_pos.y.signum());
-
-            teleport_to_duplicate(offset);
-        }
-
-        if (edge_positions.left == Position::Outside || edge_positions.right == Position::Outside)
-            && edge_positions.top == Position::Inside
-            && edge_positions.bottom == Position::Inside
-        {
-            let offset = Vec2::new(bounds.0.x * 2. * -original_pos.x.signum(), 0.);
-
-            teleport_to_duplicate(offset);
-        }
-    }
-}
-
-fn duplicable_removed(
-    mut commands: Commands,
-    mut removed: RemovedComponents<Duplicable>,
-    duplicate_query: Query<(Entity, &Duplicate)>,
-) {
-    for entity in removed.read() {
-        for (duplicate_entity, duplicate) in &duplicate_query {
-            if duplicate.original == entity {
-                debug!("Removing duplicate entity {:?}", duplicate_entity);
-                commands.entity(duplicate_entity).despawn_recursive();
+        let collider = world.get::<Collider>(original).unwrap();
+        match world.get::<Wrapped>(original) {
+            Some(wrapped) => {
+                let ghost_count = wrapped.offsets.iter().flatten().count();
+                assert_eq!(ghosts.len(), ghost_count);
+                assert_eq!(
+                    shape_count(collider),
+                    shape_count(wrapped.base_collider().unwrap()) * (1 + ghost_count)
+                );
+            }
+            None => {
+                assert!(ghosts.is_empty());
+                assert_eq!(shape_count(collider), 1);
             }
         }
     }
-}
-
-pub fn get_original_entities(
-    duplicate_query: &Query<&Duplicate, ()>,
-    entity_a: &Entity,
-) -> (Entity, Option<Entity>) {
-    if let Ok(Duplicate { original }) = duplicate_query.get(*entity_a) {
-        (*original, Some(*entity_a))
-    } else {
-        (*entity_a, None)
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use bevy::{math::primitives::RegularPolygon, render::mesh::Mesh};
+    /// Moves the body in straight lines, like physics would, checking the invariants every frame.
+    fn run_wrap_scenario(start: Vec2, velocity: Vec2, angular_velocity: f32, frames: usize) {
+        let mut app = create_test_app();
+        let original = spawn_test_body(&mut app.world, start, 0.);
+        app.update();
+        assert_wrap_invariants(&mut app.world, original);
 
-    use crate::utils::mesh_to_collider;
+        for _ in 0..frames {
+            let mut transform = app.world.get_mut::<Transform>(original).unwrap();
+            transform.translation += (velocity * TEST_TIME_STEP).extend(0.);
+            transform.rotate_z(angular_velocity * TEST_TIME_STEP);
 
-    use super::*;
+            app.update();
+            assert_wrap_invariants(&mut app.world, original);
+        }
+    }
 
     fn create_test_collider() -> Collider {
         let shape = RegularPolygon::new(10., 3);
@@ -482,6 +992,18 @@ mod tests {
         Bounds((distance, distance).into())
     }
 
+    fn edge_positions(
+        global_transform: &GlobalTransform,
+        collider: &Collider,
+        bounds: &Bounds,
+    ) -> EdgePositions {
+        let transform = global_transform.compute_transform();
+        let half_extents = wrap_half_extents(&transform, Some(collider), None)
+            .expect("Extent of a collider is always known");
+
+        edge_positions_of_box(transform.translation.xy(), half_extents, bounds)
+    }
+
     #[test]
     fn test_edge_positions_inside() {
         let bounds = create_test_bounds(500.0);
@@ -510,6 +1032,43 @@ mod tests {
         assert_eq!(positions.right, Position::Outside);
     }
 
+    #[test]
+    fn test_wrapped_displacement_inside() {
+        let bounds = create_test_bounds(500.0);
+
+        let displacement = bounds.wrapped_displacement(Vec2::new(-100., 0.), Vec2::new(100., 50.));
+
+        assert_eq!(displacement, Vec2::new(200., 50.));
+    }
+
+    #[test]
+    fn test_wrapped_displacement_across_edges() {
+        let bounds = create_test_bounds(500.0);
+
+        let displacement =
+            bounds.wrapped_displacement(Vec2::new(-450., 450.), Vec2::new(450., -450.));
+
+        assert_eq!(displacement, Vec2::new(-100., 100.));
+        assert_eq!(
+            bounds.wrapped_distance(Vec2::new(-450., 450.), Vec2::new(450., -450.)),
+            displacement.length()
+        );
+    }
+
+    #[test]
+    fn test_wrap_position() {
+        let bounds = create_test_bounds(500.0);
+
+        assert_eq!(
+            bounds.wrap_position(Vec2::new(600., -700.)),
+            Vec2::new(-400., 300.)
+        );
+        assert_eq!(
+            bounds.wrap_position(Vec2::new(100., 100.)),
+            Vec2::new(100., 100.)
+        );
+    }
+
     #[test]
     fn test_edge_positions_intersecting() {
         let bounds = create_test_bounds(500.0);
@@ -523,4 +1082,320 @@ mod tests {
         assert_eq!(positions.left, Position::Inside);
         assert_eq!(positions.right, Position::Intersecting);
     }
+
+    #[test]
+    fn test_ghost_offsets_at_corner() {
+        let bounds = create_test_bounds(500.0);
+        let collider = create_test_collider();
+        let transform = Transform::from_xyz(495., -495., 0.);
+
+        let half_extents = wrap_half_extents(&transform, Some(&collider), None).unwrap();
+
+        let offsets = ghost_offsets(transform.translation.xy(), half_extents, &bounds);
+
+        assert_eq!(
+            offsets,
+            [
+                Some(Vec2::new(-1000., 0.)),
+                Some(Vec2::new(0., 1000.)),
+                Some(Vec2::new(-1000., 1000.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_base_mass_properties_keep_density() {
+        let collider = create_test_collider();
+        let area = collider.raw.mass_properties(1.).mass();
+
+        let ColliderMassProperties::MassProperties(mass_properties) =
+            base_mass_properties(&collider, ColliderMassProperties::Density(3.))
+        else {
+            panic!("Base mass properties should be explicit");
+        };
+
+        assert!((mass_properties.mass - area * 3.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_ghost_offsets_inside() {
+        let bounds = create_test_bounds(500.0);
+        let collider = create_test_collider();
+        let transform = Transform::from_xyz(100., 200., 0.);
+
+        let half_extents = wrap_half_extents(&transform, Some(&collider), None).unwrap();
+
+        let offsets = ghost_offsets(transform.translation.xy(), half_extents, &bounds);
+
+        assert_eq!(offsets, [None; 3]);
+    }
+
+    #[test]
+    fn test_wrap_across_every_edge_and_corner() {
+        let directions = [
+            Vec2::X,
+            Vec2::NEG_X,
+            Vec2::Y,
+            Vec2::NEG_Y,
+            Vec2::ONE,
+            Vec2::new(1., -1.),
+            Vec2::new(-1., 1.),
+            Vec2::NEG_ONE,
+        ];
+
+        for direction in directions {
+            let start = direction * (TEST_BOUNDS - 30.);
+            run_wrap_scenario(start, direction * 300., 2., 30);
+        }
+    }
+
+    #[test]
+    fn test_ghosts_removed_with_original() {
+        let mut app = create_test_app();
+        let original = spawn_test_body(&mut app.world, Vec2::splat(TEST_BOUNDS - 2.), 0.);
+        app.update();
+        app.update();
+        assert_eq!(app.world.query::<&WrapGhost>().iter(&app.world).count(), 3);
+
+        // Gameplay code despawns without the children
+        app.world.despawn(original);
+        app.update();
+
+        assert_eq!(app.world.query::<&WrapGhost>().iter(&app.world).count(), 0);
+    }
+
+    #[test]
+    fn test_wrapping_stops_when_duplicable_removed() {
+        let mut app = create_test_app();
+        let original = spawn_test_body(&mut app.world, Vec2::new(TEST_BOUNDS - 2., 0.), 0.);
+        app.update();
+        app.update();
+
+        app.world.entity_mut(original).remove::<Duplicable>();
+        app.update();
+
+        assert_eq!(app.world.query::<&WrapGhost>().iter(&app.world).count(), 0);
+        assert!(app.world.get::<Wrapped>(original).is_none());
+        assert!(app.world.get::<ColliderMassProperties>(original).is_none());
+        assert_eq!(shape_count(app.world.get::<Collider>(original).unwrap()), 1);
+    }
+
+    #[test]
+    fn test_wrapping_keeps_the_density_of_the_body() {
+        let mut app = create_test_app();
+        let original = spawn_test_body(&mut app.world, Vec2::new(TEST_BOUNDS - 2., 0.), 0.);
+        app.world
+            .entity_mut(original)
+            .insert(ColliderMassProperties::Density(3.));
+        app.update();
+        app.update();
+
+        let area = create_test_collider().raw.mass_properties(1.).mass();
+        let Some(ColliderMassProperties::MassProperties(mass_properties)) =
+            app.world.get::<ColliderMassProperties>(original)
+        else {
+            panic!("Wrapped body should have the mass properties of its base collider");
+        };
+        assert!((mass_properties.mass - area * 3.).abs() < 1e-3);
+
+        app.world.entity_mut(original).remove::<Duplicable>();
+        app.update();
+
+        assert_eq!(
+            app.world.get::<ColliderMassProperties>(original),
+            Some(&ColliderMassProperties::Density(3.))
+        );
+    }
+
+    #[test]
+    fn test_ghosts_removed_when_leaving_wrap_mode() {
+        let mut app = create_test_app();
+        let original = spawn_test_body(&mut app.world, Vec2::splat(TEST_BOUNDS - 2.), 0.);
+        app.update();
+        app.update();
+        assert_eq!(app.world.query::<&WrapGhost>().iter(&app.world).count(), 3);
+
+        app.insert_resource(BoundaryMode::Walls);
+        app.update();
+
+        assert_eq!(app.world.query::<&WrapGhost>().iter(&app.world).count(), 0);
+        assert!(app.world.get::<Wrapped>(original).is_none());
+        assert_eq!(shape_count(app.world.get::<Collider>(original).unwrap()), 1);
+    }
+
+    #[test]
+    fn test_walls_follow_bounds() {
+        let mut app = create_test_app();
+        app.insert_resource(BoundaryMode::Walls);
+        app.update();
+        assert_eq!(
+            app.world.query::<&BoundaryWall>().iter(&app.world).count(),
+            4
+        );
+
+        app.insert_resource(create_test_bounds(TEST_BOUNDS * 2.));
+        app.update();
+
+        let mut wall_query = app.world.query_filtered::<&Transform, With<BoundaryWall>>();
+        let wall_positions = wall_query
+            .iter(&app.world)
+            .map(|transform| transform.translation.xy().abs().max_element())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            wall_positions,
+            vec![TEST_BOUNDS * 2. + WALL_THICKNESS / 2.; 4]
+        );
+
+        app.insert_resource(BoundaryMode::Wrap);
+        app.update();
+        assert_eq!(
+            app.world.query::<&BoundaryWall>().iter(&app.world).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_void_despawns_or_damages() {
+        let mut app = create_test_app();
+        app.insert_resource(BoundaryMode::Void);
+        let lost = spawn_test_body(&mut app.world, Vec2::new(TEST_BOUNDS + 1., 0.), 0.);
+        let damaged = spawn_test_body(&mut app.world, Vec2::new(0., -TEST_BOUNDS - 1.), 0.);
+        let inside = spawn_test_body(&mut app.world, Vec2::new(TEST_BOUNDS - 1., 0.), 0.);
+        app.world.entity_mut(damaged).insert(DamagedByVoid);
+        app.update();
+
+        assert!(app.world.get_entity(lost).is_none());
+        assert!(app.world.get_entity(damaged).is_some());
+        assert!(app.world.get::<Wrapped>(inside).is_none());
+        assert_eq!(
+            app.world.get::<Transform>(inside).unwrap().translation.x,
+            TEST_BOUNDS - 1.
+        );
+
+        let events = app.world.resource::<Events<LeftBoundsEvent>>();
+        let mut left_entities = events
+            .get_reader()
+            .read(events)
+            .map(|event| event.entity)
+            .collect::<Vec<_>>();
+        left_entities.sort();
+        let mut expected = vec![lost, damaged];
+        expected.sort();
+        assert_eq!(left_entities, expected);
+    }
+
+    #[test]
+    fn test_replaced_collider_becomes_base() {
+        let mut app = create_test_app();
+        let original = spawn_test_body(&mut app.world, Vec2::new(TEST_BOUNDS - 2., 0.), 0.);
+        app.update();
+
+        // E.g. a damaged hull
+        let replacement = mesh_to_collider(&Mesh::from(RegularPolygon::new(5., 4))).unwrap();
+        app.world.entity_mut(original).insert(replacement.clone());
+        app.update();
+
+        let wrapped = app.world.get::<Wrapped>(original).unwrap();
+        assert!(Arc::ptr_eq(
+            &wrapped.base_collider().unwrap().raw.0,
+            &replacement.raw.0
+        ));
+        assert_wrap_invariants(&mut app.world, original);
+    }
+
+    #[test]
+    fn test_wrap_extent_of_rotated_visual() {
+        let transform =
+            Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+                .with_scale(Vec3::splat(2.));
+
+        let half_extents =
+            wrap_half_extents(&transform, None, Some(&WrapExtent(Vec2::new(10., 5.)))).unwrap();
+
+        assert!((half_extents - Vec2::new(10., 20.)).length() < 0.001);
+        assert!(wrap_half_extents(&transform, None, None).is_none());
+    }
+
+    #[test]
+    fn test_visual_ghosts_mirror_sprites_and_children() {
+        let mut app = create_test_app();
+        let original = app
+            .world
+            .spawn((
+                Duplicable,
+                WrapExtent(Vec2::splat(10.)),
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::RED,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(TEST_BOUNDS - 2., 0., 0.),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent.spawn(SpriteBundle {
+                    transform: Transform::from_xyz(0., 5., 0.),
+                    ..default()
+                });
+            })
+            .id();
+        app.update();
+        app.update();
+
+        let ghost = app
+            .world
+            .query_filtered::<Entity, With<WrapGhost>>()
+            .single(&app.world);
+        assert_eq!(app.world.get::<Sprite>(ghost).unwrap().color, Color::RED);
+        assert_eq!(app.world.get::<Parent>(ghost).unwrap().get(), original);
+        assert_eq!(app.world.get::<Children>(ghost).unwrap().len(), 1);
+        assert_eq!(
+            app.world
+                .get::<GlobalTransform>(ghost)
+                .unwrap()
+                .translation()
+                .xy(),
+            Vec2::new(-TEST_BOUNDS - 2., 0.)
+        );
+        assert!(app.world.get::<Collider>(original).is_none());
+
+        // Mirrors follow the look of their source
+        app.world.get_mut::<Sprite>(original).unwrap().color = Color::BLUE;
+        app.update();
+        assert_eq!(app.world.get::<Sprite>(ghost).unwrap().color, Color::BLUE);
+
+        // Moving back inside drops the ghost and its mirrored children
+        app.world
+            .get_mut::<Transform>(original)
+            .unwrap()
+            .translation
+            .x = 0.;
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world.query::<&GhostMirror>().iter(&app.world).count(),
+            0
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_wrap_invariants_hold(
+            x in -TEST_BOUNDS..TEST_BOUNDS,
+            y in -TEST_BOUNDS..TEST_BOUNDS,
+            angle in 0.0..std::f32::consts::TAU,
+            speed in 0.0..1500.0f32,
+            angular_velocity in -10.0..10.0f32,
+        ) {
+            run_wrap_scenario(
+                Vec2::new(x, y),
+                Vec2::from_angle(angle) * speed,
+                angular_velocity,
+                120,
+            );
+        }
+    }
 }
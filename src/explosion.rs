@@ -1,6 +1,11 @@
 use bevy::{ecs::component::Component, time::Timer};
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 
+use crate::{
+    edge_wrap::{Duplicable, WrapExtent},
+    particle::{spawn_particle_burst, ParticleBurst, EXPLOSION_BURST},
+};
+
 pub struct ExplosionPlugin;
 
 impl Plugin for ExplosionPlugin {
@@ -18,6 +23,7 @@ impl Plugin for ExplosionPlugin {
 pub struct ExplosionSet;
 
 const EXPLOSION_DURATION: f32 = 0.25;
+const EXPLOSION_PARTICLES_PER_RADIUS: f32 = 2.;
 
 #[derive(Component)]
 pub struct Explosion {
@@ -79,8 +85,22 @@ fn spawn_explosion(
     transform: &Transform,
     radius: f32,
 ) {
+    spawn_particle_burst(
+        commands,
+        &mut rand::thread_rng(),
+        ParticleBurst {
+            settings: EXPLOSION_BURST,
+            count: (radius * EXPLOSION_PARTICLES_PER_RADIUS).ceil() as usize,
+            position: transform.translation.xy(),
+            velocity: Vec2::ZERO,
+            direction: Vec2::Y,
+            scale: radius,
+        },
+    );
     commands.spawn((
         Explosion::default(),
+        Duplicable,
+        WrapExtent(Vec2::splat(radius)),
         MaterialMesh2dBundle {
             transform: *transform,
             mesh: meshes.add(Circle::new(radius)).into(),
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::player::PlayerId;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default, States)]
 pub enum GameState {
     #[default]
@@ -12,4 +14,38 @@ pub enum GameState {
 pub enum GameResult {
     Win,
     Lose,
+    /// The player won the versus match.
+    Winner(PlayerId),
+    /// The peer of an online game stopped responding.
+    Disconnected,
+    /// The simulation of the peers of an online game diverged.
+    Desynced,
+}
+
+/// Who plays, chosen on the start screen.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Solo,
+    /// Two local players fighting the asteroids together.
+    Coop,
+    /// Two local players shooting at each other between the asteroids, over several rounds.
+    Versus,
+}
+
+impl GameMode {
+    pub fn player_count(self) -> usize {
+        match self {
+            GameMode::Solo => 1,
+            GameMode::Coop | GameMode::Versus => 2,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Solo => GameMode::Coop,
+            GameMode::Coop => GameMode::Versus,
+            GameMode::Versus => GameMode::Solo,
+        }
+    }
 }
@@ -6,11 +6,17 @@ use bevy::{
         query::With,
         schedule::{
             common_conditions::{in_state, not, resource_exists_and_equals},
-            IntoSystemConfigs, SystemSet,
+            IntoSystemConfigs, IntoSystemSetConfigs, SystemSet,
         },
         system::{Commands, Query, Res, Resource},
     },
-    input::{mouse::MouseButton, touch::Touches, ButtonInput},
+    input::{
+        gamepad::{GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads},
+        keyboard::KeyCode,
+        mouse::MouseButton,
+        touch::Touches,
+        Axis, ButtonInput,
+    },
     math::{Quat, Vec2},
     prelude::{OnExit, ResMut},
     render::camera::Camera,
@@ -20,6 +26,10 @@ use bevy::{
 };
 
 use crate::{
+    missile::FireMissileEvent,
+    net::SimulationSet,
+    player::PlayerId,
+    playfield::{window_to_world, MainCamera},
     ship::{Ship, Throttling},
     turret::FireEvent,
     utils::cleanup_resource,
@@ -30,76 +40,127 @@ pub struct PlayerInputPlugin;
 
 impl Plugin for PlayerInputPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
+        app.init_resource::<PlayerInputs>()
+            .configure_sets(Update, SampleInputSet.in_set(PlayerInputSet))
+            .add_systems(
+                Update,
                 (
-                    player_ship_mouse_input.run_if(resource_exists_and_equals(InputMode::Mouse)),
-                    (touch_shoot_timer_update, player_ship_touch_input)
-                        .chain()
-                        .run_if(resource_exists_and_equals(InputMode::Touch)),
+                    clear_player_inputs,
+                    (
+                        player_ship_mouse_input
+                            .run_if(resource_exists_and_equals(InputMode::Mouse)),
+                        (touch_shoot_timer_update, player_ship_touch_input)
+                            .chain()
+                            .run_if(resource_exists_and_equals(InputMode::Touch)),
+                        player_ship_keyboard_input,
+                    ),
                 )
-                    .run_if(in_state(GameState::Playing)),
-                stop_player_throttling.run_if(not(in_state(GameState::Playing))),
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(SampleInputSet),
             )
-                .in_set(PlayerInputSet),
-        )
-        .add_systems(
-            OnExit(GameState::Playing),
-            cleanup_resource::<TouchShootTimer>,
-        );
+            .add_systems(
+                Update,
+                (
+                    apply_player_inputs
+                        .after(SampleInputSet)
+                        .in_set(SimulationSet)
+                        .run_if(in_state(GameState::Playing)),
+                    stop_player_throttling.run_if(not(in_state(GameState::Playing))),
+                )
+                    .in_set(PlayerInputSet),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                cleanup_resource::<TouchShootTimer>,
+            );
     }
 }
 
 #[derive(SystemSet, PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub struct PlayerInputSet;
 
+/// Reads the input devices into [`PlayerInputs`], before they are applied to the ships.
+#[derive(SystemSet, PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct SampleInputSet;
+
+/// Radians per second the keyboard turns the ship.
+const KEYBOARD_TURN_SPEED: f32 = 4.;
+/// Stick deflection below which the gamepad leaves the heading of the ship alone.
+const GAMEPAD_AIM_DEAD_ZONE: f32 = 0.3;
+
 #[derive(Resource, PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum InputMode {
     Mouse,
     Touch,
 }
 
+/// How a player turns their ship.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Steering {
+    /// Keep the current heading.
+    #[default]
+    Keep,
+    /// Point the ship at a position in the world, like the mouse cursor.
+    Towards(Vec2),
+    /// Point the ship in a direction, like the gamepad stick.
+    Heading(Vec2),
+    /// Turn at a fraction of the keyboard turn speed, counterclockwise when positive.
+    Turn(f32),
+}
+
+/// What a player does with their ship this frame, independent of the device they play with.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PlayerInput {
+    pub steering: Steering,
+    pub throttle: bool,
+    pub fire: bool,
+    pub missile: bool,
+}
+
+/// Input of every player for the current frame.
+///
+/// Player one plays with the mouse or touch, player two with the keyboard or a gamepad. In an
+/// online game the inputs are replaced by the ones agreed on with the peer before they are
+/// applied.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerInputs(pub [PlayerInput; 2]);
+
+impl PlayerInputs {
+    pub fn get(&self, id: PlayerId) -> &PlayerInput {
+        &self.0[id as usize]
+    }
+
+    pub fn get_mut(&mut self, id: PlayerId) -> &mut PlayerInput {
+        &mut self.0[id as usize]
+    }
+}
+
+fn clear_player_inputs(mut inputs: ResMut<PlayerInputs>) {
+    *inputs = PlayerInputs::default();
+}
+
 pub fn player_ship_mouse_input(
-    mut commands: Commands,
     mouse_input: Res<ButtonInput<MouseButton>>,
-    mut player_query: Query<(Entity, &GlobalTransform, &mut Transform), (With<Player>, With<Ship>)>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    mut fire_projectile_event_writer: EventWriter<FireEvent>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut inputs: ResMut<PlayerInputs>,
 ) {
     let (camera, camera_global_transform) = camera_query.single();
     let Some(cursor_pos) = primary_window
         .single()
         .cursor_position()
-        .and_then(|cp| camera.viewport_to_world_2d(camera_global_transform, cp))
+        .and_then(|cp| window_to_world(camera, camera_global_transform, cp))
     else {
         return;
     };
 
-    for (player_entity, player_global_transform, mut player_transform) in player_query.iter_mut() {
-        let throttle = mouse_input.pressed(MouseButton::Left);
-
-        if throttle {
-            commands.entity(player_entity).insert(Throttling);
-        } else {
-            commands.entity(player_entity).remove::<Throttling>();
-        }
-
-        let direction = cursor_pos - player_global_transform.translation().truncate();
-        let angle = direction.y.atan2(direction.x);
-        let target_rotation = Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2);
-
-        player_transform.rotation = target_rotation;
-
-        let fire_projectile = mouse_input.pressed(MouseButton::Right);
-
-        if fire_projectile {
-            fire_projectile_event_writer.send(FireEvent {
-                turret_entity: player_entity,
-            });
-        }
-    }
+    *inputs.get_mut(PlayerId::One) = PlayerInput {
+        steering: Steering::Towards(cursor_pos),
+        throttle: mouse_input.pressed(MouseButton::Left),
+        fire: mouse_input.pressed(MouseButton::Right),
+        missile: mouse_input.just_pressed(MouseButton::Middle),
+    };
 }
 
 #[derive(Resource)]
@@ -132,48 +193,138 @@ fn touch_shoot_timer_update(
 fn player_ship_touch_input(
     mut commands: Commands,
     touches: Res<Touches>,
-    mut player_query: Query<(Entity, &GlobalTransform, &mut Transform), (With<Player>, With<Ship>)>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    mut fire_projectile_event_writer: EventWriter<FireEvent>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     touch_shoot_timer: Option<Res<TouchShootTimer>>,
+    mut inputs: ResMut<PlayerInputs>,
 ) {
     let (camera, camera_global_transform) = camera_query.single();
+    let input = inputs.get_mut(PlayerId::One);
 
-    let Ok((player_entity, player_global_transform, mut player_transform)) =
-        player_query.get_single_mut()
-    else {
+    // A second finger touching down launches a missile
+    if touches.iter().count() > 1 && touches.any_just_pressed() {
+        input.missile = true;
         return;
-    };
+    }
 
     if let Some(touch) = touches.first_pressed_position() {
         if let Some(timer) = touch_shoot_timer {
             if timer.position.distance_squared(touch) < 1000.0 {
-                fire_projectile_event_writer.send(FireEvent {
-                    turret_entity: player_entity,
-                });
+                input.fire = true;
                 commands.remove_resource::<TouchShootTimer>();
                 return;
             }
         }
-        let touch_world_pos = camera
-            .viewport_to_world_2d(camera_global_transform, touch)
+        let touch_world_pos = window_to_world(camera, camera_global_transform, touch)
             .expect("Touch position not in world coordinates");
-        {
-            // Point ship towards touch location
-            let direction = touch_world_pos - player_global_transform.translation().truncate();
-            let angle = direction.y.atan2(direction.x);
-            let target_rotation = Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2);
-            player_transform.rotation = target_rotation;
+        // Point ship towards touch location
+        input.steering = Steering::Towards(touch_world_pos);
+        input.throttle = true;
+    } else if let Some(touch) = touches.iter_just_released().next() {
+        commands.insert_resource(TouchShootTimer::new(touch.position()));
+    }
+}
+
+/// Reads the input of the second player from the arrow keys or the first connected gamepad.
+///
+/// The keyboard turns the ship, the left stick of the gamepad points it like the mouse does.
+fn player_ship_keyboard_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut inputs: ResMut<PlayerInputs>,
+) {
+    let gamepad = gamepads.iter().next();
+    let gamepad_pressed = |button_type| {
+        gamepad.is_some_and(|gamepad| {
+            gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type))
+        })
+    };
+    let gamepad_just_pressed = |button_type| {
+        gamepad.is_some_and(|gamepad| {
+            gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type))
+        })
+    };
+
+    let stick = gamepad.map_or(Vec2::ZERO, |gamepad| {
+        Vec2::new(
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap_or(0.),
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+                .unwrap_or(0.),
+        )
+    });
+    let steering = if stick.length() > GAMEPAD_AIM_DEAD_ZONE {
+        Steering::Heading(stick)
+    } else {
+        let turn = keyboard_input.pressed(KeyCode::ArrowLeft) as i32
+            - keyboard_input.pressed(KeyCode::ArrowRight) as i32;
+        Steering::Turn(turn as f32)
+    };
+
+    *inputs.get_mut(PlayerId::Two) = PlayerInput {
+        steering,
+        throttle: keyboard_input.pressed(KeyCode::ArrowUp)
+            || gamepad_pressed(GamepadButtonType::South),
+        fire: keyboard_input.pressed(KeyCode::Enter)
+            || gamepad_pressed(GamepadButtonType::RightTrigger2),
+        missile: keyboard_input.just_pressed(KeyCode::ShiftRight)
+            || gamepad_just_pressed(GamepadButtonType::West),
+    };
+}
+
+/// Steers the player ships and fires their weapons according to [`PlayerInputs`].
+pub fn apply_player_inputs(
+    mut commands: Commands,
+    inputs: Res<PlayerInputs>,
+    mut player_query: Query<
+        (Entity, &PlayerId, &GlobalTransform, &mut Transform),
+        (With<Player>, With<Ship>),
+    >,
+    mut fire_projectile_event_writer: EventWriter<FireEvent>,
+    mut fire_missile_event_writer: EventWriter<FireMissileEvent>,
+    time: Res<Time>,
+) {
+    for (player_entity, &player_id, player_global_transform, mut player_transform) in
+        player_query.iter_mut()
+    {
+        let input = inputs.get(player_id);
 
-            for (player_entity, _, _) in player_query.iter() {
-                commands.entity(player_entity).insert(Throttling);
+        let heading = match input.steering {
+            Steering::Keep => None,
+            Steering::Towards(target) => {
+                Some(target - player_global_transform.translation().truncate())
+            }
+            Steering::Heading(direction) => Some(direction),
+            Steering::Turn(turn) => {
+                player_transform.rotate_z(turn * KEYBOARD_TURN_SPEED * time.delta_seconds());
+                None
             }
+        };
+        if let Some(direction) = heading {
+            let angle = direction.y.atan2(direction.x);
+            player_transform.rotation = Quat::from_rotation_z(angle - std::f32::consts::FRAC_PI_2);
         }
-    } else if let Some(touch) = touches.iter_just_released().next() {
-        for (player_entity, _, _) in player_query.iter() {
+
+        if input.throttle {
+            commands.entity(player_entity).insert(Throttling);
+        } else {
             commands.entity(player_entity).remove::<Throttling>();
         }
-        commands.insert_resource(TouchShootTimer::new(touch.position()));
+
+        if input.fire {
+            fire_projectile_event_writer.send(FireEvent {
+                turret_entity: player_entity,
+            });
+        }
+
+        if input.missile {
+            fire_missile_event_writer.send(FireMissileEvent {
+                launcher_entity: player_entity,
+            });
+        }
     }
 }
 
@@ -0,0 +1,386 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate, Startup, Update},
+    core_pipeline::core_2d::Camera2dBundle,
+    ecs::{
+        component::Component,
+        query::{With, Without},
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, SystemSet},
+        system::{Commands, Query, Res, ResMut},
+    },
+    gizmos::{
+        config::{GizmoConfig, GizmoConfigGroup},
+        gizmos::Gizmos,
+        AppGizmoBuilder,
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    math::{BVec2, UVec2, Vec2, Vec3Swizzles},
+    reflect::Reflect,
+    render::{
+        camera::{Camera, ClearColorConfig, OrthographicProjection, ScalingMode, Viewport},
+        color::Color,
+    },
+    time::Time,
+    transform::{
+        components::{GlobalTransform, Transform},
+        TransformSystem,
+    },
+    utils::default,
+};
+use bevy_rapier2d::dynamics::Velocity;
+
+use crate::{
+    asteroid::Asteroid,
+    camera_effects::CameraEffectsSet,
+    edge_wrap::{BoundaryMode, Bounds},
+    game_state::GameState,
+    player::Player,
+    playfield::{MainCamera, Playfield},
+    ufo::Ufo,
+};
+
+/// Optional world several screens in size, with a camera that follows the player.
+///
+/// The world still wraps, so seam cameras render the other side of the world wherever the view
+/// reaches over an edge, and a minimap shows the whole world.
+pub struct LargeWorldPlugin;
+
+impl Plugin for LargeWorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_gizmo_group(
+            MinimapGizmos,
+            GizmoConfig {
+                line_width: MINIMAP_LINE_WIDTH,
+                ..default()
+            },
+        )
+        .add_systems(Startup, spawn_seam_cameras)
+        .add_systems(
+            Update,
+            toggle_world_size
+                .run_if(in_state(GameState::Menu))
+                .in_set(LargeWorldSet),
+        )
+        .add_systems(OnEnter(GameState::Playing), reset_camera_follow)
+        .add_systems(
+            PostUpdate,
+            (
+                follow_player.before(CameraEffectsSet),
+                sync_seam_cameras.after(CameraEffectsSet),
+            )
+                .before(TransformSystem::TransformPropagate)
+                .in_set(LargeWorldSet),
+        )
+        .add_systems(
+            PostUpdate,
+            draw_minimap
+                .run_if(is_large_world)
+                .after(TransformSystem::TransformPropagate)
+                .in_set(LargeWorldSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct LargeWorldSet;
+
+/// Size of the large world in screens.
+const LARGE_WORLD_SCREENS: Vec2 = Vec2::splat(3.);
+/// Seconds of the player's velocity the camera looks ahead.
+const LOOK_AHEAD_TIME: f32 = 0.5;
+const MAX_LOOK_AHEAD: f32 = 300.;
+/// How quickly the camera catches up with its target, the higher the stiffer.
+const FOLLOW_SMOOTHING: f32 = 4.;
+/// Seam cameras render before the main camera, which doesn't clear the viewport.
+const SEAM_CAMERA_ORDER: isize = -4;
+/// Width of the minimap in playfield units, its height follows the aspect ratio of the world.
+const MINIMAP_WIDTH: f32 = 240.;
+const MINIMAP_MARGIN: f32 = 20.;
+const MINIMAP_LINE_WIDTH: f32 = 1.;
+const MINIMAP_DOT_RADIUS: f32 = 2.;
+const MINIMAP_FRAME_COLOR: Color = Color::rgba(1., 1., 1., 0.5);
+const MINIMAP_VIEW_COLOR: Color = Color::rgba(1., 1., 1., 0.25);
+const MINIMAP_ASTEROID_COLOR: Color = Color::GRAY;
+const MINIMAP_UFO_COLOR: Color = Color::RED;
+const MINIMAP_PLAYER_COLOR: Color = Color::WHITE;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct MinimapGizmos;
+
+/// Position the camera follows the player from, inside the bounds.
+///
+/// The camera is moved by the change of this position only, so it composes with the
+/// [`CameraEffects`](crate::camera_effects::CameraEffects) offsets.
+#[derive(Component, Debug, Default)]
+pub struct CameraFollow {
+    position: Vec2,
+}
+
+/// Renders the part of the main camera's view beyond the edges of the world from the opposite
+/// side, offset along the wrapped axes.
+#[derive(Component, Debug)]
+struct SeamCamera {
+    axes: BVec2,
+}
+
+fn is_large_world(playfield: Res<Playfield>) -> bool {
+    playfield.is_large_world()
+}
+
+fn toggle_world_size(mut playfield: ResMut<Playfield>, keyboard_input: Res<ButtonInput<KeyCode>>) {
+    if keyboard_input.just_pressed(KeyCode::F8) {
+        playfield.world_screens = if !playfield.is_large_world() {
+            LARGE_WORLD_SCREENS
+        } else {
+            Vec2::ONE
+        };
+        info!(world_screens = ?playfield.world_screens, "World size changed");
+    }
+}
+
+fn spawn_seam_cameras(mut commands: Commands) {
+    let axes = [
+        BVec2::new(true, false),
+        BVec2::new(false, true),
+        BVec2::TRUE,
+    ];
+
+    for (i, axes) in axes.into_iter().enumerate() {
+        commands.spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    order: SEAM_CAMERA_ORDER + i as isize,
+                    is_active: false,
+                    clear_color: ClearColorConfig::None,
+                    ..default()
+                },
+                ..default()
+            },
+            SeamCamera { axes },
+        ));
+    }
+}
+
+fn reset_camera_follow(mut camera_query: Query<(&mut CameraFollow, &mut Transform)>) {
+    for (mut follow, mut transform) in &mut camera_query {
+        transform.translation -= follow.position.extend(0.);
+        follow.position = Vec2::ZERO;
+    }
+}
+
+/// Moves the camera smoothly towards the players and ahead of where they are heading.
+///
+/// With several players the camera keeps to the middle between them. It takes the shortest way
+/// around the world and jumps to the other side together with the players, which the seam
+/// cameras hide.
+fn follow_player(
+    mut camera_query: Query<(&mut CameraFollow, &mut Transform), With<MainCamera>>,
+    player_query: Query<(&Transform, Option<&Velocity>), (With<Player>, Without<MainCamera>)>,
+    playfield: Res<Playfield>,
+    bounds: Res<Bounds>,
+    time: Res<Time>,
+) {
+    let target = if !playfield.is_large_world() {
+        Some(Vec2::ZERO)
+    } else {
+        let targets = player_query
+            .iter()
+            .map(|(transform, opt_velocity)| {
+                let look_ahead = opt_velocity.map_or(Vec2::ZERO, |velocity| {
+                    (velocity.linvel * LOOK_AHEAD_TIME).clamp_length_max(MAX_LOOK_AHEAD)
+                });
+                transform.translation.xy() + look_ahead
+            })
+            .collect::<Vec<_>>();
+        targets.first().map(|&first| {
+            let mean_displacement = targets
+                .iter()
+                .map(|&target| bounds.wrapped_displacement(first, target))
+                .sum::<Vec2>()
+                / targets.len() as f32;
+            bounds.wrap_position(first + mean_displacement)
+        })
+    };
+    let Some(target) = target else {
+        return;
+    };
+
+    let t = 1. - (-FOLLOW_SMOOTHING * time.delta_seconds()).exp();
+    for (mut follow, mut transform) in &mut camera_query {
+        let position = if !playfield.is_large_world() {
+            target
+        } else {
+            let displacement = bounds.wrapped_displacement(follow.position, target);
+            bounds.wrap_position(follow.position + displacement * t)
+        };
+
+        transform.translation += (position - follow.position).extend(0.);
+        follow.position = position;
+    }
+}
+
+/// Range of the view beyond an edge of the world along one axis, together with the shift to the
+/// opposite side.
+fn seam_range(center: f32, half_view: f32, half_bounds: f32) -> Option<(f32, f32, f32)> {
+    if center + half_view > half_bounds {
+        Some((-2. * half_bounds, half_bounds, center + half_view))
+    } else if center - half_view < -half_bounds {
+        Some((2. * half_bounds, center - half_view, -half_bounds))
+    } else {
+        None
+    }
+}
+
+/// Limits every seam camera to the part of the main viewport beyond the edges of the world and
+/// moves it to the opposite side.
+fn sync_seam_cameras(
+    main_camera_query: Query<
+        (&Camera, &Transform, &OrthographicProjection),
+        (With<MainCamera>, Without<SeamCamera>),
+    >,
+    mut seam_camera_query: Query<(
+        &SeamCamera,
+        &mut Camera,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+    playfield: Res<Playfield>,
+    bounds: Res<Bounds>,
+    boundary_mode: Res<BoundaryMode>,
+) {
+    // Only wrapping edges show the opposite side of the world beyond them
+    let main_camera = main_camera_query
+        .get_single()
+        .ok()
+        .filter(|_| playfield.is_large_world() && *boundary_mode == BoundaryMode::Wrap)
+        .and_then(|(camera, transform, projection)| {
+            camera
+                .viewport
+                .clone()
+                .map(|viewport| (viewport, transform, projection))
+        });
+    let Some((main_viewport, main_transform, main_projection)) = main_camera else {
+        for (_, mut camera, _, _) in &mut seam_camera_query {
+            if camera.is_active {
+                camera.is_active = false;
+            }
+        }
+        return;
+    };
+
+    let center = main_transform.translation.xy();
+    let half_view = playfield.size / 2. * main_projection.scale;
+    let view_min = center - half_view;
+    let seams = [
+        seam_range(center.x, half_view.x, bounds.0.x),
+        seam_range(center.y, half_view.y, bounds.0.y),
+    ];
+    let viewport_size = main_viewport.physical_size.as_vec2();
+
+    for (seam_camera, mut camera, mut transform, mut projection) in &mut seam_camera_query {
+        // Shift, min and max of the region along every axis, the full view on unwrapped axes
+        let mut region = [
+            (0., view_min.x, view_min.x + half_view.x * 2.),
+            (0., view_min.y, view_min.y + half_view.y * 2.),
+        ];
+        let mut active = true;
+        for axis in 0..2 {
+            if seam_camera.axes.test(axis) {
+                match seams[axis] {
+                    Some(seam) => region[axis] = seam,
+                    None => active = false,
+                }
+            }
+        }
+
+        // Snap to whole pixels and back so the regions of the cameras line up exactly
+        let to_pixels = |world: Vec2| {
+            let fraction = (world - view_min) / (half_view * 2.);
+            (Vec2::new(fraction.x, 1. - fraction.y) * viewport_size)
+                .round()
+                .clamp(Vec2::ZERO, viewport_size)
+        };
+        let pixel_min = to_pixels(Vec2::new(region[0].1, region[1].2));
+        let pixel_max = to_pixels(Vec2::new(region[0].2, region[1].1));
+        let pixel_size = (pixel_max - pixel_min).as_uvec2();
+        if pixel_size.min_element() == 0 {
+            active = false;
+        }
+
+        if camera.is_active != active {
+            camera.is_active = active;
+        }
+        if !active {
+            continue;
+        }
+
+        let to_world = |pixels: Vec2| {
+            let fraction = pixels / viewport_size;
+            view_min + Vec2::new(fraction.x, 1. - fraction.y) * half_view * 2.
+        };
+        let world_top_left = to_world(pixel_min);
+        let world_bottom_right = to_world(pixel_max);
+        let shift = Vec2::new(region[0].0, region[1].0);
+
+        camera.viewport = Some(Viewport {
+            physical_position: main_viewport.physical_position + pixel_min.as_uvec2(),
+            physical_size: pixel_size.max(UVec2::ONE),
+            ..default()
+        });
+        transform.translation = (((world_top_left + world_bottom_right) / 2.) + shift)
+            .extend(main_transform.translation.z);
+        projection.scaling_mode = ScalingMode::Fixed {
+            width: world_bottom_right.x - world_top_left.x,
+            height: world_top_left.y - world_bottom_right.y,
+        };
+        projection.scale = 1.;
+    }
+}
+
+/// Draws the whole world scaled down into the top right corner of the view.
+fn draw_minimap(
+    mut gizmos: Gizmos<MinimapGizmos>,
+    camera_query: Query<(&GlobalTransform, &OrthographicProjection), With<MainCamera>>,
+    asteroid_query: Query<&GlobalTransform, With<Asteroid>>,
+    ufo_query: Query<&GlobalTransform, With<Ufo>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    playfield: Res<Playfield>,
+    bounds: Res<Bounds>,
+) {
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+
+    let world_size = bounds.size();
+    let minimap_size =
+        Vec2::new(MINIMAP_WIDTH, MINIMAP_WIDTH * world_size.y / world_size.x) * projection.scale;
+    let center = camera_transform.translation().xy()
+        + (playfield.size / 2. * projection.scale
+            - Vec2::splat(MINIMAP_MARGIN) * projection.scale
+            - minimap_size / 2.);
+    let to_minimap =
+        |position: Vec2| center + bounds.wrap_position(position) / world_size * minimap_size;
+
+    gizmos.rect_2d(center, 0., minimap_size, MINIMAP_FRAME_COLOR);
+    gizmos.rect_2d(
+        to_minimap(camera_transform.translation().xy()),
+        0.,
+        playfield.size * projection.scale / world_size * minimap_size,
+        MINIMAP_VIEW_COLOR,
+    );
+
+    let dot_radius = MINIMAP_DOT_RADIUS * projection.scale;
+    let dots = [
+        (
+            asteroid_query.iter().collect::<Vec<_>>(),
+            MINIMAP_ASTEROID_COLOR,
+        ),
+        (ufo_query.iter().collect(), MINIMAP_UFO_COLOR),
+        (player_query.iter().collect(), MINIMAP_PLAYER_COLOR),
+    ];
+    for (transforms, color) in dots {
+        for transform in transforms {
+            gizmos.circle_2d(to_minimap(transform.translation().xy()), dot_radius, color);
+        }
+    }
+}
@@ -1,13 +1,25 @@
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
 
 mod asteroid;
+mod camera_effects;
+mod codec;
 mod edge_wrap;
 mod explosion;
 mod game_state;
 mod input;
+mod large_world;
+mod mesh_cut;
 mod mesh_utils;
+mod missile;
+mod net;
+mod outline;
+mod particle;
 mod player;
+mod playfield;
 mod projectile;
+mod rng;
+mod rollback;
+mod save;
 mod shatter;
 mod ship;
 mod split_mesh;
@@ -15,22 +27,34 @@ mod turret;
 mod ufo;
 mod ui;
 mod utils;
+mod versus;
 
 use asteroid::{spawn_asteroids, Asteroid, AsteroidPlugin, AsteroidSet};
 use bevy::{asset::AssetMetaCheck, prelude::*, window::WindowMode};
-use bevy_rapier2d::prelude::{NoUserData, RapierConfiguration, RapierPhysicsPlugin};
+use bevy_rapier2d::prelude::{RapierConfiguration, RapierPhysicsPlugin};
+use camera_effects::{CameraEffects, CameraEffectsPlugin};
 use edge_wrap::{EdgeWrapPlugin, EdgeWrapSet};
 use explosion::{Explosion, ExplosionPlugin};
-use game_state::{GameResult, GameState};
+use game_state::{GameMode, GameResult, GameState};
 use input::{PlayerInputPlugin, PlayerInputSet};
-use player::{spawn_player, Player};
-use projectile::{Projectile, ProjectilePlugin, ProjectileSet};
+use large_world::{CameraFollow, LargeWorldPlugin};
+use missile::MissilePlugin;
+use net::{NetPlugin, NetSet, SimulationSet};
+use outline::OutlinePlugin;
+use particle::{Particle, ParticlePlugin};
+use player::{Player, PlayerPlugin, PlayerSet, Players};
+use playfield::{MainCamera, PlayfieldPlugin};
+use projectile::{Projectile, ProjectileOwnerFilter, ProjectilePlugin, ProjectileSet};
+use rng::GameRng;
+use rollback::RollbackPlugin;
+use save::{is_new_game, SavePlugin};
 use shatter::{Debris, ShatterPlugin, ShatterSet};
-use ship::{ShipDestroyedEvent, ShipPlugin, ShipSet};
+use ship::{ShipPlugin, ShipSet};
 use turret::{TurretPlugin, TurretSet};
-use ufo::{Ufo, UfoPlugin};
-use ui::{FinishedScreenPlugin, StartScreenPlugin};
+use ufo::{Ufo, UfoPlugin, UfoSet};
+use ui::{FinishedScreenPlugin, HudPlugin, StartScreenPlugin};
 use utils::cleanup_component;
+use versus::{VersusPlugin, VersusSet};
 
 const PHYSICS_LENGTH_UNIT: f32 = 100.0;
 
@@ -63,30 +87,47 @@ fn main() {
             ..default()
         }))
         .insert_resource(rapier_configuration)
+        .init_resource::<GameRng>()
         .add_plugins((
-            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(PHYSICS_LENGTH_UNIT),
+            RapierPhysicsPlugin::<ProjectileOwnerFilter>::pixels_per_meter(PHYSICS_LENGTH_UNIT),
             // RapierDebugRenderPlugin::default(),
         ))
         .init_state::<GameState>()
         .add_plugins((
             EdgeWrapPlugin,
+            PlayerPlugin,
             PlayerInputPlugin,
             ShipPlugin,
             TurretPlugin,
             ProjectilePlugin,
+            MissilePlugin,
             ExplosionPlugin,
             AsteroidPlugin,
             ShatterPlugin,
             StartScreenPlugin,
             FinishedScreenPlugin,
+            HudPlugin,
             UfoPlugin,
         ))
+        .add_plugins((
+            PlayfieldPlugin,
+            LargeWorldPlugin,
+            CameraEffectsPlugin,
+            OutlinePlugin,
+            ParticlePlugin,
+            VersusPlugin,
+            NetPlugin,
+            RollbackPlugin,
+            SavePlugin,
+        ))
         .add_systems(Startup, setup_camera)
-        .add_systems(OnEnter(GameState::Playing), spawn_player)
-        .add_systems(OnEnter(GameState::Playing), spawn_asteroids)
+        .add_systems(
+            OnEnter(GameState::Playing),
+            spawn_asteroids.after(NetSet).run_if(is_new_game),
+        )
         .add_systems(
             OnExit(GameState::Finished),
-            cleanup_types!(Player, Asteroid, Debris, Projectile, Explosion, Ufo),
+            cleanup_types!(Player, Asteroid, Debris, Projectile, Explosion, Ufo, Particle),
         )
         .configure_sets(
             Update,
@@ -96,14 +137,36 @@ fn main() {
                 EdgeWrapSet,
                 TurretSet,
                 ProjectileSet,
-                (AsteroidSet, (ShatterSet, GameFlowSet)).chain(),
+                (
+                    AsteroidSet,
+                    (ShatterSet, (PlayerSet, VersusSet, GameFlowSet).chain()),
+                )
+                    .chain(),
             )
                 .chain(),
         )
+        .configure_sets(
+            Update,
+            (
+                ShipSet,
+                EdgeWrapSet,
+                TurretSet,
+                ProjectileSet,
+                AsteroidSet,
+                ShatterSet,
+                UfoSet,
+                PlayerSet,
+                VersusSet,
+                GameFlowSet,
+            )
+                .in_set(SimulationSet),
+        )
         .add_systems(
             Update,
-            ((player_destroyed, level_cleared).run_if(in_state(GameState::Playing)))
-                .in_set(GameFlowSet),
+            ((player_destroyed, level_cleared).run_if(
+                in_state(GameState::Playing).and_then(not(resource_equals(GameMode::Versus))),
+            ))
+            .in_set(GameFlowSet),
         );
 
     app.run();
@@ -113,22 +176,31 @@ fn main() {
 struct GameFlowSet;
 
 fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                // The letterbox camera clears the window, the seam cameras render before this one
+                clear_color: ClearColorConfig::None,
+                ..default()
+            },
+            ..default()
+        },
+        MainCamera,
+        CameraFollow::default(),
+        CameraEffects::default(),
+    ));
 }
 
 fn player_destroyed(
     mut commands: Commands,
     mut next_gamestate: ResMut<NextState<GameState>>,
-    mut ship_destroyed_events: EventReader<ShipDestroyedEvent>,
-    player_query: Query<Entity, With<Player>>,
+    players: Res<Players>,
 ) {
-    if !ship_destroyed_events.is_empty() && player_query.is_empty() {
-        info!("Player destroyed");
+    if players.all_out_of_lives() {
+        info!("All players destroyed");
         commands.insert_resource(GameResult::Lose);
         next_gamestate.set(GameState::Finished);
     }
-
-    ship_destroyed_events.clear();
 }
 
 fn level_cleared(
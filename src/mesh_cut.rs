@@ -0,0 +1,679 @@
+//! Cutting 2D triangle meshes along arbitrary polylines and polygons.
+//!
+//! Every triangle of the mesh is clipped against convex pieces of the cut shape, the resulting
+//! fragments are welded back together and split into connected pieces. Pieces are returned
+//! recentered around their own origin together with their offset, like [`split_mesh`] does.
+//!
+//! [`split_mesh`]: crate::split_mesh::split_mesh
+
+use bevy::{math::Vec2, render::mesh::Mesh};
+use itertools::Itertools;
+use tracing::instrument;
+
+use crate::split_mesh::{check_mesh, create_mesh_2d, recenter_mesh, repair_mesh_geometry};
+
+/// Fragments smaller than this are dropped.
+const MIN_FRAGMENT_AREA: f32 = 1e-3;
+/// Vertices closer than this are considered the same vertex.
+const WELD_DISTANCE: f32 = 1e-3;
+
+/// Cuts the mesh along a polyline, returning the connected pieces to the left and to the right of
+/// it.
+///
+/// The first and last segments of the polyline are extended until they leave the mesh, so the
+/// polyline doesn't need to start or end outside of it.
+#[instrument(skip(mesh))]
+pub fn cut_mesh_along_polyline(mesh: &Mesh, polyline: &[Vec2]) -> [Vec<(Mesh, Vec2)>; 2] {
+    let triangles = mesh_triangles(mesh);
+
+    let Some(left_polygon) = polyline_left_polygon(&triangles, polyline) else {
+        return [
+            pieces_from_polygons(triangles_to_polygons(&triangles)),
+            vec![],
+        ];
+    };
+
+    cut_triangles_with_polygon(&triangles, &left_polygon)
+}
+
+/// Cuts the mesh with a simple polygon, returning the connected pieces inside and outside of it.
+#[instrument(skip(mesh))]
+pub fn cut_mesh_with_polygon(mesh: &Mesh, polygon: &[Vec2]) -> [Vec<(Mesh, Vec2)>; 2] {
+    cut_triangles_with_polygon(&mesh_triangles(mesh), polygon)
+}
+
+/// Cuts the mesh into convex, counter-clockwise cells, returning the connected pieces inside of
+/// every cell.
+#[instrument(skip(mesh, cells))]
+pub fn cut_mesh_into_cells(mesh: &Mesh, cells: &[Vec<Vec2>]) -> Vec<(Mesh, Vec2)> {
+    let triangles = mesh_triangles(mesh);
+
+    cells
+        .iter()
+        .filter(|cell| cell.len() >= 3)
+        .flat_map(|cell| {
+            pieces_from_polygons(
+                triangles
+                    .iter()
+                    .map(|triangle| clip_convex(triangle, cell))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Voronoi cells of the seeds within the box between `min` and `max`, as convex,
+/// counter-clockwise polygons in the same order as the seeds.
+///
+/// Seeds that coincide with an earlier seed get an empty cell.
+pub fn voronoi_cells(seeds: &[Vec2], min: Vec2, max: Vec2) -> Vec<Vec<Vec2>> {
+    let bounding_box = vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+
+    seeds
+        .iter()
+        .enumerate()
+        .map(|(i, seed)| {
+            seeds.iter().enumerate().filter(|(j, _)| *j != i).fold(
+                bounding_box.clone(),
+                |cell, (j, other)| {
+                    let away_from_other = *seed - *other;
+                    if away_from_other.length_squared() <= f32::EPSILON {
+                        return if j < i { Vec::new() } else { cell };
+                    }
+                    if cell.is_empty() {
+                        return cell;
+                    }
+
+                    // Keep the half closer to the seed, to the left of the bisector
+                    let midpoint = (*seed + *other) / 2.;
+                    clip_half_plane(&cell, midpoint, midpoint - away_from_other.perp())
+                },
+            )
+        })
+        .collect()
+}
+
+/// Regular polygon approximating a circle, for circular cuts.
+pub fn circle_polygon(center: Vec2, radius: f32, segments: usize) -> Vec<Vec2> {
+    (0..segments)
+        .map(|i| {
+            center + Vec2::from_angle(i as f32 / segments as f32 * std::f32::consts::TAU) * radius
+        })
+        .collect()
+}
+
+fn cut_triangles_with_polygon(triangles: &[[Vec2; 3]], polygon: &[Vec2]) -> [Vec<(Mesh, Vec2)>; 2] {
+    let Some(polygon_triangles) = triangulate_polygon(polygon) else {
+        return [
+            vec![],
+            pieces_from_polygons(triangles_to_polygons(triangles)),
+        ];
+    };
+
+    let mut inside = Vec::new();
+    let mut outside = Vec::new();
+
+    for triangle in triangles {
+        let mut remaining = vec![triangle.to_vec()];
+
+        for polygon_triangle in &polygon_triangles {
+            inside.push(clip_convex(triangle, polygon_triangle));
+
+            remaining = remaining
+                .iter()
+                .flat_map(|fragment| subtract_convex(fragment, polygon_triangle))
+                .collect();
+        }
+
+        outside.extend(remaining);
+    }
+
+    [pieces_from_polygons(inside), pieces_from_polygons(outside)]
+}
+
+/// Triangles of the mesh, all wound counter-clockwise.
+fn mesh_triangles(mesh: &Mesh) -> Vec<[Vec2; 3]> {
+    let vertices = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .unwrap()
+        .as_float3()
+        .expect("Only Float32x3 positions are supported");
+    let indices = mesh.indices().expect("Mesh must have indices");
+
+    indices
+        .iter()
+        .tuples()
+        .map(|(i0, i1, i2)| {
+            let [a, b, c] = [i0, i1, i2].map(|i| Vec2::new(vertices[i][0], vertices[i][1]));
+            if signed_area(&[a, b, c]) < 0. {
+                [a, c, b]
+            } else {
+                [a, b, c]
+            }
+        })
+        .collect()
+}
+
+fn triangles_to_polygons(triangles: &[[Vec2; 3]]) -> Vec<Vec<Vec2>> {
+    triangles.iter().map(|triangle| triangle.to_vec()).collect()
+}
+
+/// Closes the polyline around the mesh into the polygon covering everything to its left.
+fn polyline_left_polygon(triangles: &[[Vec2; 3]], polyline: &[Vec2]) -> Option<Vec<Vec2>> {
+    let polyline = polyline.iter().copied().dedup().collect_vec();
+    if polyline.len() < 2 || triangles.is_empty() {
+        return None;
+    }
+
+    let (min, max) = triangles.iter().flatten().chain(polyline.iter()).fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), point| (min.min(*point), max.max(*point)),
+    );
+    let margin = (max - min).max_element() + 1.;
+    let min = min - margin;
+    let max = max + margin;
+
+    let start = polyline[0];
+    let end = polyline[polyline.len() - 1];
+    let start_direction = (polyline[0] - polyline[1]).normalize();
+    let end_direction = (end - polyline[polyline.len() - 2]).normalize();
+
+    let start_exit = ray_box_exit(start, start_direction, min, max);
+    let end_exit = ray_box_exit(end, end_direction, min, max);
+
+    // Walk counter-clockwise around the box from where the polyline leaves it back to where it
+    // enters it, the region enclosed this way is to the left of the polyline.
+    let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+    let perimeter_position = |point: Vec2| box_perimeter_position(point, min, max);
+    let perimeter = 2. * (max.x - min.x) + 2. * (max.y - min.y);
+
+    let end_position = perimeter_position(end_exit);
+    let mut start_position = perimeter_position(start_exit);
+    if start_position <= end_position {
+        start_position += perimeter;
+    }
+
+    let walked_corners = [0, 1, 2, 3, 4, 5, 6, 7]
+        .map(|i| {
+            (
+                corners[i % 4],
+                perimeter_position(corners[i % 4]) + (i / 4) as f32 * perimeter,
+            )
+        })
+        .into_iter()
+        .filter(|(_, position)| *position > end_position && *position < start_position)
+        .map(|(corner, _)| corner);
+
+    let polygon = std::iter::once(start_exit)
+        .chain(polyline.iter().copied())
+        .chain(std::iter::once(end_exit))
+        .chain(walked_corners)
+        .dedup_by(|a, b| a.distance_squared(*b) < WELD_DISTANCE * WELD_DISTANCE)
+        .collect_vec();
+
+    Some(polygon)
+}
+
+/// Point where a ray starting inside the box leaves it.
+fn ray_box_exit(origin: Vec2, direction: Vec2, min: Vec2, max: Vec2) -> Vec2 {
+    let exit_distance = |origin: f32, direction: f32, min: f32, max: f32| {
+        if direction > 0. {
+            (max - origin) / direction
+        } else if direction < 0. {
+            (min - origin) / direction
+        } else {
+            f32::INFINITY
+        }
+    };
+
+    let t = exit_distance(origin.x, direction.x, min.x, max.x).min(exit_distance(
+        origin.y,
+        direction.y,
+        min.y,
+        max.y,
+    ));
+
+    (origin + direction * t).clamp(min, max)
+}
+
+/// Distance along the box boundary, counter-clockwise starting from `min`.
+fn box_perimeter_position(point: Vec2, min: Vec2, max: Vec2) -> f32 {
+    let size = max - min;
+    let local = point - min;
+    let tolerance = size.max_element() * 1e-5;
+
+    if local.y <= tolerance {
+        local.x
+    } else if local.x >= size.x - tolerance {
+        size.x + local.y
+    } else if local.y >= size.y - tolerance {
+        size.x + size.y + (size.x - local.x)
+    } else {
+        2. * size.x + size.y + (size.y - local.y)
+    }
+}
+
+fn signed_area(polygon: &[Vec2]) -> f32 {
+    polygon
+        .iter()
+        .circular_tuple_windows()
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum::<f32>()
+        * 0.5
+}
+
+/// Signed distance of `point` to the left of the line through `a` and `b`.
+fn side_of_line(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    (b - a).normalize_or_zero().perp_dot(point - a)
+}
+
+/// Keeps the part of a convex polygon to the left of the line through `a` and `b`.
+fn clip_half_plane(polygon: &[Vec2], a: Vec2, b: Vec2) -> Vec<Vec2> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+
+    for (&p, &q) in polygon.iter().circular_tuple_windows() {
+        let side_p = side_of_line(p, a, b);
+        let side_q = side_of_line(q, a, b);
+
+        if side_p >= 0. {
+            clipped.push(p);
+        }
+
+        if (side_p >= 0.) != (side_q >= 0.) {
+            clipped.push(p + (q - p) * (side_p / (side_p - side_q)));
+        }
+    }
+
+    clipped
+}
+
+/// Intersection of two convex, counter-clockwise polygons.
+fn clip_convex(subject: &[Vec2], clip: &[Vec2]) -> Vec<Vec2> {
+    clip.iter()
+        .circular_tuple_windows()
+        .fold(subject.to_vec(), |polygon, (&a, &b)| {
+            if polygon.is_empty() {
+                polygon
+            } else {
+                clip_half_plane(&polygon, a, b)
+            }
+        })
+}
+
+/// Parts of a convex polygon outside of another convex polygon, as convex polygons.
+fn subtract_convex(subject: &[Vec2], clip: &[Vec2]) -> Vec<Vec<Vec2>> {
+    let mut fragments = Vec::new();
+    let mut remaining = subject.to_vec();
+
+    for (&a, &b) in clip.iter().circular_tuple_windows() {
+        let outside = clip_half_plane(&remaining, b, a);
+        if signed_area(&outside) > MIN_FRAGMENT_AREA {
+            fragments.push(outside);
+        }
+
+        remaining = clip_half_plane(&remaining, a, b);
+        if signed_area(&remaining) <= MIN_FRAGMENT_AREA {
+            return fragments;
+        }
+    }
+
+    fragments
+}
+
+fn point_in_triangle(point: Vec2, [a, b, c]: [Vec2; 3]) -> bool {
+    let tolerance = -1e-6;
+    side_of_line(point, a, b) >= tolerance
+        && side_of_line(point, b, c) >= tolerance
+        && side_of_line(point, c, a) >= tolerance
+}
+
+/// Splits a simple polygon into counter-clockwise triangles by ear clipping.
+///
+/// Returns `None` if the polygon is degenerate or self-intersecting.
+fn triangulate_polygon(polygon: &[Vec2]) -> Option<Vec<[Vec2; 3]>> {
+    let mut points = polygon
+        .iter()
+        .copied()
+        .dedup_by(|a, b| a.distance_squared(*b) < WELD_DISTANCE * WELD_DISTANCE)
+        .collect_vec();
+    while points.len() > 1
+        && points[0].distance_squared(points[points.len() - 1]) < WELD_DISTANCE * WELD_DISTANCE
+    {
+        points.pop();
+    }
+
+    let area = signed_area(&points);
+    if points.len() < 3 || area.abs() <= MIN_FRAGMENT_AREA {
+        return None;
+    }
+    if area < 0. {
+        points.reverse();
+    }
+
+    let mut remaining = (0..points.len()).collect_vec();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corner = |i: usize| {
+            [
+                remaining[(i + count - 1) % count],
+                remaining[i],
+                remaining[(i + 1) % count],
+            ]
+        };
+        let turn = |i: usize| {
+            let [a, b, c] = corner(i).map(|index| points[index]);
+            (b - a).perp_dot(c - b)
+        };
+
+        let ear = (0..count).find(|&i| {
+            if turn(i) <= 0. {
+                return false;
+            }
+
+            let ear_corner = corner(i);
+            let triangle = ear_corner.map(|index| points[index]);
+            remaining
+                .iter()
+                .filter(|index| !ear_corner.contains(index))
+                .all(|&index| !point_in_triangle(points[index], triangle))
+        });
+
+        if let Some(ear) = ear {
+            triangles.push(corner(ear).map(|index| points[index]));
+            remaining.remove(ear);
+        } else if let Some(collinear) = (0..count).find(|&i| turn(i).abs() <= f32::EPSILON) {
+            remaining.remove(collinear);
+        } else {
+            return None;
+        }
+    }
+
+    let last = [remaining[0], remaining[1], remaining[2]].map(|index| points[index]);
+    if signed_area(&last) > 0. {
+        triangles.push(last);
+    }
+
+    Some(triangles)
+}
+
+/// Welds the convex fragments into meshes, one for every connected group of fragments.
+fn pieces_from_polygons(polygons: Vec<Vec<Vec2>>) -> Vec<(Mesh, Vec2)> {
+    let mut vertices: Vec<Vec2> = Vec::new();
+    let mut triangles: Vec<[usize; 3]> = Vec::new();
+
+    let mut weld = |vertex: Vec2| {
+        vertices
+            .iter()
+            .position(|existing| existing.distance_squared(vertex) < WELD_DISTANCE * WELD_DISTANCE)
+            .unwrap_or_else(|| {
+                vertices.push(vertex);
+                vertices.len() - 1
+            })
+    };
+
+    for polygon in polygons {
+        if signed_area(&polygon) <= MIN_FRAGMENT_AREA {
+            continue;
+        }
+
+        let indices = polygon.iter().map(|vertex| weld(*vertex)).collect_vec();
+        for i in 1..indices.len() - 1 {
+            let triangle = [indices[0], indices[i], indices[i + 1]];
+            if triangle[0] != triangle[1]
+                && triangle[1] != triangle[2]
+                && triangle[0] != triangle[2]
+            {
+                triangles.push(triangle);
+            }
+        }
+    }
+
+    let triangles = triangles
+        .into_iter()
+        .filter(|triangle| signed_area(&triangle.map(|index| vertices[index])) > MIN_FRAGMENT_AREA)
+        .collect_vec();
+
+    // Group the triangles by the vertices they share
+    let mut parents = (0..vertices.len()).collect_vec();
+    fn root(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+        index
+    }
+    for [a, b, c] in &triangles {
+        for (x, y) in [(*a, *b), (*b, *c)] {
+            let (root_x, root_y) = (root(&mut parents, x), root(&mut parents, y));
+            parents[root_x] = root_y;
+        }
+    }
+
+    triangles
+        .into_iter()
+        .into_group_map_by(|triangle| root(&mut parents, triangle[0]))
+        .into_values()
+        .filter_map(|piece_triangles| {
+            let mut piece_vertices = Vec::new();
+            let mut remap = vec![None; vertices.len()];
+            let mut piece_indices = piece_triangles
+                .iter()
+                .map(|triangle| {
+                    triangle.map(|index| {
+                        *remap[index].get_or_insert_with(|| {
+                            piece_vertices.push(vertices[index]);
+                            piece_vertices.len() - 1
+                        })
+                    })
+                })
+                .collect_vec();
+
+            // Recentering moves the vertices slightly, repair afterwards so the piece is checked
+            // exactly as repaired
+            let offset = recenter_mesh(&mut piece_vertices);
+            repair_mesh_geometry(&mut piece_vertices, &mut piece_indices);
+            if piece_indices.is_empty() {
+                return None;
+            }
+
+            let mesh = create_mesh_2d(&piece_vertices, &piece_indices);
+            check_mesh(&mesh).then_some((mesh, offset))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use bevy::math::primitives::{Rectangle, RegularPolygon};
+    use proptest::prelude::*;
+
+    use crate::mesh_utils::{calculate_mesh_area, is_ccw_winded, valid_mesh};
+
+    use super::*;
+
+    fn total_area(pieces: &[(Mesh, Vec2)]) -> f32 {
+        pieces
+            .iter()
+            .map(|(mesh, _)| calculate_mesh_area(mesh))
+            .sum()
+    }
+
+    fn assert_valid_pieces(pieces: &[(Mesh, Vec2)]) {
+        for (mesh, _) in pieces {
+            assert!(valid_mesh(mesh));
+
+            let vertices = mesh
+                .attribute(Mesh::ATTRIBUTE_POSITION)
+                .unwrap()
+                .as_float3()
+                .unwrap()
+                .iter()
+                .map(|v| Vec2::new(v[0], v[1]))
+                .collect_vec();
+            for (i0, i1, i2) in mesh.indices().unwrap().iter().tuples() {
+                assert!(is_ccw_winded(&vertices, &[i0, i1, i2]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_straight_polyline_cut() {
+        let mesh = Mesh::from(Rectangle::new(100., 100.));
+
+        let [left, right] =
+            cut_mesh_along_polyline(&mesh, &[Vec2::new(0., -10.), Vec2::new(0., 10.)]);
+
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+        assert_approx_eq!(total_area(&left), 5000., 0.1);
+        assert_approx_eq!(total_area(&right), 5000., 0.1);
+        assert_approx_eq!(left[0].1.x, -25., 0.001);
+        assert_approx_eq!(right[0].1.x, 25., 0.001);
+    }
+
+    #[test]
+    fn test_jagged_polyline_cut() {
+        let mesh = Mesh::from(Rectangle::new(100., 100.));
+        let polyline = [
+            Vec2::new(-60., 0.),
+            Vec2::new(-20., 20.),
+            Vec2::new(0., -20.),
+            Vec2::new(20., 20.),
+            Vec2::new(60., 0.),
+        ];
+
+        let [left, right] = cut_mesh_along_polyline(&mesh, &polyline);
+
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+        assert_approx_eq!(total_area(&left) + total_area(&right), 10000., 0.1);
+        assert_valid_pieces(&left);
+        assert_valid_pieces(&right);
+    }
+
+    #[test]
+    fn test_circle_bite_splits_strip() {
+        let mesh = Mesh::from(Rectangle::new(200., 10.));
+
+        let [inside, outside] = cut_mesh_with_polygon(&mesh, &circle_polygon(Vec2::ZERO, 20., 16));
+
+        assert_eq!(inside.len(), 1);
+        assert_eq!(outside.len(), 2);
+        assert_approx_eq!(total_area(&inside) + total_area(&outside), 2000., 0.1);
+    }
+
+    #[test]
+    fn test_polygon_outside_mesh() {
+        let mesh = Mesh::from(Rectangle::new(100., 100.));
+
+        let [inside, outside] =
+            cut_mesh_with_polygon(&mesh, &circle_polygon(Vec2::new(500., 0.), 20., 8));
+
+        assert!(inside.is_empty());
+        assert_eq!(outside.len(), 1);
+        assert_approx_eq!(total_area(&outside), 10000., 0.1);
+    }
+
+    #[test]
+    fn test_voronoi_cells_of_two_seeds() {
+        let cells = voronoi_cells(
+            &[Vec2::new(-10., 0.), Vec2::new(10., 0.)],
+            Vec2::splat(-50.),
+            Vec2::splat(50.),
+        );
+
+        assert_eq!(cells.len(), 2);
+        assert_approx_eq!(signed_area(&cells[0]), 5000., 0.01);
+        assert_approx_eq!(signed_area(&cells[1]), 5000., 0.01);
+        assert!(cells[0].iter().all(|point| point.x <= 0.));
+        assert!(cells[1].iter().all(|point| point.x >= 0.));
+    }
+
+    #[test]
+    fn test_triangulate_concave_polygon() {
+        let polygon = [
+            Vec2::new(0., 0.),
+            Vec2::new(4., 0.),
+            Vec2::new(4., 4.),
+            Vec2::new(2., 1.),
+            Vec2::new(0., 4.),
+        ];
+
+        let triangles = triangulate_polygon(&polygon).unwrap();
+
+        assert_eq!(triangles.len(), 3);
+        let area: f32 = triangles.iter().map(|triangle| signed_area(triangle)).sum();
+        assert_approx_eq!(area, signed_area(&polygon), 0.0001);
+    }
+
+    proptest! {
+        #[test]
+        fn test_polyline_cut_preserves_area(
+            angle in 0.0..std::f32::consts::TAU,
+            offset in -30.0..30.0f32,
+            jaggedness in prop::collection::vec(-15.0..15.0f32, 0..6),
+        ) {
+            let mesh = Mesh::from(RegularPolygon::new(50., 14));
+            let direction = Vec2::from_angle(angle);
+            let normal = direction.perp();
+            let step = 100. / (jaggedness.len() + 1) as f32;
+            let polyline = std::iter::once(0.)
+                .chain(jaggedness.iter().copied())
+                .chain(std::iter::once(0.))
+                .enumerate()
+                .map(|(i, jag)| {
+                    direction * (i as f32 * step - 50.) + normal * (offset + jag)
+                })
+                .collect_vec();
+
+            let [left, right] = cut_mesh_along_polyline(&mesh, &polyline);
+
+            let area = calculate_mesh_area(&mesh);
+            prop_assert!((total_area(&left) + total_area(&right) - area).abs() < area * 0.001);
+            prop_assert!(!left.is_empty());
+            prop_assert!(!right.is_empty());
+            assert_valid_pieces(&left);
+            assert_valid_pieces(&right);
+        }
+
+        #[test]
+        fn test_voronoi_cut_preserves_area(
+            seeds in prop::collection::vec((-60.0..60.0f32, -60.0..60.0f32), 1..16),
+        ) {
+            let mesh = Mesh::from(RegularPolygon::new(50., 14));
+            let seeds = seeds.into_iter().map(|(x, y)| Vec2::new(x, y)).collect_vec();
+            let cells = voronoi_cells(&seeds, Vec2::splat(-60.), Vec2::splat(60.));
+
+            let cells_area: f32 = cells.iter().map(|cell| signed_area(cell)).sum();
+            prop_assert!((cells_area - 120. * 120.).abs() < 1.);
+
+            let pieces = cut_mesh_into_cells(&mesh, &cells);
+
+            let area = calculate_mesh_area(&mesh);
+            prop_assert!((total_area(&pieces) - area).abs() < area * 0.001);
+            assert_valid_pieces(&pieces);
+        }
+
+        #[test]
+        fn test_polygon_cut_preserves_area(
+            x in -60.0..60.0f32,
+            y in -60.0..60.0f32,
+            radius in 1.0..40.0f32,
+            segments in 3usize..12,
+        ) {
+            let mesh = Mesh::from(RegularPolygon::new(50., 14));
+            let polygon = circle_polygon(Vec2::new(x, y), radius, segments);
+
+            let [inside, outside] = cut_mesh_with_polygon(&mesh, &polygon);
+
+            let area = calculate_mesh_area(&mesh);
+            prop_assert!((total_area(&inside) + total_area(&outside) - area).abs() < area * 0.001);
+            prop_assert!(total_area(&inside) <= signed_area(&polygon) + 0.01);
+            assert_valid_pieces(&inside);
+            assert_valid_pieces(&outside);
+        }
+    }
+}
@@ -32,6 +32,166 @@ pub fn valid_mesh(mesh: &Mesh) -> bool {
     true
 }
 
+/// Triangles with less area than this are considered degenerate.
+const DEGENERATE_AREA: f32 = 1e-5;
+/// Vertices closer than this are considered duplicates.
+const DUPLICATE_VERTEX_DISTANCE: f32 = 1e-4;
+/// Vertices closer than this to an edge are considered to lie on it.
+const ON_EDGE_DISTANCE: f32 = 1e-3;
+/// Triangles overlapping less than this are considered to merely touch.
+const OVERLAP_TOLERANCE: f32 = 1e-2;
+
+/// Geometry problems found by [`validate_mesh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshDefect {
+    MissingAttributes,
+    /// The index count is not a multiple of three, or an index is out of bounds.
+    InvalidIndices,
+    ClockwiseTriangle {
+        triangle: usize,
+    },
+    ZeroAreaTriangle {
+        triangle: usize,
+    },
+    DuplicateVertices {
+        a: usize,
+        b: usize,
+    },
+    /// A vertex lies on the edge of a triangle it is not a corner of.
+    TJunction {
+        vertex: usize,
+        triangle: usize,
+    },
+    Overlap {
+        a: usize,
+        b: usize,
+    },
+}
+
+/// Checks the mesh for everything that trips up splitting and collider generation.
+#[instrument(skip(mesh))]
+pub fn validate_mesh(mesh: &Mesh) -> Vec<MeshDefect> {
+    let (Some(vertices), Some(indices)) = (
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|attribute| attribute.as_float3()),
+        mesh.indices(),
+    ) else {
+        return vec![MeshDefect::MissingAttributes];
+    };
+    let vertices = vertices.iter().map(|v| Vec2::new(v[0], v[1])).collect_vec();
+    let indices = indices.iter().collect_vec();
+
+    if indices.len() % 3 != 0 || indices.iter().any(|&index| index >= vertices.len()) {
+        return vec![MeshDefect::InvalidIndices];
+    }
+
+    let triangles = indices
+        .iter()
+        .copied()
+        .tuples()
+        .map(|(a, b, c)| [a, b, c])
+        .collect_vec();
+    let mut defects = Vec::new();
+
+    let mut solid_triangles = Vec::new();
+    for (triangle, indices) in triangles.iter().enumerate() {
+        if is_degenerate_triangle(indices.map(|index| vertices[index])) {
+            defects.push(MeshDefect::ZeroAreaTriangle { triangle });
+            continue;
+        }
+        if !is_ccw_winded(&vertices, indices) {
+            defects.push(MeshDefect::ClockwiseTriangle { triangle });
+        }
+        solid_triangles.push(triangle);
+    }
+
+    let used_vertices = triangles.iter().flatten().copied().unique().collect_vec();
+
+    for (&a, &b) in used_vertices.iter().tuple_combinations() {
+        if vertices[a].distance(vertices[b]) < DUPLICATE_VERTEX_DISTANCE {
+            defects.push(MeshDefect::DuplicateVertices { a, b });
+        }
+    }
+
+    for (triangle, indices) in triangles.iter().enumerate() {
+        for vertex in used_vertices
+            .iter()
+            .copied()
+            .filter(|vertex| !indices.contains(vertex))
+        {
+            let on_edge = (0..3).any(|edge| {
+                let triangle = [edge, edge + 1, edge + 2].map(|i| vertices[indices[i % 3]]);
+                splits_triangle(vertices[vertex], triangle)
+            });
+            if on_edge {
+                defects.push(MeshDefect::TJunction { vertex, triangle });
+            }
+        }
+    }
+
+    for (&a, &b) in solid_triangles.iter().tuple_combinations() {
+        if triangles_overlap(
+            triangles[a].map(|index| vertices[index]),
+            triangles[b].map(|index| vertices[index]),
+        ) {
+            defects.push(MeshDefect::Overlap { a, b });
+        }
+    }
+
+    defects
+}
+
+/// Whether the triangle has too little area to count as a triangle at all.
+pub fn is_degenerate_triangle([a, b, c]: [Vec2; 3]) -> bool {
+    (b - a).perp_dot(c - a).abs() / 2. < DEGENERATE_AREA
+}
+
+/// Whether `point` lies on the edge from `a` to `b` of the counter-clockwise triangle, such that
+/// splitting the triangle there leaves two counter-clockwise halves with area.
+pub fn splits_triangle(point: Vec2, [a, b, c]: [Vec2; 3]) -> bool {
+    let solid = |[a, b, c]: [Vec2; 3]| (b - a).perp_dot(c - a) / 2. >= DEGENERATE_AREA;
+    point_on_segment(point, a, b) && solid([a, point, c]) && solid([point, b, c])
+}
+
+/// Whether `point` lies on the segment between `a` and `b`, excluding its ends.
+pub fn point_on_segment(point: Vec2, a: Vec2, b: Vec2) -> bool {
+    let segment = b - a;
+    let length_squared = segment.length_squared();
+    if length_squared <= f32::EPSILON {
+        return false;
+    }
+
+    let t = (point - a).dot(segment) / length_squared;
+    let end_margin = ON_EDGE_DISTANCE / length_squared.sqrt();
+    t > end_margin && t < 1. - end_margin && (a + segment * t).distance(point) < ON_EDGE_DISTANCE
+}
+
+/// Whether the interiors of two triangles overlap, using the separating axis theorem.
+fn triangles_overlap(a: [Vec2; 3], b: [Vec2; 3]) -> bool {
+    let project = |triangle: &[Vec2; 3], axis: Vec2| {
+        triangle.iter().map(|point| point.dot(axis)).fold(
+            (f32::INFINITY, f32::NEG_INFINITY),
+            |(min, max), projection| (min.min(projection), max.max(projection)),
+        )
+    };
+
+    let separated = [a, b].iter().any(|triangle| {
+        (0..3).any(|edge| {
+            let Some(axis) = (triangle[(edge + 1) % 3] - triangle[edge])
+                .perp()
+                .try_normalize()
+            else {
+                return false;
+            };
+            let (min_a, max_a) = project(&a, axis);
+            let (min_b, max_b) = project(&b, axis);
+            max_a <= min_b + OVERLAP_TOLERANCE || max_b <= min_a + OVERLAP_TOLERANCE
+        })
+    });
+
+    !separated
+}
+
 pub fn distance_to_plane(point: Vec2, plane: Plane2d, plane_point: Vec2) -> f32 {
     plane.normal.dot(point - plane_point)
 }
@@ -104,6 +264,24 @@ pub fn calculate_area(vertices: &[[f32; 3]], indices: impl Iterator<Item = usize
         .sum()
 }
 
+/// Center of the bounding box of the mesh, the point split and cut pieces are recentered on.
+pub fn mesh_center(mesh: &Mesh) -> Vec2 {
+    let vertices = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .unwrap()
+        .as_float3()
+        .unwrap();
+
+    let (min, max) = vertices.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), vertex| {
+            let vertex = Vec2::new(vertex[0], vertex[1]);
+            (min.min(vertex), max.max(vertex))
+        },
+    );
+    (min + max) / 2.
+}
+
 #[instrument(skip(mesh))]
 pub fn mesh_longest_axis(mesh: &Mesh) -> Vec2 {
     let vertices = mesh
@@ -136,11 +314,131 @@ pub fn mesh_longest_axis(mesh: &Mesh) -> Vec2 {
 
     panic!("Mesh has no edges");
 }
+/// Merges triangles into convex, counter-clockwise polygons.
+///
+/// Neighbouring polygons are greedily joined over their shared edge for as long as the result
+/// stays convex. That doesn't give the minimal decomposition, but it is cheap and good enough for
+/// the few dozen triangles of an asteroid.
+#[instrument(skip(vertices, triangles))]
+pub fn convex_decomposition(vertices: &[Vec2], triangles: &[[usize; 3]]) -> Vec<Vec<Vec2>> {
+    // Weld duplicated vertices so neighbouring triangles share their edges
+    let mut welded: Vec<Vec2> = Vec::new();
+    let welded_indices = vertices
+        .iter()
+        .map(|vertex| {
+            welded
+                .iter()
+                .position(|existing| existing.abs_diff_eq(*vertex, 1e-4))
+                .unwrap_or_else(|| {
+                    welded.push(*vertex);
+                    welded.len() - 1
+                })
+        })
+        .collect_vec();
+
+    let mut polygons = triangles
+        .iter()
+        .map(|triangle| {
+            let mut triangle = triangle.map(|index| welded_indices[index]);
+            ensure_ccw(&welded, &mut triangle);
+            triangle.to_vec()
+        })
+        .filter(|polygon| {
+            let [a, b, c] = [polygon[0], polygon[1], polygon[2]].map(|index| welded[index]);
+            (b - a).perp_dot(c - a) > f32::EPSILON
+        })
+        .collect_vec();
+
+    while let Some((i, j, merged)) = (0..polygons.len()).tuple_combinations().find_map(|(i, j)| {
+        merge_convex_polygons(&welded, &polygons[i], &polygons[j]).map(|merged| (i, j, merged))
+    }) {
+        polygons[i] = merged;
+        polygons.swap_remove(j);
+    }
+
+    polygons
+        .into_iter()
+        .map(|polygon| polygon.into_iter().map(|index| welded[index]).collect())
+        .collect()
+}
+
+/// Joins two counter-clockwise polygons over a shared edge, if the result is convex.
+fn merge_convex_polygons(vertices: &[Vec2], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (start_a, start_b) = (0..a.len()).find_map(|i| {
+        let (from, to) = (a[i], a[(i + 1) % a.len()]);
+        (0..b.len())
+            .find(|&j| b[j] == to && b[(j + 1) % b.len()] == from)
+            .map(|j| ((i + 1) % a.len(), (j + 1) % b.len()))
+    })?;
+
+    // Walk `a` from the end of the shared edge around to its start, then `b` back again
+    let merged = (0..a.len())
+        .map(|i| a[(start_a + i) % a.len()])
+        .chain((1..b.len() - 1).map(|i| b[(start_b + i) % b.len()]))
+        .collect_vec();
+
+    let convex = merged.iter().circular_tuple_windows().all(|(&p, &q, &r)| {
+        let (p, q, r) = (vertices[p], vertices[q], vertices[r]);
+        (q - p).perp_dot(r - q) >= -1e-3
+    });
+
+    convex.then_some(merged)
+}
+
+/// Returns the edges of the mesh that belong to exactly one triangle, i.e. its outline.
+///
+/// Vertices at the same position are treated as one, so meshes that don't share their vertices
+/// between triangles still only return the outer edges.
+#[instrument(skip(mesh))]
+pub fn boundary_edges(mesh: &Mesh) -> Vec<[Vec2; 2]> {
+    let (Some(vertices), Some(indices)) = (
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3()),
+        mesh.indices(),
+    ) else {
+        return Vec::new();
+    };
+
+    let vertices = vertices
+        .iter()
+        .map(|vertex| Vec2::new(vertex[0], vertex[1]))
+        .collect_vec();
+    let welded_indices = vertices
+        .iter()
+        .enumerate()
+        .map(|(index, vertex)| {
+            vertices
+                .iter()
+                .position(|existing| existing.abs_diff_eq(*vertex, 1e-4))
+                .unwrap_or(index)
+        })
+        .collect_vec();
+
+    let edge_counts = indices
+        .iter()
+        .tuples()
+        .flat_map(|(a, b, c)| [(a, b), (b, c), (c, a)])
+        .filter_map(|(from, to)| {
+            let (from, to) = (*welded_indices.get(from)?, *welded_indices.get(to)?);
+            (from != to).then_some((from.min(to), from.max(to)))
+        })
+        .counts();
+
+    edge_counts
+        .into_iter()
+        .filter(|(_, count)| *count == 1)
+        .map(|((from, to), _)| [vertices[from], vertices[to]])
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use assert_approx_eq::assert_approx_eq;
-    use bevy::render::{mesh::PrimitiveTopology, render_asset::RenderAssetUsages};
+    use bevy::render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    };
     use proptest::prelude::*;
 
     #[test]
@@ -292,4 +590,169 @@ mod tests {
 
         let _longest_axis = mesh_longest_axis(&mesh);
     }
+
+    fn create_test_mesh(vertices: &[Vec2], indices: &[u16]) -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vertices.iter().map(|v| [v.x, v.y, 0.]).collect_vec(),
+        );
+        mesh.insert_indices(Indices::U16(indices.to_vec()));
+        mesh
+    }
+
+    #[test]
+    fn test_validate_mesh_valid() {
+        let mesh = Mesh::from(RegularPolygon::new(10., 8));
+
+        assert_eq!(validate_mesh(&mesh), vec![]);
+    }
+
+    #[test]
+    fn test_validate_mesh_defects() {
+        let vertices = [
+            Vec2::new(0., 0.),
+            Vec2::new(2., 0.),
+            Vec2::new(0., 2.),
+            // On the edge between the first two vertices
+            Vec2::new(1., 0.),
+            Vec2::new(1., -1.),
+            // Duplicate of the first vertex
+            Vec2::new(0., 0.),
+        ];
+        let mesh = create_test_mesh(&vertices, &[0, 1, 2, 0, 3, 4, 0, 1, 1, 5, 2, 1]);
+
+        let defects = validate_mesh(&mesh);
+
+        assert!(defects.contains(&MeshDefect::TJunction {
+            vertex: 3,
+            triangle: 0
+        }));
+        assert!(defects.contains(&MeshDefect::ClockwiseTriangle { triangle: 1 }));
+        assert!(defects.contains(&MeshDefect::ZeroAreaTriangle { triangle: 2 }));
+        assert!(defects.contains(&MeshDefect::DuplicateVertices { a: 0, b: 5 }));
+        assert!(defects.contains(&MeshDefect::Overlap { a: 0, b: 3 }));
+    }
+
+    #[test]
+    fn test_splits_triangle() {
+        let triangle = [
+            Vec2::new(0., 0.),
+            Vec2::new(10., 0.),
+            Vec2::new(20., 0.0005),
+        ];
+
+        assert!(splits_triangle(Vec2::new(5., 0.), triangle));
+        // Just below the edge, the second half would be turned around
+        assert!(!splits_triangle(Vec2::new(5., -0.0009), triangle));
+    }
+
+    #[test]
+    fn test_validate_mesh_invalid_indices() {
+        let mesh = create_test_mesh(&[Vec2::ZERO, Vec2::X, Vec2::Y], &[0, 1, 3]);
+
+        assert_eq!(validate_mesh(&mesh), vec![MeshDefect::InvalidIndices]);
+    }
+
+    #[test]
+    fn test_convex_decomposition_of_convex_mesh() {
+        let mesh = Mesh::from(RegularPolygon::new(10., 8));
+        let vertices = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap()
+            .iter()
+            .map(|v| Vec2::new(v[0], v[1]))
+            .collect_vec();
+        let triangles = mesh
+            .indices()
+            .unwrap()
+            .iter()
+            .tuples()
+            .map(|(a, b, c)| [a, b, c])
+            .collect_vec();
+
+        let polygons = convex_decomposition(&vertices, &triangles);
+
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].len(), 8);
+    }
+
+    #[test]
+    fn test_convex_decomposition_of_concave_mesh() {
+        // An L shape made of three squares
+        let vertices = [
+            Vec2::new(0., 0.),
+            Vec2::new(2., 0.),
+            Vec2::new(2., 1.),
+            Vec2::new(1., 1.),
+            Vec2::new(1., 2.),
+            Vec2::new(0., 2.),
+            Vec2::new(1., 0.),
+            Vec2::new(0., 1.),
+        ];
+        let triangles = [
+            [0, 6, 3],
+            [0, 3, 7],
+            [6, 1, 2],
+            [6, 2, 3],
+            [7, 3, 4],
+            [7, 4, 5],
+        ];
+
+        let polygons = convex_decomposition(&vertices, &triangles);
+
+        assert_eq!(polygons.len(), 2);
+        let area: f32 = polygons
+            .iter()
+            .map(|polygon| {
+                polygon
+                    .iter()
+                    .circular_tuple_windows()
+                    .map(|(a, b)| a.perp_dot(*b))
+                    .sum::<f32>()
+                    / 2.
+            })
+            .sum();
+        assert_approx_eq!(area, 3., 0.0001);
+    }
+
+    #[test]
+    fn test_boundary_edges() {
+        let mesh = Mesh::from(RegularPolygon::new(10., 6));
+
+        let edges = boundary_edges(&mesh);
+
+        assert_eq!(edges.len(), 6);
+        for [from, to] in edges {
+            assert_approx_eq!(from.length(), 10., 0.0001);
+            assert_approx_eq!(to.length(), 10., 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_boundary_edges_of_unshared_vertices() {
+        // Two triangles forming a square, without sharing vertices
+        let vertices = [
+            Vec2::new(0., 0.),
+            Vec2::new(1., 0.),
+            Vec2::new(1., 1.),
+            Vec2::new(0., 0.),
+            Vec2::new(1., 1.),
+            Vec2::new(0., 1.),
+        ];
+        let mesh = create_test_mesh(&vertices, &[0, 1, 2, 3, 4, 5]);
+
+        let edges = boundary_edges(&mesh);
+
+        assert_eq!(edges.len(), 4);
+        assert!(!edges.iter().any(|[from, to]| {
+            let diagonal = [Vec2::ZERO, Vec2::ONE];
+            [*from, *to] == diagonal || [*to, *from] == diagonal
+        }));
+    }
 }
@@ -0,0 +1,237 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle},
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::{Entity, EntityMapper, MapEntities},
+        event::{Event, EventReader},
+        query::{Or, With},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    log::info,
+    math::{primitives::Triangle2d, Quat, Vec2, Vec3, Vec3Swizzles},
+    render::{color::Color, mesh::Mesh},
+    sprite::{ColorMaterial, MaterialMesh2dBundle},
+    time::{Time, Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+    utils::default,
+};
+use bevy_rapier2d::{
+    dynamics::{RigidBody, Velocity},
+    geometry::{ActiveEvents, ActiveHooks, CollisionGroups},
+};
+
+use crate::{
+    asteroid::Asteroid,
+    edge_wrap::{Bounds, Duplicable},
+    game_state::GameMode,
+    player::PlayerId,
+    projectile::{projectile_targets, Projectile, ProjectileSet, PROJECTILE_GROUP},
+    rollback::RollbackAppExt,
+    ufo::Ufo,
+    utils::mesh_to_collider,
+};
+
+pub struct MissilePlugin;
+
+impl Plugin for MissilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FireMissileEvent>()
+            .rollback_component::<MissileAmmo>()
+            .rollback_component_with_entities::<Missile>()
+            .add_systems(Startup, load_missile_assets)
+            .add_systems(
+                Update,
+                (fire_missile, steer_missiles).chain().in_set(ProjectileSet),
+            );
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FireMissileEvent {
+    pub launcher_entity: Entity,
+}
+
+/// Number of missiles left in a launcher.
+#[derive(Component, Debug, Clone)]
+pub struct MissileAmmo(pub u32);
+
+impl Default for MissileAmmo {
+    fn default() -> Self {
+        Self(MISSILE_AMMO)
+    }
+}
+
+#[derive(Component, Clone)]
+pub struct Missile {
+    target: Option<Entity>,
+    /// Velocity of the launcher when the missile was fired, kept on top of its own thrust.
+    launcher_velocity: Vec2,
+}
+
+impl MapEntities for Missile {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.target = self.target.map(|target| entity_mapper.map_entity(target));
+    }
+}
+
+const MISSILE_AMMO: u32 = 3;
+const MISSILE_SPEED: f32 = 300.;
+/// Maximum rotation of the missile in radians per second.
+const MISSILE_TURN_RATE: f32 = 3.;
+const MISSILE_LIFETIME: f32 = 8.;
+const MISSILE_EXPLOSION_RADIUS: f32 = 20.;
+const MISSILE_LENGTH: f32 = 12.;
+const MISSILE_WIDTH: f32 = 6.;
+
+#[derive(Resource)]
+struct MissileAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+fn load_missile_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(MissileAssets {
+        mesh: meshes.add(Triangle2d::new(
+            Vec2::new(0., MISSILE_LENGTH / 2.),
+            Vec2::new(-MISSILE_WIDTH / 2., -MISSILE_LENGTH / 2.),
+            Vec2::new(MISSILE_WIDTH / 2., -MISSILE_LENGTH / 2.),
+        )),
+        material: materials.add(ColorMaterial::from(Color::ORANGE)),
+    });
+}
+
+fn fire_missile(
+    mut commands: Commands,
+    mut fire_missile_events: EventReader<FireMissileEvent>,
+    mut launcher_query: Query<(
+        &Transform,
+        Option<&Velocity>,
+        &mut MissileAmmo,
+        Option<&PlayerId>,
+    )>,
+    meshes: Res<Assets<Mesh>>,
+    missile_assets: Res<MissileAssets>,
+    game_mode: Res<GameMode>,
+) {
+    for FireMissileEvent { launcher_entity } in fire_missile_events.read() {
+        let Ok((launcher_transform, opt_launcher_velocity, mut ammo, opt_player_id)) =
+            launcher_query.get_mut(*launcher_entity)
+        else {
+            continue;
+        };
+
+        if ammo.0 == 0 {
+            continue;
+        }
+        ammo.0 -= 1;
+
+        let direction = launcher_transform
+            .rotation
+            .mul_vec3(Vec3::new(0., 1., 0.))
+            .xy();
+        let position = launcher_transform.translation.xy() + direction * 20.;
+        let launcher_velocity =
+            opt_launcher_velocity.map_or(Vec2::ZERO, |velocity| velocity.linvel);
+        let velocity = direction * MISSILE_SPEED + launcher_velocity;
+
+        let mesh = meshes
+            .get(&missile_assets.mesh)
+            .expect("Missile mesh not found");
+        let collider = mesh_to_collider(mesh).expect("Failed to create collider");
+
+        info!(ammo = ammo.0, "Missile launched");
+        let mut missile_cmd = commands.spawn((
+            Name::new("Missile"),
+            Missile {
+                target: None,
+                launcher_velocity,
+            },
+            Projectile {
+                lifetime: Timer::from_seconds(MISSILE_LIFETIME, TimerMode::Once),
+                explosion_radius: MISSILE_EXPLOSION_RADIUS,
+                owner: *launcher_entity,
+            },
+            MaterialMesh2dBundle {
+                mesh: missile_assets.mesh.clone().into(),
+                material: missile_assets.material.clone(),
+                transform: Transform::from_translation(position.extend(0.))
+                    .with_rotation(launcher_transform.rotation),
+                ..default()
+            },
+            RigidBody::Dynamic,
+            Velocity {
+                linvel: velocity,
+                ..default()
+            },
+            collider,
+            Duplicable,
+            ActiveEvents::COLLISION_EVENTS,
+            ActiveHooks::FILTER_CONTACT_PAIRS,
+            CollisionGroups::new(PROJECTILE_GROUP, projectile_targets(*game_mode)),
+        ));
+        if let Some(&player_id) = opt_player_id {
+            missile_cmd.insert(player_id);
+        }
+    }
+}
+
+fn steer_missiles(
+    mut missile_query: Query<(&mut Missile, &mut Transform, &mut Velocity)>,
+    target_query: Query<(Entity, &GlobalTransform), Or<(With<Asteroid>, With<Ufo>)>>,
+    bounds: Res<Bounds>,
+    time: Res<Time>,
+) {
+    for (mut missile, mut transform, mut velocity) in &mut missile_query {
+        let position = transform.translation.xy();
+
+        if missile
+            .target
+            .is_none_or(|target| !target_query.contains(target))
+        {
+            missile.target = target_query
+                .iter()
+                .min_by(|(_, a), (_, b)| {
+                    let distance_a = bounds.wrapped_distance(position, a.translation().xy());
+                    let distance_b = bounds.wrapped_distance(position, b.translation().xy());
+                    distance_a.total_cmp(&distance_b)
+                })
+                .map(|(entity, _)| entity);
+        }
+
+        // Only the thrust of the missile is steered, not the velocity it inherited
+        let heading = transform.rotation.mul_vec3(Vec3::Y).xy();
+
+        let heading = match missile
+            .target
+            .and_then(|target| target_query.get(target).ok())
+        {
+            Some((_, target_transform)) => {
+                let to_target =
+                    bounds.wrapped_displacement(position, target_transform.translation().xy());
+                turn_towards(heading, to_target, MISSILE_TURN_RATE * time.delta_seconds())
+            }
+            None => heading,
+        };
+
+        velocity.linvel = missile.launcher_velocity + heading * MISSILE_SPEED;
+        velocity.angvel = 0.;
+        transform.rotation = Quat::from_rotation_z(Vec2::Y.angle_between(heading));
+    }
+}
+
+/// Rotates `heading` towards `desired` by at most `max_angle` radians.
+fn turn_towards(heading: Vec2, desired: Vec2, max_angle: f32) -> Vec2 {
+    if desired == Vec2::ZERO {
+        return heading;
+    }
+
+    let angle = heading.angle_between(desired);
+    Vec2::from_angle(angle.clamp(-max_angle, max_angle)).rotate(heading)
+}
@@ -0,0 +1,815 @@
+mod message;
+mod transport;
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use bevy::{
+    app::{App, First, Last, Plugin, PostUpdate, Update},
+    ecs::{
+        query::{Or, With},
+        schedule::{
+            common_conditions::{in_state, not, resource_changed, resource_exists},
+            Condition, IntoSystemConfigs, IntoSystemSetConfigs, NextState, OnEnter, SystemSet,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    log::{error, info, warn},
+    time::{Time, TimeSystem, TimeUpdateStrategy, Virtual},
+    transform::components::Transform,
+    utils::Instant,
+};
+use bevy_rapier2d::{
+    dynamics::Velocity,
+    plugin::{PhysicsSet, RapierConfiguration, TimestepMode},
+};
+
+use crate::{
+    asteroid::Asteroid,
+    edge_wrap::{BoundaryMode, BoundaryModeSettings, Bounds},
+    game_state::{GameMode, GameResult, GameState},
+    input::{
+        apply_player_inputs, InputMode, PlayerInput, PlayerInputSet, PlayerInputs, SampleInputSet,
+    },
+    player::PlayerId,
+    playfield::Playfield,
+    rng::GameRng,
+    ship::Ship,
+    ufo::Ufo,
+    versus::VersusSettings,
+};
+use message::{GameSettings, Message};
+#[cfg(test)]
+use transport::LoopbackTransport;
+use transport::{Transport, UdpTransport};
+
+/// Online games between two peers, started with `--host <local address> <peer address>` or
+/// `--join <local address> <peer address>`.
+///
+/// The peers run the same deterministic simulation in lockstep: a frame runs at most one tick of
+/// fixed length, paced by real time, and a tick only runs once the inputs of both players for it
+/// are known. On other frames the [`SimulationSet`] and the physics are held and virtual time
+/// stands still, while the frames keep rendering. Only inputs are exchanged, checksums of the simulated bodies catch peers that
+/// drifted apart anyway.
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            start_game
+                .in_set(NetSet)
+                .run_if(resource_exists::<Lockstep>),
+        )
+        .add_systems(
+            Update,
+            (
+                require_two_players.run_if(resource_changed::<GameMode>),
+                await_game,
+            )
+                .run_if(resource_exists::<Lockstep>.and_then(not(in_state(GameState::Playing)))),
+        )
+        .add_systems(
+            First,
+            wait_for_peer
+                .before(TimeSystem)
+                .run_if(in_state(GameState::Playing).and_then(resource_exists::<Lockstep>)),
+        )
+        .add_systems(
+            Update,
+            exchange_inputs
+                .in_set(PlayerInputSet)
+                .in_set(SimulationSet)
+                .after(SampleInputSet)
+                .before(apply_player_inputs)
+                .run_if(in_state(GameState::Playing).and_then(resource_exists::<Lockstep>)),
+        )
+        .add_systems(
+            Last,
+            check_sync.run_if(
+                in_state(GameState::Playing)
+                    .and_then(resource_exists::<Lockstep>)
+                    .and_then(tick_ready),
+            ),
+        )
+        .configure_sets(Update, SimulationSet.run_if(tick_ready))
+        .configure_sets(PostUpdate, PhysicsSet::StepSimulation.run_if(tick_ready));
+
+        let config = match NetConfig::from_args(std::env::args().skip(1)) {
+            Ok(Some(config)) => config,
+            Ok(None) => return,
+            Err(error) => {
+                error!(%error, "Invalid network options, playing offline");
+                return;
+            }
+        };
+        let transport = match UdpTransport::bind(config.local_address, config.peer_address) {
+            Ok(transport) => transport,
+            Err(error) => {
+                error!(%error, "Failed to open the connection, playing offline");
+                return;
+            }
+        };
+        info!(?config, "Playing online");
+
+        app.insert_resource(Lockstep::new(Box::new(transport), config.player))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(TICK));
+        if let Some(mut rapier_configuration) = app.world.get_resource_mut::<RapierConfiguration>()
+        {
+            rapier_configuration.timestep_mode = TimestepMode::Fixed {
+                dt: TICK.as_secs_f32(),
+                substeps: 1,
+            };
+        }
+    }
+}
+
+/// Prepares the simulation of an online game, before anything is spawned into it.
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct NetSet;
+
+/// Gameplay systems that advance the simulation, they only run on frames that are [`tick_ready`].
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct SimulationSet;
+
+/// Length of a tick, online games advance one tick per frame at most.
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / 60);
+/// Real time the ticks catch up on at most, so a stall isn't followed by a burst of ticks.
+const MAX_LAG: Duration = Duration::from_millis(100);
+/// Ticks between sampling the input of a player and applying it, which hides the latency of the
+/// connection as long as it's shorter.
+const INPUT_DELAY: u32 = 3;
+/// Inputs repeated in every datagram, so a few lost datagrams don't stall the peer.
+const REDUNDANT_INPUTS: usize = 16;
+/// Ticks between comparing checksums with the peer.
+const CHECKSUM_INTERVAL: u32 = 30;
+/// Checksums are forgotten once they are this many ticks old, in case the one of the peer got
+/// lost.
+const CHECKSUM_HISTORY: u32 = CHECKSUM_INTERVAL * 8;
+const RESEND_INTERVAL: Duration = Duration::from_millis(50);
+/// The peer is considered gone after not hearing from it for this long.
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NetConfig {
+    /// The host plays player one and decides on the game settings.
+    player: PlayerId,
+    local_address: SocketAddr,
+    peer_address: SocketAddr,
+}
+
+impl NetConfig {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let player = match args.next().as_deref() {
+            Some("--host") => PlayerId::One,
+            Some("--join") => PlayerId::Two,
+            _ => return Ok(None),
+        };
+        let mut address = || -> Result<SocketAddr, String> {
+            let arg = args.next().ok_or("Expected a local and a peer address")?;
+            arg.parse()
+                .map_err(|error| format!("Invalid address {arg}: {error}"))
+        };
+
+        Ok(Some(Self {
+            player,
+            local_address: address()?,
+            peer_address: address()?,
+        }))
+    }
+}
+
+/// The peer didn't respond within [`DISCONNECT_TIMEOUT`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disconnected;
+
+/// Connection to the peer of an online game and the state of the lockstep between them.
+#[derive(Resource)]
+pub struct Lockstep {
+    transport: Box<dyn Transport>,
+    local: PlayerId,
+    /// Number and settings of the current game, `None` until the first one starts.
+    game: Option<(u8, GameSettings)>,
+    /// Whether any inputs of the peer for the current game arrived.
+    peer_joined: bool,
+    /// Whether inputs of the peer arrived since the last poll.
+    peer_waiting: bool,
+    /// Whether the simulation advances by a tick this frame: one is due and the inputs of both
+    /// players for it are known.
+    tick_ready: bool,
+    /// Real time the ticks fell behind, a tick is due once it reaches [`TICK`].
+    lag: Duration,
+    last_frame: Instant,
+    tick: u32,
+    /// Inputs of the local player for the ticks starting at `first_local_tick`.
+    local_inputs: VecDeque<PlayerInput>,
+    first_local_tick: u32,
+    remote_inputs: BTreeMap<u32, PlayerInput>,
+    local_checksums: BTreeMap<u32, u64>,
+    remote_checksums: BTreeMap<u32, u64>,
+    desyncs: Vec<u32>,
+    last_received: Instant,
+    last_sent: Instant,
+}
+
+impl Lockstep {
+    pub fn new(transport: Box<dyn Transport>, local: PlayerId) -> Self {
+        Self {
+            transport,
+            local,
+            game: None,
+            peer_joined: false,
+            peer_waiting: false,
+            tick_ready: true,
+            lag: Duration::ZERO,
+            last_frame: Instant::now(),
+            tick: 0,
+            local_inputs: VecDeque::new(),
+            first_local_tick: INPUT_DELAY,
+            remote_inputs: BTreeMap::new(),
+            local_checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            desyncs: Vec::new(),
+            last_received: Instant::now(),
+            last_sent: Instant::now(),
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.local == PlayerId::One
+    }
+
+    fn remote(&self) -> PlayerId {
+        match self.local {
+            PlayerId::One => PlayerId::Two,
+            PlayerId::Two => PlayerId::One,
+        }
+    }
+
+    fn settings(&self) -> Option<GameSettings> {
+        self.game.map(|(_, settings)| settings)
+    }
+
+    /// Starts the next game as the host, the peer follows once the settings reach it.
+    fn host(&mut self, settings: GameSettings) {
+        let game = self.game.map_or(0, |(game, _)| game.wrapping_add(1));
+        self.begin(game, settings);
+        self.send(&Message::Start { game, settings });
+    }
+
+    fn begin(&mut self, game: u8, settings: GameSettings) {
+        self.game = Some((game, settings));
+        self.peer_joined = false;
+        self.tick_ready = true;
+        self.lag = Duration::ZERO;
+        self.tick = 0;
+        self.local_inputs.clear();
+        self.first_local_tick = INPUT_DELAY;
+        self.remote_inputs.clear();
+        self.local_checksums.clear();
+        self.remote_checksums.clear();
+        self.last_received = Instant::now();
+    }
+
+    fn send(&mut self, message: &Message) {
+        self.transport.send(&message.encode());
+        self.last_sent = Instant::now();
+    }
+
+    /// Sends the recent local inputs, and the game settings until the peer has joined.
+    fn send_inputs(&mut self) {
+        let Some((game, settings)) = self.game else {
+            return;
+        };
+        if self.is_host() && !self.peer_joined {
+            self.send(&Message::Start { game, settings });
+        }
+        self.send(&Message::Inputs {
+            game,
+            first_tick: self.first_local_tick,
+            inputs: self.local_inputs.iter().copied().collect(),
+        });
+    }
+
+    /// Handles everything the peer sent. Returns the settings of a game the host started.
+    fn receive(&mut self) -> Option<(u8, GameSettings)> {
+        let mut started = None;
+        while let Some(bytes) = self.transport.receive() {
+            let Some(message) = Message::decode(&bytes) else {
+                warn!("Received an invalid message");
+                continue;
+            };
+            self.last_received = Instant::now();
+
+            let current_game = self.game.map(|(game, _)| game);
+            match message {
+                Message::Start { game, settings } => {
+                    if !self.is_host() && current_game != Some(game) {
+                        started = Some((game, settings));
+                    }
+                }
+                Message::Inputs {
+                    game,
+                    first_tick,
+                    inputs,
+                } if current_game == Some(game) => {
+                    self.peer_joined = true;
+                    self.peer_waiting = true;
+                    for (tick, input) in (first_tick..).zip(inputs) {
+                        if tick >= self.tick {
+                            self.remote_inputs.insert(tick, input);
+                        }
+                    }
+                }
+                Message::Checksum { game, tick, value } if current_game == Some(game) => {
+                    self.remote_checksums.insert(tick, value);
+                    self.compare_checksums(tick);
+                }
+                // Left over from a previous game
+                Message::Inputs { .. } | Message::Checksum { .. } => {}
+            }
+        }
+        started
+    }
+
+    /// Keeps the connection going between games. Returns the settings of a game the host started,
+    /// which the client should join.
+    fn poll(&mut self) -> Option<GameSettings> {
+        let started = self.receive();
+
+        // The peer is still waiting for the last inputs of the game, which got lost
+        if std::mem::take(&mut self.peer_waiting) {
+            self.send_inputs();
+        }
+
+        let (game, settings) = started?;
+        self.begin(game, settings);
+        Some(settings)
+    }
+
+    /// Adds the real time since the last frame to the lag. Returns whether a tick is due, which
+    /// keeps the game speed independent of the frame rate.
+    fn tick_due(&mut self, now: Instant) -> bool {
+        self.lag = (self.lag + now.saturating_duration_since(self.last_frame)).min(MAX_LAG);
+        self.last_frame = now;
+        self.lag >= TICK
+    }
+
+    /// Handles everything the peer sent, without waiting for more. Returns whether the input of
+    /// the peer for the current tick is known.
+    fn poll_remote_input(&mut self) -> Result<bool, Disconnected> {
+        self.receive();
+        if self.tick < INPUT_DELAY || self.remote_inputs.contains_key(&self.tick) {
+            return Ok(true);
+        }
+
+        if self.last_received.elapsed() > DISCONNECT_TIMEOUT {
+            return Err(Disconnected);
+        }
+        if self.last_sent.elapsed() > RESEND_INTERVAL {
+            self.send_inputs();
+        }
+        Ok(false)
+    }
+
+    /// Sends the input of the local player for the current tick. Returns the inputs of both
+    /// players, indexed by [`PlayerId`], or `None` while the input of the peer is still missing.
+    fn exchange(&mut self, local_input: PlayerInput) -> Option<[PlayerInput; 2]> {
+        let tick = self.tick;
+        let remote_input = if tick >= INPUT_DELAY {
+            Some(*self.remote_inputs.get(&tick)?)
+        } else {
+            None
+        };
+
+        self.local_inputs.push_back(local_input);
+        if self.local_inputs.len() > REDUNDANT_INPUTS {
+            self.local_inputs.pop_front();
+            self.first_local_tick += 1;
+        }
+        self.send_inputs();
+
+        let mut inputs = [PlayerInput::default(); 2];
+        if let Some(remote_input) = remote_input {
+            inputs[self.local as usize] =
+                self.local_inputs[(tick - self.first_local_tick) as usize];
+            inputs[self.remote() as usize] = remote_input;
+        }
+        self.remote_inputs.remove(&tick);
+
+        Some(inputs)
+    }
+
+    /// Finishes the current tick, sharing the checksum of the simulation every
+    /// [`CHECKSUM_INTERVAL`] ticks.
+    fn end_tick(&mut self, checksum: impl FnOnce() -> u64) {
+        let tick = self.tick;
+        if tick.is_multiple_of(CHECKSUM_INTERVAL) {
+            let value = checksum();
+            if let Some((game, _)) = self.game {
+                self.send(&Message::Checksum { game, tick, value });
+            }
+            self.local_checksums.insert(tick, value);
+            self.compare_checksums(tick);
+        }
+
+        let oldest = tick.saturating_sub(CHECKSUM_HISTORY);
+        self.local_checksums.retain(|&tick, _| tick >= oldest);
+        self.remote_checksums.retain(|&tick, _| tick >= oldest);
+        self.tick += 1;
+    }
+
+    fn compare_checksums(&mut self, tick: u32) {
+        let (Some(local), Some(remote)) = (
+            self.local_checksums.get(&tick),
+            self.remote_checksums.get(&tick),
+        ) else {
+            return;
+        };
+        if local != remote {
+            self.desyncs.push(tick);
+        }
+        self.local_checksums.remove(&tick);
+        self.remote_checksums.remove(&tick);
+    }
+}
+
+/// Hashes the state of the simulated bodies, independent of the order they are in.
+fn state_checksum<'a>(bodies: impl Iterator<Item = (&'a Transform, Option<&'a Velocity>)>) -> u64 {
+    bodies
+        .map(|(transform, velocity)| {
+            let velocity = velocity.copied().unwrap_or_else(Velocity::zero);
+            let values = [
+                transform.translation.to_array().as_slice(),
+                transform.rotation.to_array().as_slice(),
+                velocity.linvel.to_array().as_slice(),
+                &[velocity.angvel],
+            ]
+            .concat();
+            fnv1a(
+                values
+                    .iter()
+                    .flat_map(|value| value.to_bits().to_le_bytes()),
+            )
+        })
+        .fold(0, u64::wrapping_add)
+}
+
+fn fnv1a(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Online games always have both players.
+fn require_two_players(mut game_mode: ResMut<GameMode>) {
+    if *game_mode == GameMode::Solo {
+        *game_mode = GameMode::Coop;
+    }
+}
+
+fn start_game(
+    mut session: ResMut<Lockstep>,
+    mut rng: ResMut<GameRng>,
+    game_mode: Res<GameMode>,
+    boundary_mode: Res<BoundaryMode>,
+    playfield: Res<Playfield>,
+    versus_settings: Res<VersusSettings>,
+) {
+    if session.is_host() {
+        session.host(GameSettings {
+            seed: rand::random(),
+            game_mode: *game_mode,
+            boundary_mode: *boundary_mode,
+            world_screens: playfield.world_screens,
+            score_limit: versus_settings.score_limit,
+        });
+    }
+
+    let Some(settings) = session.settings() else {
+        return;
+    };
+    rng.reseed(settings.seed);
+    info!(?settings, "Online game started");
+}
+
+/// Joins the games the host starts.
+fn await_game(
+    mut commands: Commands,
+    mut session: ResMut<Lockstep>,
+    input_mode: Option<Res<InputMode>>,
+    mut game_mode: ResMut<GameMode>,
+    mut boundary_mode: ResMut<BoundaryMode>,
+    mut boundary_mode_settings: ResMut<BoundaryModeSettings>,
+    mut playfield: ResMut<Playfield>,
+    mut bounds: ResMut<Bounds>,
+    mut versus_settings: ResMut<VersusSettings>,
+    mut next_gamestate: ResMut<NextState<GameState>>,
+) {
+    let Some(settings) = session.poll() else {
+        return;
+    };
+
+    *game_mode = settings.game_mode;
+    *boundary_mode = settings.boundary_mode;
+    *boundary_mode_settings.get_mut(settings.game_mode) = settings.boundary_mode;
+    playfield.world_screens = settings.world_screens;
+    // The asteroids are spawned before the bounds would follow the playfield
+    bounds.0 = playfield.world_size() / 2.;
+    versus_settings.score_limit = settings.score_limit;
+    // The start screen might not have been clicked through yet
+    if input_mode.is_none() {
+        commands.insert_resource(InputMode::Mouse);
+    }
+
+    next_gamestate.set(GameState::Playing);
+}
+
+/// Whether the simulation advances this frame, which offline is always the case.
+pub fn tick_ready(session: Option<Res<Lockstep>>) -> bool {
+    session.is_none_or(|session| session.tick_ready)
+}
+
+/// Holds the simulation until the next tick is due and the input of the peer for it arrived,
+/// without blocking the frame.
+///
+/// Virtual time stands still meanwhile, so nothing outside of the [`SimulationSet`] moves on and
+/// events stay around until the simulation gets to them.
+fn wait_for_peer(
+    mut commands: Commands,
+    mut session: ResMut<Lockstep>,
+    mut time: ResMut<Time<Virtual>>,
+    mut next_gamestate: ResMut<NextState<GameState>>,
+) {
+    let tick_due = session.tick_due(Instant::now());
+    session.tick_ready = match session.poll_remote_input() {
+        Ok(inputs_ready) => tick_due && inputs_ready,
+        Err(Disconnected) => {
+            warn!("Lost the connection to the peer");
+            commands.insert_resource(GameResult::Disconnected);
+            next_gamestate.set(GameState::Finished);
+            true
+        }
+    };
+
+    if session.tick_ready {
+        session.lag = session.lag.saturating_sub(TICK);
+        time.unpause();
+    } else {
+        time.pause();
+    }
+}
+
+fn exchange_inputs(mut session: ResMut<Lockstep>, mut inputs: ResMut<PlayerInputs>) {
+    // The local player plays with the controls of player one, whichever ship they fly
+    match session.exchange(*inputs.get(PlayerId::One)) {
+        Some(agreed_inputs) => inputs.0 = agreed_inputs,
+        None => *inputs = PlayerInputs::default(),
+    }
+}
+
+/// Ends the game once the simulation of the peers diverged, there is no way to bring them back
+/// in sync.
+fn check_sync(
+    mut commands: Commands,
+    mut session: ResMut<Lockstep>,
+    body_query: Query<(&Transform, Option<&Velocity>), Or<(With<Asteroid>, With<Ship>, With<Ufo>)>>,
+    mut next_gamestate: ResMut<NextState<GameState>>,
+) {
+    session.end_tick(|| state_checksum(body_query.iter()));
+
+    if let Some(&tick) = session.desyncs.first() {
+        error!(tick, "Simulation out of sync with the peer");
+        session.desyncs.clear();
+        commands.insert_resource(GameResult::Desynced);
+        next_gamestate.set(GameState::Finished);
+    }
+}
+
+/// Whether this is the joining peer of an online game, which waits for the host to start games.
+pub fn is_online_client(session: Option<Res<Lockstep>>) -> bool {
+    session.is_some_and(|session| !session.is_host())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{Quat, Vec2, Vec3};
+
+    use crate::input::Steering;
+
+    use super::*;
+
+    fn settings() -> GameSettings {
+        GameSettings {
+            seed: 1234,
+            game_mode: GameMode::Versus,
+            boundary_mode: BoundaryMode::Walls,
+            world_screens: Vec2::new(2., 1.),
+            score_limit: 5,
+        }
+    }
+
+    fn input(tick: u32) -> PlayerInput {
+        PlayerInput {
+            steering: Steering::Turn(tick as f32),
+            throttle: tick.is_multiple_of(2),
+            fire: tick.is_multiple_of(3),
+            missile: tick.is_multiple_of(5),
+        }
+    }
+
+    fn connected_pair() -> (Lockstep, Lockstep) {
+        let (host_transport, client_transport) = LoopbackTransport::pair();
+        let mut host = Lockstep::new(Box::new(host_transport), PlayerId::One);
+        let mut client = Lockstep::new(Box::new(client_transport), PlayerId::Two);
+
+        host.host(settings());
+        assert_eq!(client.poll(), Some(settings()));
+
+        (host, client)
+    }
+
+    /// Runs a tick whose inputs are already known.
+    fn step(peer: &mut Lockstep, input: PlayerInput) -> [PlayerInput; 2] {
+        assert_eq!(peer.poll_remote_input(), Ok(true));
+        peer.exchange(input).unwrap()
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let messages = [
+            Message::Start {
+                game: 3,
+                settings: settings(),
+            },
+            Message::Inputs {
+                game: 3,
+                first_tick: 42,
+                inputs: vec![
+                    input(1),
+                    PlayerInput {
+                        steering: Steering::Towards(Vec2::new(-1.5, 300.)),
+                        ..input(2)
+                    },
+                    PlayerInput {
+                        steering: Steering::Heading(Vec2::new(0.5, -0.5)),
+                        ..input(3)
+                    },
+                    PlayerInput::default(),
+                ],
+            },
+            Message::Checksum {
+                game: 3,
+                tick: 90,
+                value: u64::MAX - 7,
+            },
+        ];
+
+        for message in messages {
+            assert_eq!(Message::decode(&message.encode()), Some(message));
+        }
+    }
+
+    #[test]
+    fn test_invalid_messages_are_rejected() {
+        let bytes = Message::Checksum {
+            game: 0,
+            tick: 1,
+            value: 2,
+        }
+        .encode();
+
+        assert_eq!(Message::decode(&[]), None);
+        assert_eq!(Message::decode(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(Message::decode(&[bytes.as_slice(), &[0]].concat()), None);
+        assert_eq!(Message::decode(&[99, 2, 0]), None);
+    }
+
+    #[test]
+    fn test_peers_agree_on_delayed_inputs() {
+        let (mut host, mut client) = connected_pair();
+
+        for tick in 0..100 {
+            let host_inputs = step(&mut host, input(tick));
+            let client_inputs = step(&mut client, input(tick + 1000));
+            assert_eq!(host_inputs, client_inputs);
+
+            let expected = if tick < INPUT_DELAY {
+                [PlayerInput::default(); 2]
+            } else {
+                [input(tick - INPUT_DELAY), input(tick - INPUT_DELAY + 1000)]
+            };
+            assert_eq!(host_inputs, expected);
+
+            host.end_tick(|| 0);
+            client.end_tick(|| 0);
+        }
+    }
+
+    #[test]
+    fn test_lost_datagrams_are_covered_by_redundant_inputs() {
+        let (mut host, mut client) = connected_pair();
+
+        for tick in 0..50 {
+            // Every other datagram of the client is lost
+            step(&mut client, input(tick));
+            if tick.is_multiple_of(2) {
+                while host.transport.receive().is_some() {}
+            }
+            let host_inputs = step(&mut host, PlayerInput::default());
+            if tick >= INPUT_DELAY {
+                assert_eq!(
+                    host_inputs[PlayerId::Two as usize],
+                    input(tick - INPUT_DELAY)
+                );
+            }
+
+            host.end_tick(|| 0);
+            client.end_tick(|| 0);
+        }
+    }
+
+    #[test]
+    fn test_waiting_for_the_peer_does_not_block() {
+        let (mut host, mut client) = connected_pair();
+
+        for tick in 0..INPUT_DELAY {
+            step(&mut host, input(tick));
+            host.end_tick(|| 0);
+        }
+
+        // The client hasn't sent any inputs yet, so the host has to wait
+        assert_eq!(host.poll_remote_input(), Ok(false));
+        assert_eq!(host.exchange(input(INPUT_DELAY)), None);
+
+        step(&mut client, input(1000));
+        assert_eq!(host.poll_remote_input(), Ok(true));
+        assert_eq!(
+            host.exchange(input(INPUT_DELAY)),
+            Some([input(0), input(1000)])
+        );
+    }
+
+    #[test]
+    fn test_ticks_are_paced_by_real_time() {
+        let (mut host, _client) = connected_pair();
+        let start = host.last_frame;
+
+        // Frames faster than the ticks only run a tick once enough time passed
+        assert!(!host.tick_due(start + TICK / 3));
+        assert!(!host.tick_due(start + TICK * 2 / 3));
+        assert!(host.tick_due(start + TICK));
+        host.lag -= TICK;
+        assert!(!host.tick_due(start + TICK * 4 / 3));
+
+        // A long stall only catches up on a few ticks
+        assert!(host.tick_due(start + Duration::from_secs(5)));
+        assert_eq!(host.lag, MAX_LAG);
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_detected() {
+        let (mut host, mut client) = connected_pair();
+
+        for tick in 0..CHECKSUM_INTERVAL * 3 {
+            step(&mut host, PlayerInput::default());
+            step(&mut client, PlayerInput::default());
+
+            host.end_tick(|| 7);
+            // The client diverges after the first comparison
+            client.end_tick(|| if tick < CHECKSUM_INTERVAL { 7 } else { 8 });
+        }
+        host.receive();
+
+        assert_eq!(host.desyncs, vec![CHECKSUM_INTERVAL, CHECKSUM_INTERVAL * 2]);
+        assert_eq!(
+            client.desyncs,
+            vec![CHECKSUM_INTERVAL, CHECKSUM_INTERVAL * 2]
+        );
+    }
+
+    #[test]
+    fn test_state_checksum() {
+        let a = Transform::from_translation(Vec3::new(1., 2., 0.));
+        let b = Transform::from_rotation(Quat::from_rotation_z(0.5));
+        let velocity = Velocity::linear(Vec2::new(3., 4.));
+
+        let checksum = state_checksum([(&a, Some(&velocity)), (&b, None)].into_iter());
+
+        assert_eq!(
+            checksum,
+            state_checksum([(&b, None), (&a, Some(&velocity))].into_iter())
+        );
+        assert_ne!(
+            checksum,
+            state_checksum([(&a, None), (&b, Some(&velocity))].into_iter())
+        );
+        assert_ne!(
+            checksum,
+            state_checksum([(&a, Some(&velocity))].into_iter())
+        );
+    }
+}
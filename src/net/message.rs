@@ -0,0 +1,168 @@
+use bevy::math::Vec2;
+
+use crate::{
+    codec::{
+        decode_boundary_mode, decode_game_mode, encode_boundary_mode, encode_game_mode,
+        encode_vec2, Reader,
+    },
+    edge_wrap::BoundaryMode,
+    game_state::GameMode,
+    input::{PlayerInput, Steering},
+};
+
+/// Bumped whenever the encoding changes, peers with a different version are ignored.
+const PROTOCOL_VERSION: u8 = 1;
+
+const START_TAG: u8 = 0;
+const INPUTS_TAG: u8 = 1;
+const CHECKSUM_TAG: u8 = 2;
+
+/// Everything the host decides on the start screen that shapes the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameSettings {
+    pub seed: u64,
+    pub game_mode: GameMode,
+    pub boundary_mode: BoundaryMode,
+    pub world_screens: Vec2,
+    pub score_limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// The host started a game. Games are counted, so stray datagrams of the previous game are
+    /// told apart.
+    Start { game: u8, settings: GameSettings },
+    /// Inputs of the sender for consecutive ticks, starting at `first_tick`. Inputs that were
+    /// sent before are repeated, so a lost datagram doesn't stall the peer.
+    Inputs {
+        game: u8,
+        first_tick: u32,
+        inputs: Vec<PlayerInput>,
+    },
+    /// Checksum of the simulation state of the sender after `tick`.
+    Checksum { game: u8, tick: u32, value: u64 },
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![PROTOCOL_VERSION];
+        match self {
+            Message::Start { game, settings } => {
+                bytes.push(START_TAG);
+                bytes.push(*game);
+                bytes.extend(settings.seed.to_le_bytes());
+                bytes.push(encode_game_mode(settings.game_mode));
+                bytes.push(encode_boundary_mode(settings.boundary_mode));
+                encode_vec2(&mut bytes, settings.world_screens);
+                bytes.extend(settings.score_limit.to_le_bytes());
+            }
+            Message::Inputs {
+                game,
+                first_tick,
+                inputs,
+            } => {
+                bytes.push(INPUTS_TAG);
+                bytes.push(*game);
+                bytes.extend(first_tick.to_le_bytes());
+                bytes.push(inputs.len() as u8);
+                for input in inputs {
+                    encode_input(&mut bytes, input);
+                }
+            }
+            Message::Checksum { game, tick, value } => {
+                bytes.push(CHECKSUM_TAG);
+                bytes.push(*game);
+                bytes.extend(tick.to_le_bytes());
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Returns `None` for datagrams that aren't a valid message.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.u8()? != PROTOCOL_VERSION {
+            return None;
+        }
+
+        let message = match reader.u8()? {
+            START_TAG => Message::Start {
+                game: reader.u8()?,
+                settings: GameSettings {
+                    seed: reader.u64()?,
+                    game_mode: decode_game_mode(reader.u8()?)?,
+                    boundary_mode: decode_boundary_mode(reader.u8()?)?,
+                    world_screens: reader.vec2()?,
+                    score_limit: reader.u32()?,
+                },
+            },
+            INPUTS_TAG => {
+                let game = reader.u8()?;
+                let first_tick = reader.u32()?;
+                let count = reader.u8()?;
+                let inputs = (0..count)
+                    .map(|_| decode_input(&mut reader))
+                    .collect::<Option<_>>()?;
+                Message::Inputs {
+                    game,
+                    first_tick,
+                    inputs,
+                }
+            }
+            CHECKSUM_TAG => Message::Checksum {
+                game: reader.u8()?,
+                tick: reader.u32()?,
+                value: reader.u64()?,
+            },
+            _ => return None,
+        };
+
+        reader.0.is_empty().then_some(message)
+    }
+}
+
+const THROTTLE_FLAG: u8 = 1;
+const FIRE_FLAG: u8 = 1 << 1;
+const MISSILE_FLAG: u8 = 1 << 2;
+
+fn encode_input(bytes: &mut Vec<u8>, input: &PlayerInput) {
+    let flags = (input.throttle as u8 * THROTTLE_FLAG)
+        | (input.fire as u8 * FIRE_FLAG)
+        | (input.missile as u8 * MISSILE_FLAG);
+    bytes.push(flags);
+
+    match input.steering {
+        Steering::Keep => bytes.push(0),
+        Steering::Towards(target) => {
+            bytes.push(1);
+            encode_vec2(bytes, target);
+        }
+        Steering::Heading(direction) => {
+            bytes.push(2);
+            encode_vec2(bytes, direction);
+        }
+        Steering::Turn(turn) => {
+            bytes.push(3);
+            bytes.extend(turn.to_le_bytes());
+        }
+    }
+}
+
+fn decode_input(reader: &mut Reader) -> Option<PlayerInput> {
+    let flags = reader.u8()?;
+    let steering = match reader.u8()? {
+        0 => Steering::Keep,
+        1 => Steering::Towards(reader.vec2()?),
+        2 => Steering::Heading(reader.vec2()?),
+        3 => Steering::Turn(reader.f32()?),
+        _ => return None,
+    };
+
+    Some(PlayerInput {
+        steering,
+        throttle: flags & THROTTLE_FLAG != 0,
+        fire: flags & FIRE_FLAG != 0,
+        missile: flags & MISSILE_FLAG != 0,
+    })
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use std::{
+    io::{self, ErrorKind},
+    net::{SocketAddr, UdpSocket},
+};
+
+use bevy::log::warn;
+
+/// Unreliable, unordered datagrams to and from the peer of an online game.
+pub trait Transport: Send + Sync {
+    /// Sends a datagram to the peer, it might never arrive.
+    fn send(&mut self, bytes: &[u8]);
+
+    /// The next datagram received from the peer, without blocking.
+    fn receive(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Largest datagram that is received, larger ones are truncated.
+const MAX_DATAGRAM_SIZE: usize = 1500;
+
+pub struct UdpTransport {
+    socket: UdpSocket,
+    buffer: [u8; MAX_DATAGRAM_SIZE],
+}
+
+impl UdpTransport {
+    pub fn bind(local: SocketAddr, peer: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(peer)?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            buffer: [0; MAX_DATAGRAM_SIZE],
+        })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, bytes: &[u8]) {
+        match self.socket.send(bytes) {
+            Ok(_) => {}
+            // The peer isn't listening yet, the datagram is resent until it is
+            Err(error) if error.kind() == ErrorKind::ConnectionRefused => {}
+            Err(error) => warn!(%error, "Failed to send datagram"),
+        }
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        match self.socket.recv(&mut self.buffer) {
+            Ok(length) => Some(self.buffer[..length].to_vec()),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::ConnectionRefused
+                ) =>
+            {
+                None
+            }
+            Err(error) => {
+                warn!(%error, "Failed to receive datagram");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+type Queue = Arc<Mutex<VecDeque<Vec<u8>>>>;
+
+/// Both ends of an in-process connection, for running two peers side by side in tests.
+#[cfg(test)]
+pub struct LoopbackTransport {
+    outgoing: Queue,
+    incoming: Queue,
+}
+
+#[cfg(test)]
+impl LoopbackTransport {
+    pub fn pair() -> (Self, Self) {
+        let a_to_b = Queue::default();
+        let b_to_a = Queue::default();
+
+        (
+            Self {
+                outgoing: a_to_b.clone(),
+                incoming: b_to_a.clone(),
+            },
+            Self {
+                outgoing: b_to_a,
+                incoming: a_to_b,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+impl Transport for LoopbackTransport {
+    fn send(&mut self, bytes: &[u8]) {
+        self.outgoing.lock().unwrap().push_back(bytes.to_vec());
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        self.incoming.lock().unwrap().pop_front()
+    }
+}
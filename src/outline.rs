@@ -0,0 +1,196 @@
+use bevy::{
+    app::{App, Plugin, PostUpdate, Update},
+    asset::{Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Changed, Or, With, Without},
+        schedule::{common_conditions::resource_equals, IntoSystemConfigs, SystemSet},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    gizmos::{
+        config::{GizmoConfig, GizmoConfigGroup},
+        gizmos::Gizmos,
+        AppGizmoBuilder,
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    math::{Vec2, Vec3Swizzles},
+    reflect::Reflect,
+    render::{color::Color, mesh::Mesh},
+    sprite::{ColorMaterial, Mesh2dHandle},
+    transform::{components::GlobalTransform, TransformSystem},
+    utils::default,
+};
+
+use crate::{edge_wrap::WrapGhost, mesh_utils::boundary_edges, rollback::RollbackAppExt};
+
+/// Draws the outlines of [`Outlined`] entities as glowing lines when the [`RenderStyle`] is
+/// [`RenderStyle::Outline`], for the look of the vector display of the original arcade game.
+pub struct OutlinePlugin;
+
+impl Plugin for OutlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RenderStyle>()
+            .rollback_component::<Outlined>()
+            .rollback_component::<OutlineEdges>()
+            .insert_gizmo_group(
+                OutlineGizmos,
+                GizmoConfig {
+                    line_width: OUTLINE_WIDTH,
+                    ..default()
+                },
+            )
+            .insert_gizmo_group(
+                OutlineGlowGizmos,
+                GizmoConfig {
+                    line_width: OUTLINE_GLOW_WIDTH,
+                    ..default()
+                },
+            )
+            .add_systems(
+                Update,
+                (toggle_render_style, update_outline_edges, sync_fill_alpha)
+                    .chain()
+                    .in_set(OutlineSet),
+            )
+            .add_systems(
+                PostUpdate,
+                draw_outlines
+                    .run_if(resource_equals(RenderStyle::Outline))
+                    .after(TransformSystem::TransformPropagate)
+                    .in_set(OutlineSet),
+            );
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct OutlineSet;
+
+const OUTLINE_WIDTH: f32 = 1.5;
+const OUTLINE_GLOW_WIDTH: f32 = 6.;
+const OUTLINE_GLOW_ALPHA: f32 = 0.25;
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// Meshes are filled with their material.
+    #[default]
+    Filled,
+    /// Only the boundary edges of meshes are drawn, in the color of their material.
+    Outline,
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct OutlineGizmos;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct OutlineGlowGizmos;
+
+/// Marks an entity whose mesh is drawn as an outline in [`RenderStyle::Outline`].
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct Outlined;
+
+/// Boundary edges of the entity's mesh, in the space of the mesh.
+#[derive(Component, Debug, Clone)]
+struct OutlineEdges(Vec<[Vec2; 2]>);
+
+fn toggle_render_style(
+    mut render_style: ResMut<RenderStyle>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        *render_style = match *render_style {
+            RenderStyle::Filled => RenderStyle::Outline,
+            RenderStyle::Outline => RenderStyle::Filled,
+        };
+        info!(render_style = ?*render_style, "Render style changed");
+    }
+}
+
+/// Recomputes the outline whenever the mesh of an entity is replaced, e.g. when it is damaged.
+///
+/// Meshes that are still loading are retried on the next frame.
+fn update_outline_edges(
+    mut commands: Commands,
+    outlined_query: Query<
+        (Entity, &Mesh2dHandle),
+        (
+            With<Outlined>,
+            Or<(Changed<Mesh2dHandle>, Without<OutlineEdges>)>,
+        ),
+    >,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for (entity, mesh_handle) in &outlined_query {
+        let Some(mesh) = meshes.get(&mesh_handle.0) else {
+            continue;
+        };
+
+        commands
+            .entity(entity)
+            .insert(OutlineEdges(boundary_edges(mesh)));
+    }
+}
+
+/// Hides the fill of outlined entities by making their material transparent.
+///
+/// Ghosts at the edges of the map share the material of their original, so they follow along.
+fn sync_fill_alpha(
+    outlined_query: Query<&Handle<ColorMaterial>, With<Outlined>>,
+    render_style: Res<RenderStyle>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let fill_alpha = match *render_style {
+        RenderStyle::Filled => 1.,
+        RenderStyle::Outline => 0.,
+    };
+
+    for material_handle in &outlined_query {
+        let needs_update = materials
+            .get(material_handle)
+            .is_some_and(|material| material.color.a() != fill_alpha);
+        if !needs_update {
+            continue;
+        }
+
+        if let Some(material) = materials.get_mut(material_handle) {
+            material.color.set_a(fill_alpha);
+        }
+    }
+}
+
+fn draw_outlines(
+    mut gizmos: Gizmos<OutlineGizmos>,
+    mut glow_gizmos: Gizmos<OutlineGlowGizmos>,
+    outlined_query: Query<
+        (&GlobalTransform, &OutlineEdges, &Handle<ColorMaterial>),
+        With<Outlined>,
+    >,
+    ghost_query: Query<(&WrapGhost, &GlobalTransform)>,
+    materials: Res<Assets<ColorMaterial>>,
+) {
+    let mut draw = |transform: &GlobalTransform,
+                    edges: &OutlineEdges,
+                    material_handle: &Handle<ColorMaterial>| {
+        let color = materials
+            .get(material_handle)
+            .map_or(Color::WHITE, |material| material.color.with_a(1.));
+
+        for [from, to] in &edges.0 {
+            let from = transform.transform_point(from.extend(0.)).xy();
+            let to = transform.transform_point(to.extend(0.)).xy();
+            glow_gizmos.line_2d(from, to, color.with_a(OUTLINE_GLOW_ALPHA));
+            gizmos.line_2d(from, to, color);
+        }
+    };
+
+    for (transform, edges, material_handle) in &outlined_query {
+        draw(transform, edges, material_handle);
+    }
+
+    for (ghost, transform) in &ghost_query {
+        if let Ok((_, edges, material_handle)) = outlined_query.get(ghost.original) {
+            draw(transform, edges, material_handle);
+        }
+    }
+}
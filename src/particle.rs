@@ -0,0 +1,277 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        schedule::{IntoSystemConfigs, SystemSet},
+        system::{Commands, Query, Res},
+    },
+    math::{Vec2, Vec3Swizzles, Vec4},
+    render::color::Color,
+    sprite::{Sprite, SpriteBundle},
+    time::{Time, Timer, TimerMode},
+    transform::components::{GlobalTransform, Transform},
+    utils::default,
+};
+use bevy_rapier2d::dynamics::Velocity;
+use rand::Rng;
+
+use crate::{
+    edge_wrap::{Duplicable, WrapExtent},
+    rollback::RollbackAppExt,
+};
+
+/// Lightweight CPU particles, drawn as plain sprites and moved without physics.
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.rollback_component::<ParticleEmitter>().add_systems(
+            Update,
+            (emit_particles, update_particles)
+                .chain()
+                .in_set(ParticleSet),
+        );
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct ParticleSet;
+
+/// Particles are drawn behind the ships and asteroids.
+const PARTICLE_Z: f32 = -2.;
+
+/// How particles of one kind look and move over their lifetime.
+#[derive(Debug, Clone, Copy)]
+pub struct ParticleSettings {
+    pub min_lifetime: f32,
+    pub max_lifetime: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Angle of the cone the particles are emitted in, in radians.
+    pub spread: f32,
+    /// Fraction of the velocity lost per second.
+    pub drag: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+}
+
+pub const THRUSTER_EXHAUST: ParticleSettings = ParticleSettings {
+    min_lifetime: 0.2,
+    max_lifetime: 0.5,
+    min_speed: 150.,
+    max_speed: 250.,
+    spread: 0.4,
+    drag: 2.,
+    start_color: Color::rgba(1., 0.8, 0.3, 1.),
+    end_color: Color::rgba(1., 0.1, 0., 0.),
+    start_size: 4.,
+    end_size: 1.,
+};
+
+pub const IMPACT_SPARKS: ParticleSettings = ParticleSettings {
+    min_lifetime: 0.1,
+    max_lifetime: 0.4,
+    min_speed: 100.,
+    max_speed: 300.,
+    spread: 1.5,
+    drag: 4.,
+    start_color: Color::rgba(1., 1., 0.8, 1.),
+    end_color: Color::rgba(1., 0.6, 0.2, 0.),
+    start_size: 2.,
+    end_size: 1.,
+};
+
+/// Explosion bursts are scaled by the radius of the explosion, see [`spawn_particle_burst`].
+pub const EXPLOSION_BURST: ParticleSettings = ParticleSettings {
+    min_lifetime: 0.3,
+    max_lifetime: 0.8,
+    min_speed: 5.,
+    max_speed: 20.,
+    spread: TAU,
+    drag: 3.,
+    start_color: Color::rgba(1., 0.9, 0.5, 1.),
+    end_color: Color::rgba(0.8, 0.1, 0., 0.),
+    start_size: 0.8,
+    end_size: 0.2,
+};
+
+#[derive(Component, Debug)]
+pub struct Particle {
+    velocity: Vec2,
+    lifetime: Timer,
+    settings: ParticleSettings,
+    scale: f32,
+}
+
+/// Continuously emits particles while active, inheriting the velocity of its entity.
+#[derive(Component, Debug, Clone)]
+pub struct ParticleEmitter {
+    pub settings: ParticleSettings,
+    /// Particles per second.
+    pub rate: f32,
+    /// Emission point in the space of the entity.
+    pub offset: Vec2,
+    /// Emission direction in the space of the entity.
+    pub direction: Vec2,
+    pub active: bool,
+    /// Particles owed from previous frames, to emit at a steady rate regardless of frame time.
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(settings: ParticleSettings, rate: f32, offset: Vec2, direction: Vec2) -> Self {
+        Self {
+            settings,
+            rate,
+            offset,
+            direction,
+            active: false,
+            pending: 0.,
+        }
+    }
+}
+
+/// A one-off burst of particles.
+#[derive(Debug, Clone, Copy)]
+pub struct ParticleBurst {
+    pub settings: ParticleSettings,
+    pub count: usize,
+    pub position: Vec2,
+    /// Velocity added to every particle, e.g. of the object that exploded.
+    pub velocity: Vec2,
+    pub direction: Vec2,
+    /// Multiplies the speed and size of the particles.
+    pub scale: f32,
+}
+
+pub fn spawn_particle_burst(commands: &mut Commands, rng: &mut impl Rng, burst: ParticleBurst) {
+    let particles = (0..burst.count)
+        .map(|_| {
+            create_particle(
+                rng,
+                &burst.settings,
+                burst.position,
+                burst.velocity,
+                burst.direction,
+                burst.scale,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    commands.spawn_batch(particles);
+}
+
+fn create_particle(
+    rng: &mut impl Rng,
+    settings: &ParticleSettings,
+    position: Vec2,
+    base_velocity: Vec2,
+    direction: Vec2,
+    scale: f32,
+) -> (Particle, Duplicable, WrapExtent, SpriteBundle) {
+    let half_spread = settings.spread / 2.;
+    let angle = if half_spread > 0. {
+        rng.gen_range(-half_spread..=half_spread)
+    } else {
+        0.
+    };
+    let direction = Vec2::from_angle(angle).rotate(direction.try_normalize().unwrap_or(Vec2::Y));
+    let speed = rng.gen_range(settings.min_speed..=settings.max_speed.max(settings.min_speed));
+    let lifetime = rng.gen_range(settings.min_lifetime..=settings.max_lifetime);
+
+    (
+        Particle {
+            velocity: base_velocity + direction * speed * scale,
+            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+            settings: *settings,
+            scale,
+        },
+        Duplicable,
+        WrapExtent(Vec2::splat(
+            settings.start_size.max(settings.end_size) * scale / 2.,
+        )),
+        SpriteBundle {
+            sprite: Sprite {
+                color: settings.start_color,
+                custom_size: Some(Vec2::splat(settings.start_size * scale)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(PARTICLE_Z)),
+            ..default()
+        },
+    )
+}
+
+fn emit_particles(
+    mut commands: Commands,
+    mut emitter_query: Query<(&GlobalTransform, &mut ParticleEmitter, Option<&Velocity>)>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (transform, mut emitter, opt_velocity) in &mut emitter_query {
+        if !emitter.active {
+            emitter.pending = 0.;
+            continue;
+        }
+
+        emitter.pending += emitter.rate * time.delta_seconds();
+        let count = emitter.pending.floor();
+        emitter.pending -= count;
+
+        let position = transform.transform_point(emitter.offset.extend(0.)).xy();
+        let direction = transform
+            .affine()
+            .transform_vector3(emitter.direction.extend(0.))
+            .xy();
+        let velocity = opt_velocity.map_or(Vec2::ZERO, |velocity| velocity.linvel);
+
+        spawn_particle_burst(
+            &mut commands,
+            &mut rng,
+            ParticleBurst {
+                settings: emitter.settings,
+                count: count as usize,
+                position,
+                velocity,
+                direction,
+                scale: 1.,
+            },
+        );
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    let delta_seconds = time.delta_seconds();
+
+    for (entity, mut particle, mut transform, mut sprite) in &mut particle_query {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let drag = (1. - particle.settings.drag * delta_seconds).max(0.);
+        particle.velocity *= drag;
+        transform.translation += (particle.velocity * delta_seconds).extend(0.);
+
+        let t = particle.lifetime.fraction();
+        let settings = &particle.settings;
+        sprite.color = lerp_color(settings.start_color, settings.end_color, t);
+        let size = settings.start_size + (settings.end_size - settings.start_size) * t;
+        sprite.custom_size = Some(Vec2::splat(size * particle.scale));
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let [from, to] = [from, to].map(|color| Vec4::from(color.as_rgba_f32()));
+    Color::rgba_from_array(from.lerp(to, t))
+}
@@ -1,19 +1,229 @@
 use bevy::{
+    app::{App, Plugin, Update},
     core::Name,
-    ecs::{component::Component, system::Commands},
+    ecs::{
+        component::Component,
+        event::{Event, EventReader},
+        query::With,
+        schedule::{common_conditions::in_state, IntoSystemConfigs, OnEnter, SystemSet},
+        system::{Commands, EntityCommands, Query, Res, ResMut, Resource},
+    },
+    math::Vec2,
+    render::color::Color,
+    time::{Time, Timer, TimerMode},
     transform::components::Transform,
+    utils::default,
 };
 
-use crate::ship::SpawnShipExt;
+use crate::{
+    game_state::{GameMode, GameState},
+    missile::MissileAmmo,
+    rollback::RollbackAppExt,
+    save::is_new_game,
+    ship::{Ship, SpawnShipExt},
+    turret::Weapon,
+};
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_event::<ScoreEvent>()
+            .rollback_component::<Player>()
+            .rollback_component::<PlayerId>()
+            .rollback_resource::<Players>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                spawn_players.run_if(is_new_game),
+            )
+            .add_systems(
+                Update,
+                (add_score, respawn_players)
+                    .chain()
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(PlayerSet),
+            );
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct PlayerSet;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Player;
 
-pub fn spawn_player(mut commands: Commands) {
-    {
-        let transform = Transform::default();
-        let mut ship_cmd = commands.spawn_ship(transform);
-        // let ship_entity = spawn_ship(commands, meshes, materials, transform);
-        ship_cmd.insert((Name::new("Player"), Player));
+/// Which of the local players a ship belongs to. Projectiles carry the id of the ship that fired
+/// them, so hits are scored for the right player.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayerId {
+    /// Flies with the mouse or touch.
+    One,
+    /// Flies with the keyboard or a gamepad.
+    Two,
+}
+
+impl PlayerId {
+    pub const ALL: [PlayerId; 2] = [PlayerId::One, PlayerId::Two];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayerId::One => "Player 1",
+            PlayerId::Two => "Player 2",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PlayerId::One => Color::WHITE,
+            PlayerId::Two => Color::rgb(0.4, 0.8, 1.),
+        }
+    }
+
+    /// Where the ship of the player appears, side by side when there is more than one player.
+    fn spawn_position(self, player_count: usize) -> Vec2 {
+        if player_count < 2 {
+            return Vec2::ZERO;
+        }
+
+        match self {
+            PlayerId::One => Vec2::new(-PLAYER_SPAWN_SPACING / 2., 0.),
+            PlayerId::Two => Vec2::new(PLAYER_SPAWN_SPACING / 2., 0.),
+        }
+    }
+}
+
+/// Score and lives of a player, kept across the ships they fly.
+#[derive(Debug, Clone)]
+pub struct PlayerStats {
+    pub id: PlayerId,
+    pub score: u32,
+    /// Ships left, including the one currently flying.
+    pub lives: u32,
+    /// Versus rounds the player survived as the last one standing.
+    pub rounds_won: u32,
+    /// Runs while the player waits for their next ship.
+    pub respawn_timer: Option<Timer>,
+}
+
+impl PlayerStats {
+    fn new(id: PlayerId, lives: u32) -> Self {
+        Self {
+            id,
+            score: 0,
+            lives,
+            rounds_won: 0,
+            respawn_timer: None,
+        }
+    }
+}
+
+/// Everyone playing the current game.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct Players(pub Vec<PlayerStats>);
+
+impl Players {
+    pub fn get(&self, id: PlayerId) -> Option<&PlayerStats> {
+        self.0.iter().find(|stats| stats.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: PlayerId) -> Option<&mut PlayerStats> {
+        self.0.iter_mut().find(|stats| stats.id == id)
+    }
+
+    /// Whether every player has lost their last ship.
+    pub fn all_out_of_lives(&self) -> bool {
+        self.0.iter().all(|stats| stats.lives == 0)
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ScoreEvent {
+    pub player: PlayerId,
+    pub points: u32,
+}
+
+pub const ASTEROID_HIT_SCORE: u32 = 10;
+pub const UFO_DESTROYED_SCORE: u32 = 100;
+
+const PLAYER_WEAPON_RECOIL: f32 = 200.;
+const PLAYER_LIVES: u32 = 3;
+const PLAYER_RESPAWN_DELAY: f32 = 2.;
+const PLAYER_SPAWN_SPACING: f32 = 80.;
+
+fn spawn_players(mut commands: Commands, game_mode: Res<GameMode>) {
+    let player_count = game_mode.player_count();
+    // A round of versus ends with the first ship lost
+    let lives = match *game_mode {
+        GameMode::Versus => 1,
+        GameMode::Solo | GameMode::Coop => PLAYER_LIVES,
     };
+    let players = PlayerId::ALL[..player_count]
+        .iter()
+        .map(|&id| {
+            spawn_player_ship(&mut commands, id, player_count);
+            PlayerStats::new(id, lives)
+        })
+        .collect();
+
+    commands.insert_resource(Players(players));
+}
+
+pub fn spawn_player_ship<'a>(
+    commands: &'a mut Commands,
+    id: PlayerId,
+    player_count: usize,
+) -> EntityCommands<'a> {
+    let transform = Transform::from_translation(id.spawn_position(player_count).extend(0.));
+    let mut ship = commands.spawn_ship(transform, id.color());
+    ship.insert((
+        Name::new(id.name()),
+        Player,
+        id,
+        MissileAmmo::default(),
+        Weapon {
+            recoil: PLAYER_WEAPON_RECOIL,
+            ..default()
+        },
+    ));
+    ship
+}
+
+fn add_score(mut score_events: EventReader<ScoreEvent>, mut players: ResMut<Players>) {
+    for event in score_events.read() {
+        if let Some(stats) = players.get_mut(event.player) {
+            stats.score += event.points;
+        }
+    }
+}
+
+/// Takes a life from players whose ship is gone and brings them back after a short delay.
+fn respawn_players(
+    mut commands: Commands,
+    mut players: ResMut<Players>,
+    ship_query: Query<&PlayerId, With<Ship>>,
+    game_mode: Res<GameMode>,
+    time: Res<Time>,
+) {
+    for stats in &mut players.0 {
+        if stats.lives == 0 || ship_query.iter().any(|&id| id == stats.id) {
+            continue;
+        }
+
+        match &mut stats.respawn_timer {
+            None => {
+                stats.lives -= 1;
+                if stats.lives > 0 {
+                    stats.respawn_timer =
+                        Some(Timer::from_seconds(PLAYER_RESPAWN_DELAY, TimerMode::Once));
+                }
+            }
+            Some(timer) => {
+                if timer.tick(time.delta()).finished() {
+                    stats.respawn_timer = None;
+                    spawn_player_ship(&mut commands, stats.id, game_mode.player_count());
+                }
+            }
+        }
+    }
 }
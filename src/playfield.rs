@@ -0,0 +1,154 @@
+use bevy::{
+    app::{App, First, Plugin, Startup},
+    core_pipeline::core_2d::Camera2dBundle,
+    ecs::{
+        component::Component,
+        query::With,
+        schedule::{common_conditions::resource_changed, IntoSystemConfigs},
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::{UVec2, Vec2},
+    render::{
+        camera::{Camera, OrthographicProjection, ScalingMode, Viewport},
+        view::RenderLayers,
+    },
+    transform::components::GlobalTransform,
+    utils::default,
+    window::{PrimaryWindow, Window},
+};
+
+use crate::edge_wrap::Bounds;
+
+/// Keeps the world the same size on every device and fits the camera to the window instead,
+/// with black bars where the aspect ratio of the window differs from the playfield.
+pub struct PlayfieldPlugin;
+
+impl Plugin for PlayfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Playfield>()
+            .add_systems(Startup, spawn_letterbox_camera)
+            .add_systems(
+                First,
+                (
+                    sync_bounds_to_playfield.run_if(resource_changed::<Playfield>),
+                    fit_camera_to_window,
+                ),
+            );
+    }
+}
+
+const DEFAULT_PLAYFIELD_SIZE: Vec2 = Vec2::new(1920., 1080.);
+/// The letterbox camera renders first, before any camera showing the world.
+const LETTERBOX_CAMERA_ORDER: isize = -10;
+/// The letterbox camera only clears the window, nothing is rendered on its layer.
+const LETTERBOX_LAYER: u8 = RenderLayers::TOTAL_LAYERS as u8 - 1;
+
+/// Logical size of the visible part of the world, independent of the window resolution.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Playfield {
+    pub size: Vec2,
+    /// Size of the whole world in screens, larger worlds are scrolled through by the camera.
+    pub world_screens: Vec2,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_PLAYFIELD_SIZE,
+            world_screens: Vec2::ONE,
+        }
+    }
+}
+
+impl Playfield {
+    pub fn world_size(&self) -> Vec2 {
+        self.size * self.world_screens
+    }
+
+    pub fn is_large_world(&self) -> bool {
+        self.world_screens != Vec2::ONE
+    }
+}
+
+/// The camera the game is rendered with.
+#[derive(Component, Debug)]
+pub struct MainCamera;
+
+fn spawn_letterbox_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: LETTERBOX_CAMERA_ORDER,
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(LETTERBOX_LAYER),
+    ));
+}
+
+fn sync_bounds_to_playfield(mut bounds: ResMut<Bounds>, playfield: Res<Playfield>) {
+    bounds.0 = playfield.world_size() / 2.;
+}
+
+/// Scales the main camera so the whole playfield fits into the window and limits its viewport
+/// to the playfield.
+fn fit_camera_to_window(
+    mut camera_query: Query<(&mut Camera, &mut OrthographicProjection), With<MainCamera>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    playfield: Res<Playfield>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    if window_size.min_element() == 0 {
+        return;
+    }
+
+    let scale = (window_size.as_vec2() / playfield.size).min_element();
+    let viewport_size = (playfield.size * scale)
+        .round()
+        .as_uvec2()
+        .clamp(UVec2::ONE, window_size);
+    let viewport_position = (window_size - viewport_size) / 2;
+
+    for (mut camera, mut projection) in &mut camera_query {
+        let viewport_changed = camera.viewport.as_ref().is_none_or(|viewport| {
+            viewport.physical_position != viewport_position
+                || viewport.physical_size != viewport_size
+        });
+        if viewport_changed {
+            camera.viewport = Some(Viewport {
+                physical_position: viewport_position,
+                physical_size: viewport_size,
+                ..default()
+            });
+        }
+
+        let scaling_changed = !matches!(
+            projection.scaling_mode,
+            ScalingMode::Fixed { width, height }
+                if width == playfield.size.x && height == playfield.size.y
+        );
+        if scaling_changed {
+            projection.scaling_mode = ScalingMode::Fixed {
+                width: playfield.size.x,
+                height: playfield.size.y,
+            };
+        }
+    }
+}
+
+/// Converts a position in the window, like the cursor position, to the world.
+pub fn window_to_world(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    window_position: Vec2,
+) -> Option<Vec2> {
+    let viewport_position = window_position
+        - camera
+            .logical_viewport_rect()
+            .map_or(Vec2::ZERO, |rect| rect.min);
+    camera.viewport_to_world_2d(camera_transform, viewport_position)
+}
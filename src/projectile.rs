@@ -1,55 +1,122 @@
 use crate::{
     asteroid::{Asteroid, SplitAsteroidEvent, ASTEROID_GROUP},
-    edge_wrap::{get_original_entities, Duplicable, Duplicate},
+    edge_wrap::Duplicable,
     explosion::ExplosionEvent,
+    game_state::GameMode,
+    net::SimulationSet,
+    particle::{spawn_particle_burst, ParticleBurst, IMPACT_SPARKS},
+    player::{PlayerId, ScoreEvent, ASTEROID_HIT_SCORE, UFO_DESTROYED_SCORE},
+    rollback::RollbackAppExt,
+    ship::{Ship, ShipHitEvent, SHIP_GROUP},
     ufo::{Ufo, UfoDestroyedEvent, UFO_GROUP},
-    utils::{contact_position_and_normal, mesh_to_collider},
+    utils::{contact_position_and_normal, local_contact_point, mesh_to_collider},
+};
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::{EntityMapper, MapEntities},
+        system::{EntityCommands, SystemParam},
+    },
+    time::Timer,
 };
-use bevy::{ecs::component::Component, time::Timer};
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use bevy_rapier2d::{
     dynamics::{RigidBody, Velocity},
-    geometry::{ActiveEvents, CollisionGroups, Group},
+    geometry::{ActiveEvents, ActiveHooks, CollisionGroups, Group},
+    pipeline::{BevyPhysicsHooks, PairFilterContextView},
     plugin::RapierContext,
     prelude::CollisionEvent,
+    rapier::geometry::SolverFlags,
 };
 
 pub struct ProjectilePlugin;
 
 impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ProjectileExplosionEvent>().add_systems(
-            Update,
-            (
-                projectile_timer,
-                (projectile_asteroid_collision, projectile_ufo_collision),
-                projectile_explosion,
-            )
-                .chain()
-                .after(ProjectileSet),
-        );
+        app.add_event::<ProjectileExplosionEvent>()
+            .rollback_component_with_entities::<Projectile>()
+            .add_systems(
+                Update,
+                (
+                    projectile_timer,
+                    (
+                        projectile_asteroid_collision,
+                        projectile_ufo_collision,
+                        projectile_ship_collision,
+                    ),
+                    projectile_explosion,
+                )
+                    .chain()
+                    .after(ProjectileSet)
+                    .in_set(SimulationSet),
+            );
     }
 }
 
 #[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
 pub struct ProjectileSet;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Projectile {
     pub lifetime: Timer,
+    pub explosion_radius: f32,
+    /// Entity that fired the projectile, which it never collides with.
+    pub owner: Entity,
+}
+
+impl MapEntities for Projectile {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.owner = entity_mapper.map_entity(self.owner);
+    }
 }
 
 pub const PROJECTILE_GROUP: Group = Group::GROUP_2;
 pub const PROJECTILE_LIFETIME: f32 = 5.;
 pub const PROJECTILE_RADIUS: f32 = 4.;
+const IMPACT_SPARK_COUNT: usize = 12;
+
+/// What projectiles collide with, ships are only fair game in versus.
+pub fn projectile_targets(game_mode: GameMode) -> Group {
+    match game_mode {
+        GameMode::Versus => ASTEROID_GROUP | UFO_GROUP | SHIP_GROUP,
+        GameMode::Solo | GameMode::Coop => ASTEROID_GROUP | UFO_GROUP,
+    }
+}
 
-pub fn spawn_projectile(
-    commands: &mut Commands,
+/// Keeps projectiles from colliding with their owner, for colliders with
+/// [`ActiveHooks::FILTER_CONTACT_PAIRS`].
+#[derive(SystemParam)]
+pub struct ProjectileOwnerFilter<'w, 's> {
+    projectile_query: Query<'w, 's, &'static Projectile>,
+}
+
+impl BevyPhysicsHooks for ProjectileOwnerFilter<'_, '_> {
+    fn filter_contact_pair(&self, context: PairFilterContextView) -> Option<SolverFlags> {
+        let owns = |projectile_entity, other_entity| {
+            self.projectile_query
+                .get(projectile_entity)
+                .is_ok_and(|projectile| projectile.owner == other_entity)
+        };
+
+        if owns(context.collider1(), context.collider2())
+            || owns(context.collider2(), context.collider1())
+        {
+            None
+        } else {
+            Some(SolverFlags::COMPUTE_IMPULSES)
+        }
+    }
+}
+
+pub fn spawn_projectile<'a>(
+    commands: &'a mut Commands,
     meshes: &mut Assets<Mesh>,
     materials: &mut Assets<ColorMaterial>,
+    owner: Entity,
     position: Vec2,
     velocity: Vec2,
-) {
+    targets: Group,
+) -> EntityCommands<'a> {
     let projectile_shape = Circle::new(PROJECTILE_RADIUS);
 
     let projectile_mesh = Mesh::from(projectile_shape);
@@ -57,6 +124,8 @@ pub fn spawn_projectile(
     commands.spawn((
         Projectile {
             lifetime: Timer::from_seconds(PROJECTILE_LIFETIME, TimerMode::Once),
+            explosion_radius: PROJECTILE_RADIUS,
+            owner,
         },
         MaterialMesh2dBundle {
             mesh: meshes.add(projectile_mesh).into(),
@@ -72,8 +141,9 @@ pub fn spawn_projectile(
         collider,
         Duplicable,
         ActiveEvents::COLLISION_EVENTS,
-        CollisionGroups::new(PROJECTILE_GROUP, ASTEROID_GROUP | UFO_GROUP),
-    ));
+        ActiveHooks::FILTER_CONTACT_PAIRS,
+        CollisionGroups::new(PROJECTILE_GROUP, targets),
+    ))
 }
 
 fn projectile_timer(
@@ -95,31 +165,37 @@ pub struct ProjectileExplosionEvent {
 }
 
 fn projectile_asteroid_collision(
+    mut commands: Commands,
     rapier_context: Res<RapierContext>,
     mut collision_events: EventReader<CollisionEvent>,
-    projectile_query: Query<&Projectile>,
+    projectile_query: Query<(&Projectile, Option<&Velocity>, Option<&PlayerId>)>,
     mut asteroid_query: Query<(&Transform, Option<&Velocity>), With<Asteroid>>,
-    duplicate_query: Query<&Duplicate>,
     transform_query: Query<&GlobalTransform>,
     mut split_asteroid_events: EventWriter<SplitAsteroidEvent>,
     mut projectile_explosion_events: EventWriter<ProjectileExplosionEvent>,
+    mut score_events: EventWriter<ScoreEvent>,
 ) {
     for event in collision_events.read() {
         if let CollisionEvent::Started(entity_a, entity_b, _) = event {
-            let (entity_a, duplicate_a) = get_original_entities(&duplicate_query, entity_a);
-            let (entity_b, duplicate_b) = get_original_entities(&duplicate_query, entity_b);
-
-            let ((projectile_entity, _projectile_duplicate), (asteroid_entity, asteroid_duplicate)) =
-                if projectile_query.contains(entity_a) && asteroid_query.contains(entity_b) {
-                    ((entity_a, duplicate_a), (entity_b, duplicate_b))
-                } else if projectile_query.contains(entity_b) && asteroid_query.contains(entity_a) {
-                    ((entity_b, duplicate_b), (entity_a, duplicate_a))
-                } else {
-                    continue;
-                };
+            let (projectile_entity, asteroid_entity) = if projectile_query.contains(*entity_a)
+                && asteroid_query.contains(*entity_b)
+            {
+                (*entity_a, *entity_b)
+            } else if projectile_query.contains(*entity_b) && asteroid_query.contains(*entity_a) {
+                (*entity_b, *entity_a)
+            } else {
+                continue;
+            };
 
             projectile_explosion_events.send(ProjectileExplosionEvent { projectile_entity });
 
+            if let Ok((_, _, Some(&player))) = projectile_query.get(projectile_entity) {
+                score_events.send(ScoreEvent {
+                    player,
+                    points: ASTEROID_HIT_SCORE,
+                });
+            }
+
             // Split asteroid into smaller asteroids
             let (transform, velocity) = asteroid_query
                 .get_mut(asteroid_entity)
@@ -128,34 +204,62 @@ fn projectile_asteroid_collision(
                 .get(projectile_entity)
                 .expect("Projectile transform not found");
 
-            let Some((collision_position, collision_direction)) = contact_position_and_normal(
-                &rapier_context,
-                projectile_entity,
-                asteroid_duplicate.unwrap_or(asteroid_entity),
-            ) else {
+            let Some((collision_position, collision_direction)) =
+                contact_position_and_normal(&rapier_context, projectile_entity, asteroid_entity)
+            else {
+                continue;
+            };
+            let Some(impact_point) =
+                local_contact_point(&rapier_context, asteroid_entity, projectile_entity)
+            else {
                 continue;
             };
 
+            let linvel_of =
+                |opt_velocity: Option<&Velocity>| opt_velocity.map_or(Vec2::ZERO, |v| v.linvel);
+            let projectile_velocity = projectile_query
+                .get(projectile_entity)
+                .map_or(Vec2::ZERO, |(_, opt_velocity, _)| linvel_of(opt_velocity));
+            let impact_speed = (projectile_velocity - linvel_of(velocity)).length();
+
+            let projectile_position = projectile_transform.translation().xy();
+            let impact_normal = (projectile_position - transform.translation.xy()).normalize();
+
+            spawn_particle_burst(
+                &mut commands,
+                &mut rand::thread_rng(),
+                ParticleBurst {
+                    settings: IMPACT_SPARKS,
+                    count: IMPACT_SPARK_COUNT,
+                    position: projectile_position,
+                    velocity: linvel_of(velocity),
+                    direction: impact_normal,
+                    scale: 1.,
+                },
+            );
+
             let mut velocity = velocity.copied().unwrap_or_else(Velocity::zero);
-            velocity.linvel -=
-                (projectile_transform.translation().xy() - transform.translation.xy()).normalize()
-                    * 100.;
+            velocity.linvel -= impact_normal * 100.;
 
             split_asteroid_events.send(SplitAsteroidEvent {
                 asteroid_entity,
                 collision_direction,
                 collision_position,
+                impact_point,
+                impact_speed,
             });
         }
     }
 }
 
 fn projectile_ufo_collision(
+    rapier_context: Res<RapierContext>,
     mut collision_events: EventReader<CollisionEvent>,
-    projectile_query: Query<&Projectile>,
+    projectile_query: Query<Option<&PlayerId>, With<Projectile>>,
     ufo_query: Query<Entity, With<Ufo>>,
     mut ufo_destroyed_events: EventWriter<UfoDestroyedEvent>,
     mut projectile_explosion_events: EventWriter<ProjectileExplosionEvent>,
+    mut score_events: EventWriter<ScoreEvent>,
 ) {
     for event in collision_events.read() {
         if let CollisionEvent::Started(entity_a, entity_b, _) = event {
@@ -170,8 +274,16 @@ fn projectile_ufo_collision(
 
             ufo_destroyed_events.send(UfoDestroyedEvent {
                 ufo_entity: *ufo_entity,
+                impact_point: local_contact_point(&rapier_context, *ufo_entity, *projectile_entity),
             });
 
+            if let Ok(Some(&player)) = projectile_query.get(*projectile_entity) {
+                score_events.send(ScoreEvent {
+                    player,
+                    points: UFO_DESTROYED_SCORE,
+                });
+            }
+
             projectile_explosion_events.send(ProjectileExplosionEvent {
                 projectile_entity: *projectile_entity,
             });
@@ -179,19 +291,66 @@ fn projectile_ufo_collision(
     }
 }
 
+fn projectile_ship_collision(
+    rapier_context: Res<RapierContext>,
+    mut collision_events: EventReader<CollisionEvent>,
+    projectile_query: Query<(&Projectile, Option<&Velocity>)>,
+    ship_query: Query<Option<&Velocity>, With<Ship>>,
+    mut ship_hit_events: EventWriter<ShipHitEvent>,
+    mut projectile_explosion_events: EventWriter<ProjectileExplosionEvent>,
+) {
+    for event in collision_events.read() {
+        if let CollisionEvent::Started(entity_a, entity_b, _) = event {
+            let (projectile_entity, ship_entity) =
+                if projectile_query.contains(*entity_a) && ship_query.contains(*entity_b) {
+                    (*entity_a, *entity_b)
+                } else if projectile_query.contains(*entity_b) && ship_query.contains(*entity_a) {
+                    (*entity_b, *entity_a)
+                } else {
+                    continue;
+                };
+
+            let (projectile, opt_projectile_velocity) = projectile_query
+                .get(projectile_entity)
+                .expect("Projectile not found");
+            if projectile.owner == ship_entity {
+                continue;
+            }
+
+            let linvel_of =
+                |opt_velocity: Option<&Velocity>| opt_velocity.map_or(Vec2::ZERO, |v| v.linvel);
+            let ship_velocity = ship_query.get(ship_entity).ok().flatten();
+            let impact_speed =
+                (linvel_of(opt_projectile_velocity) - linvel_of(ship_velocity)).length();
+
+            let impact_point = local_contact_point(&rapier_context, ship_entity, projectile_entity)
+                .unwrap_or(Vec2::ZERO);
+
+            info!("Projectile hit a ship");
+            ship_hit_events.send(ShipHitEvent {
+                ship_entity,
+                impact_point,
+                impact_speed,
+            });
+
+            projectile_explosion_events.send(ProjectileExplosionEvent { projectile_entity });
+        }
+    }
+}
+
 fn projectile_explosion(
     mut commands: Commands,
     mut events: EventReader<ProjectileExplosionEvent>,
     mut explosion_events: EventWriter<ExplosionEvent>,
-    transform_query: Query<&Transform>,
+    projectile_query: Query<(&Transform, &Projectile)>,
 ) {
     for event in events.read() {
-        let transform = transform_query
+        let (transform, projectile) = projectile_query
             .get(event.projectile_entity)
-            .expect("Projectile transform not found");
+            .expect("Projectile not found");
         explosion_events.send(ExplosionEvent {
             position: transform.translation.xy(),
-            radius: PROJECTILE_RADIUS,
+            radius: projectile.explosion_radius,
         });
         info!("Projectile exploded");
         commands.entity(event.projectile_entity).despawn_recursive();
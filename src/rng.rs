@@ -0,0 +1,21 @@
+use bevy::ecs::system::Resource;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Random numbers for everything that affects the simulation, like asteroid shapes, fractures and
+/// UFO spawns. Peers of an online game seed it the same, so they generate the same world.
+///
+/// Effects that are only drawn, like particles and explosions, keep using `thread_rng`.
+#[derive(Resource, Debug, Clone)]
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+impl GameRng {
+    pub fn reseed(&mut self, seed: u64) {
+        self.0 = StdRng::seed_from_u64(seed);
+    }
+}
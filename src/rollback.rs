@@ -0,0 +1,803 @@
+use std::{any::Any, marker::PhantomData};
+
+use bevy::{
+    app::{App, Last, Plugin},
+    asset::Handle,
+    core::Name,
+    ecs::{
+        change_detection::DetectChangesMut,
+        component::Component,
+        entity::{Entity, EntityHashMap, EntityHashSet, EntityMapper, MapEntities},
+        event::Events,
+        query::{Or, With, Without},
+        schedule::{
+            common_conditions::{in_state, not, resource_exists},
+            Condition, IntoSystemConfigs, OnExit,
+        },
+        system::{Resource, RunSystemOnce},
+        world::{Mut, World},
+    },
+    hierarchy::{BuildWorldChildren, Children, DespawnRecursiveExt, Parent},
+    input::{keyboard::KeyCode, ButtonInput},
+    log::info,
+    render::{
+        texture::Image,
+        view::{InheritedVisibility, ViewVisibility, Visibility},
+    },
+    sprite::{ColorMaterial, Mesh2dHandle, Sprite},
+    transform::components::{GlobalTransform, Transform},
+};
+use bevy_rapier2d::{
+    dynamics::{
+        ExternalImpulse, LockedAxes, RapierRigidBodyHandle, ReadMassProperties, RigidBody,
+        Sleeping, Velocity,
+    },
+    geometry::{
+        ActiveEvents, ActiveHooks, Collider, ColliderMassProperties, CollisionGroups, Friction,
+        Restitution,
+    },
+    pipeline::CollisionEvent,
+    plugin::{
+        systems::{sync_removals, writeback_rigid_bodies},
+        RapierContext,
+    },
+    rapier::{
+        dynamics::{
+            CCDSolver, ImpulseJointSet, IntegrationParameters, IslandManager, MultibodyJointSet,
+            RigidBodyHandle, RigidBodySet,
+        },
+        geometry::{ColliderSet, DefaultBroadPhase, NarrowPhase},
+        pipeline::QueryPipeline,
+    },
+};
+
+use crate::{
+    asteroid::Asteroid, game_state::GameState, net::Lockstep, projectile::Projectile, rng::GameRng,
+    ship::Ship, ufo::Ufo, utils::cleanup_resource,
+};
+
+/// Snapshots of the gameplay state that the world can be rolled back to, to resimulate the frames
+/// since with corrected inputs.
+///
+/// A [`WorldSnapshot`] holds the ships, asteroids, projectiles and UFOs with their descendants,
+/// the components and resources registered with [`RollbackAppExt`], and the state of the physics
+/// engine. F10 captures a snapshot of a local game and F11 rolls back to it.
+pub struct RollbackPlugin;
+
+impl Plugin for RollbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RollbackRegistry>()
+            .rollback_component::<Transform>()
+            .rollback_component::<GlobalTransform>()
+            .rollback_component::<Visibility>()
+            .rollback_component::<InheritedVisibility>()
+            .rollback_component::<ViewVisibility>()
+            .rollback_component::<Name>()
+            .rollback_component::<Mesh2dHandle>()
+            .rollback_component::<Handle<ColorMaterial>>()
+            .rollback_component::<Sprite>()
+            .rollback_component::<Handle<Image>>()
+            .rollback_component::<RigidBody>()
+            .rollback_component::<Velocity>()
+            .rollback_component::<ExternalImpulse>()
+            .rollback_component::<LockedAxes>()
+            .rollback_component::<ReadMassProperties>()
+            .rollback_component::<Sleeping>()
+            .rollback_component::<Collider>()
+            .rollback_component::<ColliderMassProperties>()
+            .rollback_component::<CollisionGroups>()
+            .rollback_component::<ActiveEvents>()
+            .rollback_component::<ActiveHooks>()
+            .rollback_component::<Restitution>()
+            .rollback_component::<Friction>()
+            .rollback_resource::<GameRng>()
+            .add_systems(
+                Last,
+                quick_snapshot.run_if(
+                    in_state(GameState::Playing).and_then(not(resource_exists::<Lockstep>)),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                cleanup_resource::<QuickSnapshot>,
+            );
+    }
+}
+
+/// Entities captured in snapshots, along with their descendants that have a transform.
+type RollbackFilter = Or<(With<Ship>, With<Asteroid>, With<Projectile>, With<Ufo>)>;
+
+pub trait RollbackAppExt {
+    /// Captures the component of the rollback entities in snapshots.
+    fn rollback_component<T: Component + Clone>(&mut self) -> &mut Self;
+
+    /// Captures a component that refers to other entities, which are mapped to their new ids
+    /// when they had to be spawned again.
+    fn rollback_component_with_entities<T: Component + Clone + MapEntities>(&mut self)
+        -> &mut Self;
+
+    /// Captures the resource in snapshots, it's removed again when it didn't exist yet.
+    fn rollback_resource<T: Resource + Clone>(&mut self) -> &mut Self;
+}
+
+impl RollbackAppExt for App {
+    fn rollback_component<T: Component + Clone>(&mut self) -> &mut Self {
+        register_component::<T>(self, None);
+        self
+    }
+
+    fn rollback_component_with_entities<T: Component + Clone + MapEntities>(
+        &mut self,
+    ) -> &mut Self {
+        register_component::<T>(self, Some(map_component_entities::<T>));
+        self
+    }
+
+    fn rollback_resource<T: Resource + Clone>(&mut self) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(RollbackRegistry::default)
+            .resources
+            .push(Box::new(RollbackResource::<T>(PhantomData)));
+        self
+    }
+}
+
+fn register_component<T: Component + Clone>(
+    app: &mut App,
+    map_entities: Option<fn(&mut T, &mut EntityRemap)>,
+) {
+    app.world
+        .get_resource_or_insert_with(RollbackRegistry::default)
+        .components
+        .push(Box::new(RollbackComponent::<T> {
+            map_entities,
+            marker: PhantomData,
+        }));
+}
+
+fn map_component_entities<T: MapEntities>(component: &mut T, remap: &mut EntityRemap) {
+    component.map_entities(remap);
+}
+
+/// Components and resources captured in snapshots, registered with [`RollbackAppExt`].
+#[derive(Resource, Default)]
+struct RollbackRegistry {
+    components: Vec<Box<dyn ComponentRollback>>,
+    resources: Vec<Box<dyn ResourceRollback>>,
+}
+
+type Captured = Box<dyn Any + Send + Sync>;
+
+trait ComponentRollback: Send + Sync {
+    /// Clones the component of every entity, `None` for entities without it.
+    fn capture(&self, world: &World, entities: &[Entity]) -> Captured;
+
+    /// Puts the captured components back, removing them from entities that had none.
+    fn restore(
+        &self,
+        world: &mut World,
+        entities: &[Entity],
+        captured: &Captured,
+        remap: &mut EntityRemap,
+    );
+}
+
+struct RollbackComponent<T> {
+    map_entities: Option<fn(&mut T, &mut EntityRemap)>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Component + Clone> ComponentRollback for RollbackComponent<T> {
+    fn capture(&self, world: &World, entities: &[Entity]) -> Captured {
+        Box::new(
+            entities
+                .iter()
+                .map(|&entity| world.get::<T>(entity).cloned())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn restore(
+        &self,
+        world: &mut World,
+        entities: &[Entity],
+        captured: &Captured,
+        remap: &mut EntityRemap,
+    ) {
+        let captured = captured
+            .downcast_ref::<Vec<Option<T>>>()
+            .expect("Snapshot captured a different component");
+
+        for (&entity, opt_component) in entities.iter().zip(captured) {
+            let mut entity_mut = world.entity_mut(entity);
+            let Some(component) = opt_component else {
+                entity_mut.remove::<T>();
+                continue;
+            };
+
+            let mut component = component.clone();
+            if let Some(map_entities) = self.map_entities {
+                map_entities(&mut component, remap);
+            }
+
+            // The caches that depend on the component are restored with it, so systems must not
+            // see it as changed
+            match entity_mut.get_mut::<T>() {
+                Some(mut current) => *current.bypass_change_detection() = component,
+                None => {
+                    entity_mut.insert(component);
+                }
+            }
+        }
+    }
+}
+
+trait ResourceRollback: Send + Sync {
+    fn capture(&self, world: &World) -> Captured;
+
+    fn restore(&self, world: &mut World, captured: &Captured);
+}
+
+struct RollbackResource<T>(PhantomData<fn() -> T>);
+
+impl<T: Resource + Clone> ResourceRollback for RollbackResource<T> {
+    fn capture(&self, world: &World) -> Captured {
+        Box::new(world.get_resource::<T>().cloned())
+    }
+
+    fn restore(&self, world: &mut World, captured: &Captured) {
+        let captured = captured
+            .downcast_ref::<Option<T>>()
+            .expect("Snapshot captured a different resource");
+
+        match captured {
+            Some(resource) => match world.get_resource_mut::<T>() {
+                Some(mut current) => *current.bypass_change_detection() = resource.clone(),
+                None => world.insert_resource(resource.clone()),
+            },
+            None => {
+                world.remove_resource::<T>();
+            }
+        }
+    }
+}
+
+/// New ids of the captured entities that had to be spawned again because their id was taken.
+#[derive(Default)]
+struct EntityRemap(EntityHashMap<Entity>);
+
+impl EntityMapper for EntityRemap {
+    fn map_entity(&mut self, entity: Entity) -> Entity {
+        self.0.get(&entity).copied().unwrap_or(entity)
+    }
+}
+
+/// Gameplay state of the world at the end of a frame.
+///
+/// Meshes are captured by handle, the generated meshes of asteroids are replaced when they change
+/// and never modified in place, so holding the handle keeps the mesh data as it was.
+pub struct WorldSnapshot {
+    /// Rollback entities, parents before their children.
+    entities: Vec<Entity>,
+    parents: Vec<Option<Entity>>,
+    bodies: Vec<Option<RigidBodyHandle>>,
+    /// A `Vec<Option<T>>` with a value for every entity, per registered component.
+    components: Vec<Captured>,
+    /// An `Option<T>` per registered resource.
+    resources: Vec<Captured>,
+    physics: Option<PhysicsSnapshot>,
+    /// Collision events of the last physics step, which the next frame hasn't read yet.
+    collision_events: Vec<CollisionEvent>,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let entities = rollback_entities(world);
+        let parents = entities
+            .iter()
+            .map(|&entity| world.get::<Parent>(entity).map(Parent::get))
+            .collect();
+        let bodies = entities
+            .iter()
+            .map(|&entity| {
+                world
+                    .get::<RapierRigidBodyHandle>(entity)
+                    .map(|handle| handle.0)
+            })
+            .collect();
+
+        let registry = world.resource::<RollbackRegistry>();
+        let components = registry
+            .components
+            .iter()
+            .map(|component| component.capture(world, &entities))
+            .collect();
+        let resources = registry
+            .resources
+            .iter()
+            .map(|resource| resource.capture(world))
+            .collect();
+
+        let physics = world
+            .get_resource::<RapierContext>()
+            .map(PhysicsSnapshot::capture);
+        let collision_events = world
+            .get_resource::<Events<CollisionEvent>>()
+            .map(|events| events.iter_current_update_events().copied().collect())
+            .unwrap_or_default();
+
+        Self {
+            entities,
+            parents,
+            bodies,
+            components,
+            resources,
+            physics,
+            collision_events,
+        }
+    }
+
+    /// Puts the world back into the captured state. Entities spawned since are despawned, and
+    /// captured entities that were despawned are spawned again, under their old id when it's
+    /// still free.
+    ///
+    /// Entities that are spawned again get new rigid-bodies from bevy_rapier, without the contacts
+    /// they had, so the frames after restoring only resimulate exactly when no captured entity was
+    /// despawned in between.
+    pub fn restore(&self, world: &mut World) {
+        let captured = self.entities.iter().copied().collect::<EntityHashSet>();
+        for entity in rollback_entities(world) {
+            if !captured.contains(&entity) {
+                if let Some(entity_mut) = world.get_entity_mut(entity) {
+                    entity_mut.despawn_recursive();
+                }
+            }
+        }
+        if self.physics.is_some() {
+            // Let bevy_rapier forget the bodies of the despawned entities before the old sets
+            // replace the ones they are in
+            world.run_system_once(sync_removals);
+        }
+
+        let mut remap = EntityRemap::default();
+        let mut respawned = EntityHashSet::default();
+        let entities = self
+            .entities
+            .iter()
+            .map(|&entity| {
+                if world.get_entity(entity).is_some() {
+                    return entity;
+                }
+
+                respawned.insert(entity);
+                match world.get_or_spawn(entity) {
+                    Some(entity_mut) => entity_mut.id(),
+                    None => {
+                        let new_entity = world.spawn_empty().id();
+                        remap.0.insert(entity, new_entity);
+                        new_entity
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(physics) = &self.physics {
+            let mut context = world.resource_mut::<RapierContext>();
+            physics.restore(&mut context);
+
+            // bevy_rapier creates new bodies for the respawned entities
+            let context = &mut *context;
+            for (entity, body) in self.entities.iter().zip(&self.bodies) {
+                if let (true, Some(handle)) = (respawned.contains(entity), body) {
+                    context.bodies.remove(
+                        *handle,
+                        &mut context.islands,
+                        &mut context.colliders,
+                        &mut context.impulse_joints,
+                        &mut context.multibody_joints,
+                        true,
+                    );
+                }
+            }
+        }
+
+        world.resource_scope(|world, registry: Mut<RollbackRegistry>| {
+            for (component, captured) in registry.components.iter().zip(&self.components) {
+                component.restore(world, &entities, captured, &mut remap);
+            }
+            for (resource, captured) in registry.resources.iter().zip(&self.resources) {
+                resource.restore(world, captured);
+            }
+        });
+
+        for (&entity, opt_parent) in entities.iter().zip(&self.parents) {
+            let parent = opt_parent.map(|parent| remap.map_entity(parent));
+            if world.get::<Parent>(entity).map(Parent::get) == parent {
+                continue;
+            }
+            match parent {
+                Some(parent) => {
+                    world.entity_mut(parent).add_child(entity);
+                }
+                None => {
+                    world.entity_mut(entity).remove_parent();
+                }
+            }
+        }
+
+        if let Some(mut events) = world.get_resource_mut::<Events<CollisionEvent>>() {
+            events.clear();
+            events.extend(
+                self.collision_events
+                    .iter()
+                    .map(|event| remap_collision_event(*event, &mut remap)),
+            );
+        }
+
+        // Catches up bevy_rapier's record of the transforms it wrote, which it compares against to
+        // detect transforms changed by systems
+        if self.physics.is_some() {
+            world.run_system_once(writeback_rigid_bodies);
+        }
+    }
+}
+
+/// Captured entities in breadth first order, the descendants of the ones matching the
+/// [`RollbackFilter`] are left out when they have no transform, like sounds.
+fn rollback_entities(world: &mut World) -> Vec<Entity> {
+    let mut entities = world
+        .query_filtered::<Entity, (RollbackFilter, Without<Parent>)>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    let mut index = 0;
+    while index < entities.len() {
+        if let Some(children) = world.get::<Children>(entities[index]) {
+            entities.extend(
+                children
+                    .iter()
+                    .filter(|&&child| world.get::<Transform>(child).is_some()),
+            );
+        }
+        index += 1;
+    }
+
+    entities
+}
+
+fn remap_collision_event(event: CollisionEvent, remap: &mut EntityRemap) -> CollisionEvent {
+    match event {
+        CollisionEvent::Started(entity_a, entity_b, flags) => CollisionEvent::Started(
+            remap.map_entity(entity_a),
+            remap.map_entity(entity_b),
+            flags,
+        ),
+        CollisionEvent::Stopped(entity_a, entity_b, flags) => CollisionEvent::Stopped(
+            remap.map_entity(entity_a),
+            remap.map_entity(entity_b),
+            flags,
+        ),
+    }
+}
+
+/// Everything in the [`RapierContext`] that the next physics step depends on.
+struct PhysicsSnapshot {
+    islands: IslandManager,
+    broad_phase: DefaultBroadPhase,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    query_pipeline: QueryPipeline,
+    integration_parameters: IntegrationParameters,
+}
+
+impl PhysicsSnapshot {
+    fn capture(context: &RapierContext) -> Self {
+        Self {
+            islands: context.islands.clone(),
+            broad_phase: context.broad_phase.clone(),
+            narrow_phase: context.narrow_phase.clone(),
+            bodies: context.bodies.clone(),
+            colliders: context.colliders.clone(),
+            impulse_joints: context.impulse_joints.clone(),
+            multibody_joints: context.multibody_joints.clone(),
+            ccd_solver: context.ccd_solver.clone(),
+            query_pipeline: context.query_pipeline.clone(),
+            integration_parameters: context.integration_parameters,
+        }
+    }
+
+    fn restore(&self, context: &mut RapierContext) {
+        context.islands = self.islands.clone();
+        context.broad_phase = self.broad_phase.clone();
+        context.narrow_phase = self.narrow_phase.clone();
+        context.bodies = self.bodies.clone();
+        context.colliders = self.colliders.clone();
+        context.impulse_joints = self.impulse_joints.clone();
+        context.multibody_joints = self.multibody_joints.clone();
+        context.ccd_solver = self.ccd_solver.clone();
+        context.query_pipeline = self.query_pipeline.clone();
+        context.integration_parameters = self.integration_parameters;
+    }
+}
+
+/// Snapshot taken with F10.
+#[derive(Resource)]
+struct QuickSnapshot(WorldSnapshot);
+
+fn quick_snapshot(world: &mut World) {
+    let keyboard_input = world.resource::<ButtonInput<KeyCode>>();
+    let (capture, restore) = (
+        keyboard_input.just_pressed(KeyCode::F10),
+        keyboard_input.just_pressed(KeyCode::F11),
+    );
+
+    if capture {
+        let snapshot = WorldSnapshot::capture(world);
+        info!(entities = snapshot.entities.len(), "Snapshot captured");
+        world.insert_resource(QuickSnapshot(snapshot));
+    } else if restore {
+        if let Some(snapshot) = world.remove_resource::<QuickSnapshot>() {
+            snapshot.0.restore(world);
+            info!("Rolled back to snapshot");
+            world.insert_resource(snapshot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::TAU, time::Duration};
+
+    use bevy::{
+        app::{Startup, Update},
+        asset::Assets,
+        ecs::system::{Commands, Query, ResMut},
+        hierarchy::HierarchyPlugin,
+        math::{Vec2, Vec3Swizzles},
+        render::{color::Color, mesh::Mesh},
+        time::TimeUpdateStrategy,
+        transform::{TransformBundle, TransformPlugin},
+        MinimalPlugins,
+    };
+    use bevy_rapier2d::{
+        geometry::Group,
+        plugin::{NoUserData, RapierConfiguration, RapierPhysicsPlugin, TimestepMode},
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        asteroid::SplitAsteroidEvent,
+        edge_wrap::{Duplicable, EdgeWrapPlugin, WrapGhost},
+        explosion::ExplosionEvent,
+        player::ScoreEvent,
+        projectile::{spawn_projectile, ProjectilePlugin},
+        ship::{Hull, ShipHitEvent, SpawnShipExt, Thruster},
+        turret::{reload, ReloadTimer},
+        ufo::UfoDestroyedEvent,
+    };
+
+    use super::*;
+
+    const TEST_TICK: Duration = Duration::from_nanos(1_000_000_000 / 60);
+    const TEST_LENGTH_UNIT: f32 = 100.;
+    const SETTLE_FRAMES: usize = 20;
+    /// Long enough for the asteroids to collide and the ship to fire again.
+    const ROLLBACK_FRAMES: usize = 30;
+    const TEST_MUZZLE_SPEED: f32 = 500.;
+    const TEST_MUZZLE_OFFSET: f32 = 30.;
+
+    /// Headless app stepping physics by a fixed tick, with asteroids on a collision course and a
+    /// ship firing projectiles in random directions.
+    fn create_test_app() -> App {
+        let mut rapier_configuration = RapierConfiguration::new(TEST_LENGTH_UNIT);
+        rapier_configuration.gravity = Vec2::ZERO;
+        rapier_configuration.timestep_mode = TimestepMode::Fixed {
+            dt: TEST_TICK.as_secs_f32(),
+            substeps: 1,
+        };
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(TEST_TICK))
+            .insert_resource(rapier_configuration)
+            .insert_resource(GameRng(StdRng::seed_from_u64(0)))
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_state::<GameState>()
+            .add_event::<SplitAsteroidEvent>()
+            .add_event::<ScoreEvent>()
+            .add_event::<UfoDestroyedEvent>()
+            .add_event::<ShipHitEvent>()
+            .add_event::<ExplosionEvent>()
+            .add_plugins((
+                RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(TEST_LENGTH_UNIT),
+                EdgeWrapPlugin,
+                ProjectilePlugin,
+                RollbackPlugin,
+            ))
+            .rollback_component::<Asteroid>()
+            .rollback_component::<Ship>()
+            .rollback_component::<Thruster>()
+            .rollback_component::<Hull>()
+            .rollback_component::<ReloadTimer>()
+            .add_systems(Startup, spawn_test_scene)
+            .add_systems(Update, (reload, fire_test_projectiles).chain());
+        app
+    }
+
+    fn spawn_test_scene(mut commands: Commands) {
+        for (position, velocity) in [
+            (Vec2::new(-100., 0.), Vec2::new(120., 10.)),
+            (Vec2::new(100., 20.), Vec2::new(-120., 0.)),
+            (Vec2::new(0., 250.), Vec2::new(0., -150.)),
+        ] {
+            commands.spawn((
+                Asteroid,
+                TransformBundle::from_transform(Transform::from_translation(position.extend(0.))),
+                Collider::ball(40.),
+                RigidBody::Dynamic,
+                Velocity {
+                    linvel: velocity,
+                    angvel: 1.,
+                },
+                Restitution::coefficient(0.9),
+                Duplicable,
+            ));
+        }
+
+        commands
+            .spawn_ship(Transform::from_xyz(0., -200., 0.), Color::WHITE)
+            .insert(Velocity {
+                linvel: Vec2::new(80., 60.),
+                angvel: 2.,
+            });
+    }
+
+    /// Fires a projectile in a random direction whenever a ship is done reloading.
+    fn fire_test_projectiles(
+        mut commands: Commands,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        mut rng: ResMut<GameRng>,
+        ship_query: Query<(Entity, &Transform), (With<Ship>, Without<ReloadTimer>)>,
+    ) {
+        for (ship_entity, transform) in &ship_query {
+            let direction = Vec2::from_angle(rng.0.gen_range(0.0..TAU));
+            spawn_projectile(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                ship_entity,
+                transform.translation.xy() + direction * TEST_MUZZLE_OFFSET,
+                direction * TEST_MUZZLE_SPEED,
+                Group::NONE,
+            );
+            commands.entity(ship_entity).insert(ReloadTimer::default());
+        }
+    }
+
+    fn run(app: &mut App, frames: usize) {
+        for _ in 0..frames {
+            app.update();
+        }
+    }
+
+    /// Bits of everything the test scene simulates, sorted so that entity ids don't matter.
+    fn simulation_state(world: &mut World) -> Vec<Vec<u32>> {
+        let mut state = world
+            .query_filtered::<(
+                &Transform,
+                &Velocity,
+                Option<&Projectile>,
+                Option<&ReloadTimer>,
+            ), RollbackFilter>()
+            .iter(world)
+            .map(|(transform, velocity, opt_projectile, opt_reload_timer)| {
+                let mut bits = [
+                    transform.translation.x,
+                    transform.translation.y,
+                    transform.rotation.z,
+                    transform.rotation.w,
+                    velocity.linvel.x,
+                    velocity.linvel.y,
+                    velocity.angvel,
+                ]
+                .map(f32::to_bits)
+                .to_vec();
+                bits.extend(
+                    opt_projectile.map(|projectile| projectile.lifetime.elapsed_secs().to_bits()),
+                );
+                bits.push(opt_reload_timer.is_some().into());
+                bits
+            })
+            .collect::<Vec<_>>();
+        state.sort();
+
+        let ghosts = world.query::<&WrapGhost>().iter(world).count();
+        let mut rng = world.resource::<GameRng>().clone();
+        state.push(vec![ghosts as u32, rng.0.gen()]);
+        state
+    }
+
+    #[test]
+    fn test_resimulation_matches_straight_run() {
+        let mut app = create_test_app();
+        run(&mut app, SETTLE_FRAMES);
+
+        let snapshot = WorldSnapshot::capture(&mut app.world);
+        let captured = simulation_state(&mut app.world);
+        run(&mut app, ROLLBACK_FRAMES);
+        let straight_run = simulation_state(&mut app.world);
+        assert_ne!(
+            captured.len(),
+            straight_run.len(),
+            "No projectile was fired"
+        );
+
+        // A snapshot can be restored any number of times
+        for _ in 0..2 {
+            snapshot.restore(&mut app.world);
+            assert_eq!(simulation_state(&mut app.world), captured);
+
+            run(&mut app, ROLLBACK_FRAMES);
+            assert_eq!(simulation_state(&mut app.world), straight_run);
+        }
+    }
+
+    #[test]
+    fn test_restore_respawns_despawned_entities() {
+        let mut app = create_test_app();
+        run(&mut app, SETTLE_FRAMES);
+
+        let snapshot = WorldSnapshot::capture(&mut app.world);
+        let captured = simulation_state(&mut app.world);
+
+        let ship = app
+            .world
+            .query_filtered::<Entity, With<Ship>>()
+            .single(&app.world);
+        app.world.entity_mut(ship).despawn_recursive();
+        // Takes the id of the ship, so it's spawned again under a new one
+        app.world.spawn_empty();
+        app.update();
+
+        snapshot.restore(&mut app.world);
+        assert_eq!(simulation_state(&mut app.world), captured);
+
+        let (ship, children) = app
+            .world
+            .query_filtered::<(Entity, &Children), With<Ship>>()
+            .single(&app.world);
+        let thrusters = children
+            .iter()
+            .filter(|&&child| app.world.get::<Thruster>(child).is_some())
+            .count();
+        assert_eq!(thrusters, 3);
+
+        let owners = app
+            .world
+            .query::<&Projectile>()
+            .iter(&app.world)
+            .map(|projectile| projectile.owner)
+            .collect::<Vec<_>>();
+        assert!(!owners.is_empty());
+        assert!(owners.iter().all(|&owner| owner == ship));
+
+        app.update();
+        let handle = app
+            .world
+            .get::<RapierRigidBodyHandle>(ship)
+            .expect("Ship has no rigid-body");
+        assert!(app
+            .world
+            .resource::<RapierContext>()
+            .bodies
+            .get(handle.0)
+            .is_some());
+    }
+}
@@ -0,0 +1,936 @@
+use std::{io::ErrorKind, path::PathBuf, time::Duration};
+
+use bevy::{
+    app::{App, AppExit, Last, Plugin, Startup, Update},
+    asset::Assets,
+    ecs::{
+        entity::Entity,
+        event::EventWriter,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, not, on_event, resource_exists},
+            Condition, IntoSystemConfigs, NextState, OnEnter, OnExit,
+        },
+        system::{Commands, EntityCommand, Query, Res, ResMut, Resource},
+        world::World,
+    },
+    hierarchy::Children,
+    input::{keyboard::KeyCode, ButtonInput},
+    log::{error, info, warn},
+    math::{Quat, Vec2, Vec3},
+    render::{
+        mesh::{Indices, Mesh, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::Mesh2dHandle,
+    time::{Timer, TimerMode},
+    transform::components::Transform,
+};
+use bevy_rapier2d::{dynamics::Velocity, geometry::Collider};
+
+use crate::{
+    asteroid::{create_asteroid_bundle, Asteroid, AsteroidMaterial},
+    codec::{
+        decode_boundary_mode, decode_game_mode, encode_boundary_mode, encode_game_mode,
+        encode_vec2, encode_vec3, Reader,
+    },
+    edge_wrap::{BoundaryMode, BoundaryModeSettings},
+    game_state::{GameMode, GameState},
+    input::InputMode,
+    mesh_utils::validate_mesh,
+    missile::MissileAmmo,
+    net::Lockstep,
+    particle::ParticleEmitter,
+    player::{spawn_player_ship, PlayerId, PlayerStats, Players},
+    playfield::Playfield,
+    ship::{Hull, Ship, Thruster},
+    ufo::{SpawnTimer, SpawnUfoExt, TractorBeam, Ufo},
+    utils::{cleanup_resource, mesh_to_collider},
+    versus::VersusSettings,
+};
+
+/// Suspends a game in progress to a file when quitting, to be continued from the start screen
+/// on the next launch.
+///
+/// Everything that shapes the rest of the game is kept: the settings, the score, lives and
+/// respawn timers of the players, and the bodies of the ships, asteroids and UFOs. The meshes of
+/// asteroids and damaged ships are procedural, so their vertices are stored bit for bit to break
+/// the same way after continuing. Short-lived entities like projectiles, debris and particles
+/// are left out.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        // Browsers have no file system to keep the save in
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+
+        app.add_systems(Startup, load_saved_game)
+            .add_systems(
+                Update,
+                (
+                    continue_game
+                        .run_if(in_state(GameState::Menu).and_then(resource_exists::<SavedGame>)),
+                    quit_game.run_if(in_state(GameState::Playing)),
+                )
+                    .run_if(not(resource_exists::<Lockstep>)),
+            )
+            .add_systems(
+                Last,
+                save_game.run_if(
+                    in_state(GameState::Playing)
+                        .and_then(on_event::<AppExit>())
+                        .and_then(not(resource_exists::<Lockstep>)),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                resume_game.run_if(resource_exists::<ResumedGame>),
+            )
+            .add_systems(OnExit(GameState::Playing), cleanup_resource::<ResumedGame>);
+    }
+}
+
+const SAVE_FILE_NAME: &str = "savegame.bin";
+
+/// The save goes into the per-user data directory of the platform, so it neither depends on
+/// where the game was launched from nor ends up in a read-only install directory.
+fn save_path() -> Option<PathBuf> {
+    let home_dir = || std::env::var_os("HOME").map(PathBuf::from);
+    let data_dir = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home_dir().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home_dir().map(|home| home.join(".local/share")))
+    };
+    Some(data_dir?.join(env!("CARGO_PKG_NAME")).join(SAVE_FILE_NAME))
+}
+
+/// Bumped whenever the encoding changes, saves of another version are ignored.
+const SAVE_VERSION: u8 = 1;
+
+/// A game that can be continued from the start screen.
+#[derive(Resource, Debug, Clone)]
+pub struct SavedGame {
+    pub game_mode: GameMode,
+    pub boundary_mode: BoundaryMode,
+    pub world_screens: Vec2,
+    pub score_limit: u32,
+    pub players: Vec<PlayerStats>,
+    pub ships: Vec<SavedShip>,
+    pub asteroids: Vec<SavedAsteroid>,
+    pub ufos: Vec<SavedUfo>,
+    pub ufo_spawn_timer: Timer,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedShip {
+    pub id: PlayerId,
+    pub transform: Transform,
+    pub velocity: Velocity,
+    pub mesh: Mesh,
+    pub hull: Hull,
+    pub missiles: u32,
+    /// Translations of the thrusters, which move along when a hit recenters the hull.
+    pub thrusters: Vec<Vec3>,
+    /// Emission point of the exhaust, which moves along with the thrusters.
+    pub exhaust_offset: Vec2,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedAsteroid {
+    pub transform: Transform,
+    pub velocity: Velocity,
+    pub mesh: Mesh,
+}
+
+#[derive(Debug, Clone)]
+pub struct SavedUfo {
+    pub transform: Transform,
+    pub velocity: Velocity,
+    pub tractor_beam: TractorBeam,
+}
+
+/// The game being played was continued from a save.
+#[derive(Resource)]
+pub struct ResumedGame(SavedGame);
+
+/// Whether the game being entered starts from scratch, rather than continuing a save.
+pub fn is_new_game(resumed_game: Option<Res<ResumedGame>>) -> bool {
+    resumed_game.is_none()
+}
+
+fn load_saved_game(mut commands: Commands) {
+    let Some(path) = save_path() else {
+        return;
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == ErrorKind::NotFound => return,
+        Err(error) => {
+            warn!(%error, "Failed to read the saved game");
+            return;
+        }
+    };
+
+    match SavedGame::decode(&bytes) {
+        Some(saved_game) => {
+            info!("Found a saved game");
+            commands.insert_resource(saved_game);
+        }
+        None => warn!("Ignoring a saved game of another version or that is damaged"),
+    }
+}
+
+fn continue_game(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    saved_game: Res<SavedGame>,
+    input_mode: Option<Res<InputMode>>,
+    mut game_mode: ResMut<GameMode>,
+    mut boundary_mode: ResMut<BoundaryMode>,
+    mut boundary_mode_settings: ResMut<BoundaryModeSettings>,
+    mut playfield: ResMut<Playfield>,
+    mut versus_settings: ResMut<VersusSettings>,
+    mut next_gamestate: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyC) {
+        return;
+    }
+
+    *game_mode = saved_game.game_mode;
+    *boundary_mode = saved_game.boundary_mode;
+    *boundary_mode_settings.get_mut(saved_game.game_mode) = saved_game.boundary_mode;
+    playfield.world_screens = saved_game.world_screens;
+    versus_settings.score_limit = saved_game.score_limit;
+    if input_mode.is_none() {
+        commands.insert_resource(InputMode::Mouse);
+    }
+
+    // A save is continued once, quitting again saves anew
+    if let Some(Err(error)) = save_path().map(std::fs::remove_file) {
+        warn!(%error, "Failed to remove the saved game");
+    }
+    commands.remove_resource::<SavedGame>();
+    commands.insert_resource(ResumedGame(saved_game.clone()));
+    next_gamestate.set(GameState::Playing);
+    info!("Continuing saved game");
+}
+
+fn resume_game(
+    mut commands: Commands,
+    resumed_game: Res<ResumedGame>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    asteroid_material: Res<AsteroidMaterial>,
+    mut spawn_timer: ResMut<SpawnTimer>,
+) {
+    let saved_game = &resumed_game.0;
+    let player_count = saved_game.game_mode.player_count();
+
+    for ship in &saved_game.ships {
+        spawn_player_ship(&mut commands, ship.id, player_count)
+            .insert((ship.transform, ship.velocity, MissileAmmo(ship.missiles)))
+            .add(RestoreHull {
+                mesh: ship.mesh.clone(),
+                hull: ship.hull.clone(),
+                thrusters: ship.thrusters.clone(),
+                exhaust_offset: ship.exhaust_offset,
+            });
+    }
+
+    for asteroid in &saved_game.asteroids {
+        let Some(collider) = restored_collider(&asteroid.mesh) else {
+            warn!("Leaving out a saved asteroid with a broken mesh");
+            continue;
+        };
+        commands.spawn(create_asteroid_bundle(
+            asteroid.transform,
+            meshes.add(asteroid.mesh.clone()),
+            asteroid_material.0.clone(),
+            collider,
+            asteroid.velocity,
+        ));
+    }
+
+    for ufo in &saved_game.ufos {
+        commands
+            .spawn_ufo(ufo.transform)
+            .insert((ufo.velocity, ufo.tractor_beam.clone()));
+    }
+
+    commands.insert_resource(Players(saved_game.players.clone()));
+    spawn_timer.timer = saved_game.ufo_spawn_timer.clone();
+}
+
+/// Collider of a saved mesh, or `None` if the mesh is broken, e.g. in a damaged file. Bodies
+/// with a broken mesh are left out on their own, the rest of the save is still continued.
+fn restored_collider(mesh: &Mesh) -> Option<Collider> {
+    if !validate_mesh(mesh).is_empty() {
+        return None;
+    }
+    mesh_to_collider(mesh).ok()
+}
+
+/// Puts the damaged hull of a saved ship in place of the one it spawned with.
+struct RestoreHull {
+    mesh: Mesh,
+    hull: Hull,
+    thrusters: Vec<Vec3>,
+    exhaust_offset: Vec2,
+}
+
+impl EntityCommand for RestoreHull {
+    fn apply(self, entity: Entity, world: &mut World) {
+        let Some(collider) = restored_collider(&self.mesh) else {
+            warn!("Keeping the intact hull of a saved ship with a broken mesh");
+            return;
+        };
+        let mesh_handle = world.resource_mut::<Assets<Mesh>>().add(self.mesh);
+        let mut ship = world.entity_mut(entity);
+        ship.insert((Mesh2dHandle(mesh_handle), collider, self.hull));
+        if let Some(mut exhaust) = ship.get_mut::<ParticleEmitter>() {
+            exhaust.offset = self.exhaust_offset;
+        }
+
+        let children = ship
+            .get::<Children>()
+            .map(|children| children.to_vec())
+            .unwrap_or_default();
+        let thruster_entities = children
+            .into_iter()
+            .filter(|&child| world.get::<Thruster>(child).is_some())
+            .collect::<Vec<_>>();
+        for (thruster_entity, translation) in thruster_entities.into_iter().zip(self.thrusters) {
+            if let Some(mut transform) = world.get_mut::<Transform>(thruster_entity) {
+                transform.translation = translation;
+            }
+        }
+    }
+}
+
+fn quit_game(keyboard_input: Res<ButtonInput<KeyCode>>, mut app_exit_events: EventWriter<AppExit>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_exit_events.send(AppExit);
+    }
+}
+
+/// Saves the game in progress when the app exits, whether by quitting or closing the window.
+fn save_game(
+    ship_query: Query<
+        (
+            &PlayerId,
+            &Transform,
+            Option<&Velocity>,
+            &Mesh2dHandle,
+            &Hull,
+            Option<&MissileAmmo>,
+            &Children,
+            &ParticleEmitter,
+        ),
+        With<Ship>,
+    >,
+    thruster_query: Query<&Transform, With<Thruster>>,
+    asteroid_query: Query<(&Transform, Option<&Velocity>, &Mesh2dHandle), With<Asteroid>>,
+    ufo_query: Query<(&Transform, Option<&Velocity>, &TractorBeam), With<Ufo>>,
+    meshes: Res<Assets<Mesh>>,
+    players: Res<Players>,
+    game_mode: Res<GameMode>,
+    boundary_mode: Res<BoundaryMode>,
+    playfield: Res<Playfield>,
+    versus_settings: Res<VersusSettings>,
+    spawn_timer: Res<SpawnTimer>,
+) {
+    let ships = ship_query
+        .iter()
+        .filter_map(
+            |(&id, transform, opt_velocity, mesh_handle, hull, opt_ammo, children, exhaust)| {
+                Some(SavedShip {
+                    id,
+                    transform: *transform,
+                    velocity: opt_velocity.copied().unwrap_or_else(Velocity::zero),
+                    mesh: meshes.get(&mesh_handle.0)?.clone(),
+                    hull: hull.clone(),
+                    missiles: opt_ammo.map_or(0, |ammo| ammo.0),
+                    thrusters: thruster_query
+                        .iter_many(children.iter())
+                        .map(|transform| transform.translation)
+                        .collect(),
+                    exhaust_offset: exhaust.offset,
+                })
+            },
+        )
+        .collect();
+    let asteroids = asteroid_query
+        .iter()
+        .filter_map(|(transform, opt_velocity, mesh_handle)| {
+            Some(SavedAsteroid {
+                transform: *transform,
+                velocity: opt_velocity.copied().unwrap_or_else(Velocity::zero),
+                mesh: meshes.get(&mesh_handle.0)?.clone(),
+            })
+        })
+        .collect();
+    let ufos = ufo_query
+        .iter()
+        .map(|(transform, opt_velocity, tractor_beam)| SavedUfo {
+            transform: *transform,
+            velocity: opt_velocity.copied().unwrap_or_else(Velocity::zero),
+            tractor_beam: tractor_beam.clone(),
+        })
+        .collect();
+
+    let saved_game = SavedGame {
+        game_mode: *game_mode,
+        boundary_mode: *boundary_mode,
+        world_screens: playfield.world_screens,
+        score_limit: versus_settings.score_limit,
+        players: players.0.clone(),
+        ships,
+        asteroids,
+        ufos,
+        ufo_spawn_timer: spawn_timer.timer.clone(),
+    };
+
+    let Some(path) = save_path() else {
+        error!("Failed to save the game, there is no data directory");
+        return;
+    };
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, saved_game.encode()));
+    match result {
+        Ok(()) => info!(path = %path.display(), "Game saved"),
+        Err(error) => error!(%error, "Failed to save the game"),
+    }
+}
+
+impl SavedGame {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![SAVE_VERSION];
+        bytes.push(encode_game_mode(self.game_mode));
+        bytes.push(encode_boundary_mode(self.boundary_mode));
+        encode_vec2(&mut bytes, self.world_screens);
+        bytes.extend(self.score_limit.to_le_bytes());
+
+        bytes.push(self.players.len() as u8);
+        for stats in &self.players {
+            bytes.push(encode_player_id(stats.id));
+            bytes.extend(stats.score.to_le_bytes());
+            bytes.extend(stats.lives.to_le_bytes());
+            bytes.extend(stats.rounds_won.to_le_bytes());
+            match &stats.respawn_timer {
+                None => bytes.push(0),
+                Some(timer) => {
+                    bytes.push(1);
+                    encode_timer(&mut bytes, timer);
+                }
+            }
+        }
+
+        bytes.push(self.ships.len() as u8);
+        for ship in &self.ships {
+            bytes.push(encode_player_id(ship.id));
+            encode_transform(&mut bytes, &ship.transform);
+            encode_velocity(&mut bytes, &ship.velocity);
+            encode_mesh(&mut bytes, &ship.mesh);
+            bytes.extend(ship.hull.initial_area.to_le_bytes());
+            bytes.extend(ship.hull.integrity.to_le_bytes());
+            bytes.extend(ship.missiles.to_le_bytes());
+            bytes.push(ship.thrusters.len() as u8);
+            for &translation in &ship.thrusters {
+                encode_vec3(&mut bytes, translation);
+            }
+            encode_vec2(&mut bytes, ship.exhaust_offset);
+        }
+
+        bytes.extend((self.asteroids.len() as u32).to_le_bytes());
+        for asteroid in &self.asteroids {
+            encode_transform(&mut bytes, &asteroid.transform);
+            encode_velocity(&mut bytes, &asteroid.velocity);
+            encode_mesh(&mut bytes, &asteroid.mesh);
+        }
+
+        bytes.push(self.ufos.len() as u8);
+        for ufo in &self.ufos {
+            encode_transform(&mut bytes, &ufo.transform);
+            encode_velocity(&mut bytes, &ufo.velocity);
+            let (armed, timer) = ufo.tractor_beam.state();
+            bytes.push(armed as u8);
+            encode_timer(&mut bytes, timer);
+        }
+
+        encode_timer(&mut bytes, &self.ufo_spawn_timer);
+        bytes
+    }
+
+    /// Returns `None` for files that aren't a valid save of this version.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.u8()? != SAVE_VERSION {
+            return None;
+        }
+
+        let game_mode = decode_game_mode(reader.u8()?)?;
+        let boundary_mode = decode_boundary_mode(reader.u8()?)?;
+        let world_screens = reader.vec2()?;
+        let score_limit = reader.u32()?;
+
+        let player_count = reader.u8()?;
+        let players = (0..player_count)
+            .map(|_| {
+                Some(PlayerStats {
+                    id: decode_player_id(reader.u8()?)?,
+                    score: reader.u32()?,
+                    lives: reader.u32()?,
+                    rounds_won: reader.u32()?,
+                    respawn_timer: match reader.u8()? {
+                        0 => None,
+                        1 => Some(reader.timer()?),
+                        _ => return None,
+                    },
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        let ship_count = reader.u8()?;
+        let ships = (0..ship_count)
+            .map(|_| {
+                Some(SavedShip {
+                    id: decode_player_id(reader.u8()?)?,
+                    transform: reader.transform()?,
+                    velocity: reader.velocity()?,
+                    mesh: reader.mesh()?,
+                    hull: Hull {
+                        initial_area: reader.f32()?,
+                        integrity: reader.f32()?,
+                    },
+                    missiles: reader.u32()?,
+                    thrusters: {
+                        let count = reader.u8()?;
+                        (0..count).map(|_| reader.vec3()).collect::<Option<_>>()?
+                    },
+                    exhaust_offset: reader.vec2()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        let asteroid_count = reader.u32()?;
+        let asteroids = (0..asteroid_count)
+            .map(|_| {
+                Some(SavedAsteroid {
+                    transform: reader.transform()?,
+                    velocity: reader.velocity()?,
+                    mesh: reader.mesh()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        let ufo_count = reader.u8()?;
+        let ufos = (0..ufo_count)
+            .map(|_| {
+                Some(SavedUfo {
+                    transform: reader.transform()?,
+                    velocity: reader.velocity()?,
+                    tractor_beam: {
+                        let armed = reader.u8()? != 0;
+                        TractorBeam::from_state(armed, reader.timer()?)
+                    },
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        let saved_game = SavedGame {
+            game_mode,
+            boundary_mode,
+            world_screens,
+            score_limit,
+            players,
+            ships,
+            asteroids,
+            ufos,
+            ufo_spawn_timer: reader.timer()?,
+        };
+
+        reader.0.is_empty().then_some(saved_game)
+    }
+}
+
+fn encode_transform(bytes: &mut Vec<u8>, transform: &Transform) {
+    encode_vec3(bytes, transform.translation);
+    for component in transform.rotation.to_array() {
+        bytes.extend(component.to_le_bytes());
+    }
+    encode_vec3(bytes, transform.scale);
+}
+
+fn encode_velocity(bytes: &mut Vec<u8>, velocity: &Velocity) {
+    encode_vec2(bytes, velocity.linvel);
+    bytes.extend(velocity.angvel.to_le_bytes());
+}
+
+fn encode_timer(bytes: &mut Vec<u8>, timer: &Timer) {
+    bytes.extend((timer.duration().as_nanos() as u64).to_le_bytes());
+    bytes.extend((timer.elapsed().as_nanos() as u64).to_le_bytes());
+    bytes.push((timer.mode() == TimerMode::Repeating) as u8);
+    bytes.push(timer.paused() as u8);
+}
+
+const NORMAL_FLAG: u8 = 1;
+const UV_FLAG: u8 = 1 << 1;
+
+/// Stores the positions, normals, UVs and indices of a triangle mesh as they are, so the mesh
+/// reads back bit for bit. Asteroids only have positions, ships that haven't been hit yet have
+/// normals and UVs as well.
+fn encode_mesh(bytes: &mut Vec<u8>, mesh: &Mesh) {
+    let positions = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(VertexAttributeValues::as_float3)
+        .unwrap_or_default();
+    let opt_normals = mesh
+        .attribute(Mesh::ATTRIBUTE_NORMAL)
+        .and_then(VertexAttributeValues::as_float3);
+    let opt_uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(uvs)) => Some(uvs),
+        _ => None,
+    };
+
+    let flags = (opt_normals.is_some() as u8 * NORMAL_FLAG) | (opt_uvs.is_some() as u8 * UV_FLAG);
+    bytes.push(flags);
+    bytes.extend((positions.len() as u32).to_le_bytes());
+    for values in positions.iter().chain(opt_normals.unwrap_or_default()) {
+        for value in values {
+            bytes.extend(value.to_le_bytes());
+        }
+    }
+    for uv in opt_uvs.into_iter().flatten() {
+        for value in uv {
+            bytes.extend(value.to_le_bytes());
+        }
+    }
+
+    match mesh.indices() {
+        Some(Indices::U16(indices)) => {
+            bytes.push(0);
+            bytes.extend((indices.len() as u32).to_le_bytes());
+            for index in indices {
+                bytes.extend(index.to_le_bytes());
+            }
+        }
+        Some(Indices::U32(indices)) => {
+            bytes.push(1);
+            bytes.extend((indices.len() as u32).to_le_bytes());
+            for index in indices {
+                bytes.extend(index.to_le_bytes());
+            }
+        }
+        None => bytes.push(2),
+    }
+}
+
+fn encode_player_id(id: PlayerId) -> u8 {
+    match id {
+        PlayerId::One => 0,
+        PlayerId::Two => 1,
+    }
+}
+
+fn decode_player_id(byte: u8) -> Option<PlayerId> {
+    match byte {
+        0 => Some(PlayerId::One),
+        1 => Some(PlayerId::Two),
+        _ => None,
+    }
+}
+
+/// Values only a save holds.
+impl Reader<'_> {
+    fn transform(&mut self) -> Option<Transform> {
+        Some(Transform {
+            translation: self.vec3()?,
+            rotation: Quat::from_array([self.f32()?, self.f32()?, self.f32()?, self.f32()?]),
+            scale: self.vec3()?,
+        })
+    }
+
+    fn velocity(&mut self) -> Option<Velocity> {
+        Some(Velocity {
+            linvel: self.vec2()?,
+            angvel: self.f32()?,
+        })
+    }
+
+    fn timer(&mut self) -> Option<Timer> {
+        let duration = Duration::from_nanos(self.u64()?);
+        let elapsed = Duration::from_nanos(self.u64()?);
+        let mode = match self.u8()? {
+            0 => TimerMode::Once,
+            1 => TimerMode::Repeating,
+            _ => return None,
+        };
+        let paused = self.u8()? != 0;
+
+        let mut timer = Timer::new(duration, mode);
+        timer.set_elapsed(elapsed);
+        if paused {
+            timer.pause();
+        }
+        Some(timer)
+    }
+
+    fn mesh(&mut self) -> Option<Mesh> {
+        let flags = self.u8()?;
+        let vertex_count = self.u32()?;
+        let float3 = |reader: &mut Self| Some([reader.f32()?, reader.f32()?, reader.f32()?]);
+        let positions = (0..vertex_count)
+            .map(|_| float3(self))
+            .collect::<Option<Vec<_>>>()?;
+        let opt_normals = if flags & NORMAL_FLAG != 0 {
+            Some(
+                (0..vertex_count)
+                    .map(|_| float3(self))
+                    .collect::<Option<Vec<_>>>()?,
+            )
+        } else {
+            None
+        };
+        let opt_uvs = if flags & UV_FLAG != 0 {
+            Some(
+                (0..vertex_count)
+                    .map(|_| Some([self.f32()?, self.f32()?]))
+                    .collect::<Option<Vec<_>>>()?,
+            )
+        } else {
+            None
+        };
+        let opt_indices = match self.u8()? {
+            0 => {
+                let count = self.u32()?;
+                Some(Indices::U16(
+                    (0..count).map(|_| self.u16()).collect::<Option<_>>()?,
+                ))
+            }
+            1 => {
+                let count = self.u32()?;
+                Some(Indices::U32(
+                    (0..count).map(|_| self.u32()).collect::<Option<_>>()?,
+                ))
+            }
+            2 => None,
+            _ => return None,
+        };
+
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        if let Some(normals) = opt_normals {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        }
+        if let Some(uvs) = opt_uvs {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        }
+        if let Some(indices) = opt_indices {
+            mesh.insert_indices(indices);
+        }
+        Some(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::primitives::Triangle2d;
+
+    use crate::split_mesh::{create_mesh_2d, split_mesh};
+
+    use super::*;
+
+    fn asteroid_mesh() -> Mesh {
+        let outline = (0..7)
+            .map(|i| {
+                let angle = i as f32 / 7. * std::f32::consts::TAU;
+                Vec2::from_angle(angle) * (40. + (i as f32 * 1.7).sin() * 1. / 3.)
+            })
+            .collect::<Vec<_>>();
+        let vertices = std::iter::once(Vec2::ZERO)
+            .chain(outline.iter().copied())
+            .collect::<Vec<_>>();
+        let indices = (1..=outline.len())
+            .map(|i| [0, i, i % outline.len() + 1])
+            .collect::<Vec<_>>();
+        create_mesh_2d(&vertices, &indices)
+    }
+
+    fn saved_game() -> SavedGame {
+        let mut respawn_timer = Timer::from_seconds(2., TimerMode::Once);
+        respawn_timer.tick(Duration::from_millis(700));
+        let mut spawn_timer = Timer::from_seconds(30., TimerMode::Once);
+        spawn_timer.pause();
+
+        SavedGame {
+            game_mode: GameMode::Coop,
+            boundary_mode: BoundaryMode::Walls,
+            world_screens: Vec2::new(2., 1.),
+            score_limit: 3,
+            players: vec![
+                PlayerStats {
+                    id: PlayerId::One,
+                    score: 120,
+                    lives: 2,
+                    rounds_won: 0,
+                    respawn_timer: None,
+                },
+                PlayerStats {
+                    id: PlayerId::Two,
+                    score: 30,
+                    lives: 1,
+                    rounds_won: 0,
+                    respawn_timer: Some(respawn_timer),
+                },
+            ],
+            ships: vec![SavedShip {
+                id: PlayerId::One,
+                transform: Transform::from_xyz(10.5, -3.25, 0.)
+                    .with_rotation(Quat::from_rotation_z(0.3)),
+                velocity: Velocity {
+                    linvel: Vec2::new(1. / 3., -7.),
+                    angvel: 0.1,
+                },
+                mesh: Mesh::from(Triangle2d::new(
+                    Vec2::new(0., 20.),
+                    Vec2::new(-14., -14.),
+                    Vec2::new(14., -14.),
+                )),
+                hull: Hull {
+                    initial_area: 476.,
+                    integrity: 0.8,
+                },
+                missiles: 2,
+                thrusters: vec![Vec3::new(-9., -16., -1.), Vec3::new(0., -16., -1.)],
+                exhaust_offset: Vec2::new(0., -18.),
+            }],
+            asteroids: vec![SavedAsteroid {
+                transform: Transform::from_xyz(-200., 150., 0.),
+                velocity: Velocity {
+                    linvel: Vec2::new(12., 4.),
+                    angvel: -0.5,
+                },
+                mesh: asteroid_mesh(),
+            }],
+            ufos: vec![SavedUfo {
+                transform: Transform::from_xyz(300., 0., 0.),
+                velocity: Velocity::linear(Vec2::new(-50., 0.)),
+                tractor_beam: TractorBeam::from_state(
+                    false,
+                    Timer::from_seconds(4., TimerMode::Once),
+                ),
+            }],
+            ufo_spawn_timer: spawn_timer,
+        }
+    }
+
+    fn positions(mesh: &Mesh) -> Vec<[u32; 3]> {
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(VertexAttributeValues::as_float3)
+            .unwrap()
+            .iter()
+            .map(|position| position.map(f32::to_bits))
+            .collect()
+    }
+
+    #[test]
+    fn test_saved_game_round_trip() {
+        let saved_game = saved_game();
+        let bytes = saved_game.encode();
+        let decoded = SavedGame::decode(&bytes).expect("Failed to decode the saved game");
+
+        assert_eq!(decoded.encode(), bytes);
+        assert_eq!(decoded.game_mode, GameMode::Coop);
+        assert_eq!(
+            decoded.players[1].respawn_timer,
+            saved_game.players[1].respawn_timer
+        );
+        assert_eq!(decoded.ufo_spawn_timer, saved_game.ufo_spawn_timer);
+        assert_eq!(decoded.ships[0].transform, saved_game.ships[0].transform);
+        assert_eq!(decoded.ships[0].velocity, saved_game.ships[0].velocity);
+
+        for (original, decoded) in [
+            (&saved_game.ships[0].mesh, &decoded.ships[0].mesh),
+            (&saved_game.asteroids[0].mesh, &decoded.asteroids[0].mesh),
+        ] {
+            assert_eq!(positions(decoded), positions(original));
+            assert_eq!(
+                decoded.indices().unwrap().iter().collect::<Vec<_>>(),
+                original.indices().unwrap().iter().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                matches!(decoded.indices(), Some(Indices::U16(_))),
+                matches!(original.indices(), Some(Indices::U16(_)))
+            );
+        }
+    }
+
+    #[test]
+    fn test_restored_asteroid_splits_the_same() {
+        let saved_game = saved_game();
+        let decoded = SavedGame::decode(&saved_game.encode()).unwrap();
+
+        let direction = Vec2::new(1., 0.4).normalize();
+        let point = Vec2::new(3.3, -1.7);
+        let original_pieces = split_mesh(&saved_game.asteroids[0].mesh, direction, point);
+        let decoded_pieces = split_mesh(&decoded.asteroids[0].mesh, direction, point);
+
+        for (original, decoded) in original_pieces.iter().zip(&decoded_pieces) {
+            let (original_mesh, original_offset) = original.as_ref().unwrap();
+            let (decoded_mesh, decoded_offset) = decoded.as_ref().unwrap();
+            assert_eq!(positions(decoded_mesh), positions(original_mesh));
+            assert_eq!(decoded_offset, original_offset);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_damaged_saves() {
+        let bytes = saved_game().encode();
+
+        for length in 0..bytes.len() {
+            assert!(SavedGame::decode(&bytes[..length]).is_none());
+        }
+
+        let mut other_version = bytes.clone();
+        other_version[0] = SAVE_VERSION + 1;
+        assert!(SavedGame::decode(&other_version).is_none());
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(SavedGame::decode(&trailing).is_none());
+    }
+
+    #[test]
+    fn test_broken_meshes_are_left_out() {
+        let vertices = [Vec2::ZERO, Vec2::new(10., 0.), Vec2::new(0., 10.)];
+        let broken_meshes = [
+            // Indices past the vertices
+            create_mesh_2d(&vertices, &[[0, 1, 3]]),
+            // Clockwise winding
+            create_mesh_2d(&vertices, &[[0, 2, 1]]),
+            // No area to build a collider from
+            create_mesh_2d(&[Vec2::ZERO, Vec2::X, Vec2::X * 2.], &[[0, 1, 2]]),
+        ];
+
+        for mesh in broken_meshes {
+            let mut saved_game = saved_game();
+            saved_game.asteroids[0].mesh = mesh;
+
+            // Only the broken asteroid is lost, not the whole save
+            let decoded = SavedGame::decode(&saved_game.encode()).unwrap();
+            assert!(restored_collider(&decoded.asteroids[0].mesh).is_none());
+            assert!(restored_collider(&decoded.ships[0].mesh).is_some());
+        }
+    }
+}
@@ -19,10 +19,16 @@ use bevy_rapier2d::{
     geometry::{CollisionGroups, Group, Restitution},
 };
 use itertools::Itertools;
-use rand::{rngs::ThreadRng, Rng};
+use rand::Rng;
 use tracing::info;
 
-use crate::{edge_wrap::Duplicable, split_mesh::shatter_mesh, utils::mesh_to_collider};
+use crate::{
+    edge_wrap::Duplicable,
+    mesh_utils::mesh_center,
+    outline::Outlined,
+    split_mesh::{shatter_mesh, voronoi_fracture_mesh},
+    utils::mesh_to_collider,
+};
 
 pub struct ShatterPlugin;
 
@@ -43,47 +49,92 @@ pub struct Debris {
 const DEBRIS_MAX_AREA: f32 = 6.;
 const DEBRIS_MAX_ANG_VELOCITY: f32 = 10.;
 
+/// How a mesh is broken up into debris.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fracture {
+    /// Recursively halve the mesh along its longest axis.
+    Halving,
+    /// Break the mesh into Voronoi cells clustered around a point in the space of the mesh.
+    Voronoi { impact_point: Vec2 },
+}
+
+impl Fracture {
+    /// Voronoi fracture around the point of impact in the space of the mesh, or around the center
+    /// of the mesh when it blew up without a contact.
+    pub fn voronoi(mesh: &Mesh, opt_impact_point: Option<Vec2>) -> Self {
+        Fracture::Voronoi {
+            impact_point: opt_impact_point.unwrap_or_else(|| mesh_center(mesh)),
+        }
+    }
+
+    /// The same fracture for a piece that was cut out `offset` from the origin of the mesh.
+    pub fn for_piece(self, offset: Vec2) -> Self {
+        match self {
+            Fracture::Halving => Fracture::Halving,
+            Fracture::Voronoi { impact_point } => Fracture::Voronoi {
+                impact_point: impact_point - offset,
+            },
+        }
+    }
+
+    fn shatter(&self, mesh: &Mesh, rng: &mut impl Rng) -> Vec<(Mesh, Vec2)> {
+        match self {
+            Fracture::Halving => shatter_mesh(mesh, DEBRIS_MAX_AREA),
+            Fracture::Voronoi { impact_point } => {
+                voronoi_fracture_mesh(mesh, *impact_point, DEBRIS_MAX_AREA, rng)
+            }
+        }
+    }
+}
+
 pub fn spawn_shattered_mesh(
     mesh: &Mesh,
     material_handle: Handle<ColorMaterial>,
     transform: &Transform,
     velocity: Velocity,
+    fracture: Fracture,
     commands: &mut Commands,
     meshes: &mut Assets<Mesh>,
+    rng: &mut impl Rng,
 ) {
-    let mut rng = ThreadRng::default();
-    let shards = shatter_mesh(mesh, DEBRIS_MAX_AREA)
+    let shards = fracture
+        .shatter(mesh, rng)
         .into_iter()
-        .map(|(mesh, offset)| create_shard(transform, offset, velocity, &mut rng, mesh));
+        .map(|(mesh, offset)| create_shard(transform, offset, velocity, rng, mesh))
+        .collect_vec();
 
-    spawn_debris_batch(commands, shards, meshes, material_handle);
+    spawn_debris_batch(commands, shards.into_iter(), meshes, material_handle, rng);
 }
 
+/// Shatters several pieces at once, each with its own fracture.
 pub fn spawn_shattered_mesh_batch(
     commands: &mut Commands,
     material_handle: Handle<ColorMaterial>,
-    debris: impl Iterator<Item = (Transform, Velocity, Mesh)>,
+    debris: impl Iterator<Item = (Transform, Velocity, Mesh, Fracture)>,
     meshes: &mut ResMut<Assets<Mesh>>,
+    rng: &mut impl Rng,
 ) {
-    let mut rng = ThreadRng::default();
-    let debris_bundles = debris
-        .flat_map(|(transform, velocity, mesh)| {
-            shatter_mesh(&mesh, DEBRIS_MAX_AREA)
-                .into_iter()
-                .map(move |(mesh, offset)| (transform, velocity, mesh, offset))
-        })
-        .map(move |(transform, velocity, mesh, offset)| {
-            create_shard(&transform, offset, velocity, &mut rng, mesh)
-        });
+    let mut debris_bundles = Vec::new();
+    for (transform, velocity, mesh, fracture) in debris {
+        for (mesh, offset) in fracture.shatter(&mesh, rng) {
+            debris_bundles.push(create_shard(&transform, offset, velocity, rng, mesh));
+        }
+    }
 
-    spawn_debris_batch(commands, debris_bundles, meshes, material_handle);
+    spawn_debris_batch(
+        commands,
+        debris_bundles.into_iter(),
+        meshes,
+        material_handle,
+        rng,
+    );
 }
 
 fn create_shard(
     origin: &Transform,
     offset: Vec2,
     velocity: Velocity,
-    rng: &mut ThreadRng,
+    rng: &mut impl Rng,
     mesh: Mesh,
 ) -> (Transform, Velocity, Mesh) {
     let shard_translation = origin.transform_point(offset.extend(0.));
@@ -92,8 +143,14 @@ fn create_shard(
 
     let rng_range_max = 5.;
 
+    // Shards fly away from the origin, a piece too small to break up stays on it and only drifts
     let velocity = Velocity {
-        linvel: origin.rotation.mul_vec3(offset.extend(0.)).normalize().xy() * 15.
+        linvel: origin
+            .rotation
+            .mul_vec3(offset.extend(0.))
+            .normalize_or_zero()
+            .xy()
+            * 15.
             + velocity.linvel
             + Vec2::new(
                 rng.gen_range(-rng_range_max..rng_range_max),
@@ -112,12 +169,13 @@ fn spawn_debris_batch(
     debris: impl Iterator<Item = (Transform, Velocity, Mesh)>,
     meshes: &mut Assets<Mesh>,
     material_handle: Handle<ColorMaterial>,
+    rng: &mut impl Rng,
 ) {
-    let mut rng = ThreadRng::default();
     let debris_bundles = debris
-        .map(|(transform, velocity, mesh)| {
-            let collider = mesh_to_collider(&mesh).expect("Failed to create collider");
-            (
+        .filter_map(|(transform, velocity, mesh)| {
+            // Slivers too thin for a collider are too small to be seen anyway
+            let collider = mesh_to_collider(&mesh).ok()?;
+            Some((
                 Debris {
                     lifetime: Timer::from_seconds(rng.gen_range(0.5..5.0), TimerMode::Once),
                 },
@@ -130,6 +188,7 @@ fn spawn_debris_batch(
                 collider,
                 CollisionGroups::new(DEBRIS_GROUP, Group::NONE),
                 Duplicable,
+                Outlined,
                 RigidBody::Dynamic,
                 velocity,
                 Restitution {
@@ -141,7 +200,7 @@ fn spawn_debris_batch(
                     angular_threshold: 0.001,
                     ..default()
                 },
-            )
+            ))
         })
         .collect_vec();
 
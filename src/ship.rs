@@ -7,7 +7,7 @@ use bevy::{
         component::Component,
         entity::Entity,
         event::{Event, EventReader, EventWriter},
-        query::With,
+        query::{With, Without},
         schedule::{IntoSystemConfigs, SystemSet},
         system::{Commands, EntityCommand, EntityCommands, Query, Res, ResMut, Resource},
         world::{Mut, World},
@@ -30,13 +30,20 @@ use bevy_rapier2d::{
     plugin::RapierContext,
     prelude::CollisionEvent,
 };
+use rand::Rng;
 
 use crate::{
     asteroid::{Asteroid, SplitAsteroidEvent},
-    edge_wrap::Duplicable,
+    edge_wrap::{DamagedByVoid, Duplicable, LeftBoundsEvent},
     explosion::ExplosionEvent,
-    shatter::spawn_shattered_mesh,
-    utils::{contact_position_and_normal, mesh_to_collider},
+    mesh_cut::cut_mesh_along_polyline,
+    mesh_utils::calculate_mesh_area,
+    outline::Outlined,
+    particle::{ParticleEmitter, THRUSTER_EXHAUST},
+    rng::GameRng,
+    rollback::RollbackAppExt,
+    shatter::{spawn_shattered_mesh, spawn_shattered_mesh_batch, Fracture},
+    utils::{contact_position_and_normal, local_contact_point, mesh_to_collider},
 };
 
 pub struct ShipPlugin;
@@ -44,10 +51,21 @@ pub struct ShipPlugin;
 impl Plugin for ShipPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ShipDestroyedEvent>()
+            .add_event::<ShipHitEvent>()
+            .rollback_component::<Ship>()
+            .rollback_component::<Thruster>()
+            .rollback_component::<Hull>()
+            .rollback_component::<Throttling>()
             .add_systems(Startup, load_ship_assets)
             .add_systems(
                 Update,
-                (ship_movement, ship_asteroid_collision, explode_ship)
+                (
+                    ship_movement,
+                    ship_asteroid_collision,
+                    ship_left_bounds,
+                    damage_ship,
+                    explode_ship,
+                )
                     .chain()
                     .in_set(ShipSet),
             );
@@ -59,40 +77,65 @@ pub struct ShipSet;
 
 #[derive(Resource)]
 struct ShipAssets {
-    material: Handle<ColorMaterial>,
     thruster_sound: Handle<AudioSource>,
 }
 
-fn load_ship_assets(
-    mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: Res<AssetServer>,
-) {
+fn load_ship_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(ShipAssets {
-        material: materials.add(ColorMaterial::from(Color::WHITE)),
         thruster_sound: asset_server.load("audio/thrusters.mp3"),
     });
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Ship;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Thruster;
 
+/// Remaining hull of a ship, relative to the hull it spawned with.
+#[derive(Component, Debug, Clone)]
+pub struct Hull {
+    /// Area of the hull mesh the ship spawned with.
+    pub initial_area: f32,
+    pub integrity: f32,
+}
+
+impl Hull {
+    fn new(area: f32) -> Self {
+        Self {
+            initial_area: area,
+            integrity: 1.,
+        }
+    }
+}
+
 pub const SHIP_GROUP: Group = Group::GROUP_1;
 pub const SHIP_FILTER: Group = Group::ALL;
 const SHIP_TIP_Y: f32 = 20.;
 const SHIP_SIDE_Y: f32 = -14.;
 const SHIP_SIDE_X: f32 = 14.;
+/// Impacts faster than this destroy the ship outright.
+const SHIP_FATAL_IMPACT_SPEED: f32 = 400.;
+const SHIP_CHIP_DEPTH_PER_IMPACT_SPEED: f32 = 0.05;
+const SHIP_MIN_CHIP_DEPTH: f32 = 3.;
+const SHIP_MAX_CHIP_DEPTH: f32 = 12.;
+const SHIP_CRACK_SEGMENTS: i32 = 6;
+const SHIP_CRACK_SEGMENT_LENGTH: f32 = 8.;
+/// Sideways deviation of the crack points, relative to the chip depth.
+const SHIP_CRACK_ROUGHNESS: f32 = 0.4;
+/// The ship is destroyed once less than this fraction of its hull remains.
+const SHIP_MIN_HULL_INTEGRITY: f32 = 0.5;
+/// Exhaust particles per second while throttling.
+const SHIP_EXHAUST_RATE: f32 = 60.;
 
 struct SpawnShip {
     transform: Transform,
+    color: Color,
 }
 
 impl EntityCommand for SpawnShip {
     fn apply(self, entity: Entity, world: &mut World) {
-        let (ship_mesh_handle, collider) =
+        let (ship_mesh_handle, collider, hull) =
             world.resource_scope(|_world, mut meshes: Mut<Assets<Mesh>>| {
                 let mesh = Mesh::from(Triangle2d::new(
                     Vec2::new(0., SHIP_TIP_Y),
@@ -101,12 +144,13 @@ impl EntityCommand for SpawnShip {
                 ));
 
                 let collider = mesh_to_collider(&mesh).expect("Failed to create collider");
-                (meshes.add(mesh), collider)
+                let hull = Hull::new(calculate_mesh_area(&mesh));
+                (meshes.add(mesh), collider, hull)
             });
 
         let ship_material_handle =
             world.resource_scope(|_world, mut materials: Mut<Assets<ColorMaterial>>| {
-                materials.add(ColorMaterial::from(Color::WHITE))
+                materials.add(ColorMaterial::from(self.color))
             });
 
         let thruster_mesh_handle: Mesh2dHandle = world
@@ -124,6 +168,7 @@ impl EntityCommand for SpawnShip {
             .entity_mut(entity)
             .insert((
                 Ship,
+                hull,
                 MaterialMesh2dBundle {
                     mesh: ship_mesh_handle.into(),
                     material: ship_material_handle,
@@ -131,10 +176,19 @@ impl EntityCommand for SpawnShip {
                     ..default()
                 },
                 RigidBody::Dynamic,
+                Velocity::zero(),
                 collider,
                 Duplicable,
+                DamagedByVoid,
+                Outlined,
                 ActiveEvents::COLLISION_EVENTS,
                 CollisionGroups::new(SHIP_GROUP, SHIP_FILTER),
+                ParticleEmitter::new(
+                    THRUSTER_EXHAUST,
+                    SHIP_EXHAUST_RATE,
+                    Vec2::new(0., SHIP_SIDE_Y),
+                    Vec2::NEG_Y,
+                ),
             ))
             .with_children(|parent| {
                 for x in [-9., 0., 9.] {
@@ -159,18 +213,18 @@ impl EntityCommand for SpawnShip {
 }
 
 pub trait SpawnShipExt {
-    fn spawn_ship(&mut self, transform: Transform) -> EntityCommands;
+    fn spawn_ship(&mut self, transform: Transform, color: Color) -> EntityCommands;
 }
 
 impl<'w, 's> SpawnShipExt for Commands<'w, 's> {
-    fn spawn_ship(&mut self, transform: Transform) -> EntityCommands {
+    fn spawn_ship(&mut self, transform: Transform, color: Color) -> EntityCommands {
         let mut e = self.spawn_empty();
-        e.add(SpawnShip { transform });
+        e.add(SpawnShip { transform, color });
         e
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Throttling;
 
 #[derive(Component)]
@@ -178,7 +232,17 @@ struct ThrusterSound;
 
 fn ship_movement(
     mut commands: Commands,
-    ship_query: Query<(Entity, &Transform, Option<&Throttling>, &Children), With<Ship>>,
+    mut ship_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&Throttling>,
+            &Children,
+            Option<&Hull>,
+            Option<&mut ParticleEmitter>,
+        ),
+        With<Ship>,
+    >,
     mut thruster_query: Query<&mut Handle<ColorMaterial>, With<Thruster>>,
     thruster_sound_query: Query<(Entity, &Parent), With<ThrusterSound>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
@@ -187,8 +251,15 @@ fn ship_movement(
 ) {
     let ship_power = 800.;
 
-    for (ship_entity, global_transform, throttling, children) in &ship_query {
+    for (ship_entity, global_transform, throttling, children, opt_hull, opt_exhaust) in
+        &mut ship_query
+    {
+        if let Some(mut exhaust) = opt_exhaust {
+            exhaust.active = throttling.is_some();
+        }
+
         if throttling.is_some() {
+            let ship_power = ship_power * opt_hull.map_or(1., |hull| hull.integrity);
             let force = global_transform
                 .rotation
                 .mul_vec3(Vec3::new(0., 1., 0.))
@@ -283,14 +354,25 @@ fn set_thrusters_visibility(
 #[derive(Event)]
 pub struct ShipDestroyedEvent {
     pub ship_entity: Entity,
+    /// Point of impact in the local space of the ship, if it was destroyed by a collision.
+    pub impact_point: Option<Vec2>,
+}
+
+#[derive(Event)]
+pub struct ShipHitEvent {
+    pub ship_entity: Entity,
+    /// Point of impact in the local space of the ship.
+    pub impact_point: Vec2,
+    pub impact_speed: f32,
 }
 
 fn ship_asteroid_collision(
     rapier_context: Res<RapierContext>,
     mut collision_events: EventReader<CollisionEvent>,
-    ship_query: Query<(&Transform, Option<&Velocity>, &mut Mesh2dHandle), With<Ship>>,
-    asteroid_query: Query<Entity, With<Asteroid>>,
-    mut ship_destroyed_events: EventWriter<ShipDestroyedEvent>,
+    ship_query: Query<&Transform, With<Ship>>,
+    asteroid_query: Query<&Transform, With<Asteroid>>,
+    velocity_query: Query<&Velocity>,
+    mut ship_hit_events: EventWriter<ShipHitEvent>,
     mut split_asteroid_events: EventWriter<SplitAsteroidEvent>,
 ) {
     for event in collision_events.read() {
@@ -305,12 +387,42 @@ fn ship_asteroid_collision(
                 };
             info!("Ship collided with asteroid");
 
-            ship_destroyed_events.send(ShipDestroyedEvent { ship_entity });
+            let ship_transform = ship_query.get(ship_entity).unwrap();
+            let asteroid_transform = asteroid_query.get(asteroid_entity).unwrap();
+
+            let velocity_of = |entity| {
+                velocity_query
+                    .get(entity)
+                    .map_or(Vec2::ZERO, |velocity| velocity.linvel)
+            };
+            let impact_speed = (velocity_of(ship_entity) - velocity_of(asteroid_entity)).length();
+
+            let impact_point = local_contact_point(&rapier_context, ship_entity, asteroid_entity)
+                .unwrap_or_else(|| {
+                    // Fall back to the side of the hull facing the asteroid
+                    let asteroid_direction = ship_transform
+                        .rotation
+                        .inverse()
+                        .mul_vec3(asteroid_transform.translation - ship_transform.translation)
+                        .xy()
+                        .normalize_or_zero();
+                    asteroid_direction * SHIP_TIP_Y
+                });
+
+            ship_hit_events.send(ShipHitEvent {
+                ship_entity,
+                impact_point,
+                impact_speed,
+            });
 
             let Some((collision_position, collision_direction)) =
                 contact_position_and_normal(&rapier_context, ship_entity, asteroid_entity)
             else {
-                warn!("No collision position found");
+                continue;
+            };
+            let Some(asteroid_impact_point) =
+                local_contact_point(&rapier_context, asteroid_entity, ship_entity)
+            else {
                 continue;
             };
 
@@ -318,21 +430,227 @@ fn ship_asteroid_collision(
                 asteroid_entity,
                 collision_direction,
                 collision_position,
+                impact_point: asteroid_impact_point,
+                impact_speed,
+            });
+        }
+    }
+}
+
+/// Leaving the bounds into the void is fatal for the ship.
+fn ship_left_bounds(
+    mut left_bounds_events: EventReader<LeftBoundsEvent>,
+    ship_query: Query<(), With<Ship>>,
+    mut ship_destroyed_events: EventWriter<ShipDestroyedEvent>,
+) {
+    for event in left_bounds_events.read() {
+        if ship_query.contains(event.entity) {
+            ship_destroyed_events.send(ShipDestroyedEvent {
+                ship_entity: event.entity,
+                impact_point: None,
             });
         }
     }
 }
 
+fn damage_ship(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut ship_hit_events: EventReader<ShipHitEvent>,
+    mut ship_query: Query<
+        (
+            &mut Transform,
+            Option<&Velocity>,
+            &Mesh2dHandle,
+            &Handle<ColorMaterial>,
+            &mut Hull,
+            &Children,
+            Option<&mut ParticleEmitter>,
+        ),
+        With<Ship>,
+    >,
+    mut thruster_query: Query<&mut Transform, (With<Thruster>, Without<Ship>)>,
+    mut ship_destroyed_events: EventWriter<ShipDestroyedEvent>,
+    mut explosion_events: EventWriter<ExplosionEvent>,
+    mut rng: ResMut<GameRng>,
+) {
+    let mut hit_ships = Vec::new();
+    let rng = &mut rng.0;
+
+    for hit in ship_hit_events.read() {
+        // The mesh is only replaced once the commands are applied, so further hits this frame
+        // would chip the old hull.
+        if hit_ships.contains(&hit.ship_entity) {
+            continue;
+        }
+        hit_ships.push(hit.ship_entity);
+
+        let Ok((
+            mut ship_transform,
+            opt_ship_velocity,
+            mesh_handle,
+            material_handle,
+            mut hull,
+            children,
+            opt_exhaust,
+        )) = ship_query.get_mut(hit.ship_entity)
+        else {
+            continue;
+        };
+
+        if hit.impact_speed > SHIP_FATAL_IMPACT_SPEED {
+            ship_destroyed_events.send(ShipDestroyedEvent {
+                ship_entity: hit.ship_entity,
+                impact_point: Some(hit.impact_point),
+            });
+            continue;
+        }
+
+        let mesh = meshes.get(&mesh_handle.0).expect("Ship mesh not found");
+
+        // Crack the hull along a jagged line perpendicular to the impact, a bit inside of the
+        // point of impact
+        let impact_direction = hit.impact_point.try_normalize().unwrap_or(Vec2::Y);
+        let chip_depth = (hit.impact_speed * SHIP_CHIP_DEPTH_PER_IMPACT_SPEED)
+            .clamp(SHIP_MIN_CHIP_DEPTH, SHIP_MAX_CHIP_DEPTH);
+        let crack = jagged_crack(
+            rng,
+            hit.impact_point - impact_direction * chip_depth,
+            impact_direction.perp(),
+            chip_depth * SHIP_CRACK_ROUGHNESS,
+        );
+        // Walking along the crack, the side facing away from the impact is on the left
+        let [mut hull_pieces, mut chips] = cut_mesh_along_polyline(mesh, &crack);
+
+        // The crack can break off more than one piece, only the largest one stays attached
+        hull_pieces
+            .sort_by(|(a, _), (b, _)| calculate_mesh_area(a).total_cmp(&calculate_mesh_area(b)));
+        let Some((hull_mesh, hull_offset)) = hull_pieces.pop() else {
+            ship_destroyed_events.send(ShipDestroyedEvent {
+                ship_entity: hit.ship_entity,
+                impact_point: Some(hit.impact_point),
+            });
+            continue;
+        };
+        chips.extend(hull_pieces);
+
+        if chips.is_empty() {
+            continue;
+        }
+
+        hull.integrity = calculate_mesh_area(&hull_mesh) / hull.initial_area;
+        if hull.integrity < SHIP_MIN_HULL_INTEGRITY {
+            ship_destroyed_events.send(ShipDestroyedEvent {
+                ship_entity: hit.ship_entity,
+                impact_point: Some(hit.impact_point),
+            });
+            continue;
+        }
+
+        let collider = match mesh_to_collider(&hull_mesh) {
+            Ok(collider) => collider,
+            Err(error) => {
+                warn!(%error, "Failed to create a collider for the damaged hull");
+                ship_destroyed_events.send(ShipDestroyedEvent {
+                    ship_entity: hit.ship_entity,
+                    impact_point: Some(hit.impact_point),
+                });
+                continue;
+            }
+        };
+
+        let chip_velocity = opt_ship_velocity.copied().unwrap_or_else(Velocity::zero);
+        let chips = chips
+            .into_iter()
+            .map(|(chip_mesh, chip_offset)| {
+                let chip_translation = ship_transform.transform_point(chip_offset.extend(0.));
+                explosion_events.send(ExplosionEvent {
+                    position: chip_translation.xy(),
+                    radius: 3.,
+                });
+                (
+                    Transform::from_translation(chip_translation)
+                        .with_rotation(ship_transform.rotation),
+                    chip_velocity,
+                    chip_mesh,
+                    Fracture::Halving,
+                )
+            })
+            .collect::<Vec<_>>();
+        spawn_shattered_mesh_batch(
+            &mut commands,
+            material_handle.clone(),
+            chips.into_iter(),
+            &mut meshes,
+            rng,
+        );
+
+        // The remaining hull is recentered, move the ship, its thrusters and exhaust so it stays
+        // in place
+        ship_transform.translation = ship_transform.transform_point(hull_offset.extend(0.));
+        for child_entity in children.iter() {
+            if let Ok(mut thruster_transform) = thruster_query.get_mut(*child_entity) {
+                thruster_transform.translation -= hull_offset.extend(0.);
+            }
+        }
+        if let Some(mut exhaust) = opt_exhaust {
+            exhaust.offset -= hull_offset;
+        }
+
+        commands
+            .entity(hit.ship_entity)
+            .insert((Mesh2dHandle(meshes.add(hull_mesh)), collider));
+
+        info!(integrity = hull.integrity, "Ship hull damaged");
+    }
+}
+
+/// Jagged line through `center` along `direction`, long enough to cross the whole hull.
+fn jagged_crack(rng: &mut impl Rng, center: Vec2, direction: Vec2, roughness: f32) -> Vec<Vec2> {
+    (-SHIP_CRACK_SEGMENTS / 2..=SHIP_CRACK_SEGMENTS / 2)
+        .map(|i| {
+            let deviation = if i == 0 {
+                0.
+            } else {
+                rng.gen_range(-roughness..=roughness)
+            };
+            center
+                + direction * (i as f32 * SHIP_CRACK_SEGMENT_LENGTH)
+                + direction.perp() * deviation
+        })
+        .collect()
+}
+
 fn explode_ship(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    ship_assets: Res<ShipAssets>,
     mut ship_destroyed_events: EventReader<ShipDestroyedEvent>,
-    ship_query: Query<(&Transform, Option<&Velocity>, &mut Mesh2dHandle), With<Ship>>,
+    ship_query: Query<
+        (
+            &Transform,
+            Option<&Velocity>,
+            &Mesh2dHandle,
+            &Handle<ColorMaterial>,
+        ),
+        With<Ship>,
+    >,
     mut explosion_events: EventWriter<ExplosionEvent>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for ShipDestroyedEvent { ship_entity } in ship_destroyed_events.read() {
-        let (ship_transform, ship_velocity, ship_mesh_handle) =
+    let mut destroyed_ships = Vec::new();
+
+    for ShipDestroyedEvent {
+        ship_entity,
+        impact_point,
+    } in ship_destroyed_events.read()
+    {
+        // A ship can leave the bounds and crash in the same frame
+        if destroyed_ships.contains(ship_entity) {
+            continue;
+        }
+        destroyed_ships.push(*ship_entity);
+
+        let (ship_transform, ship_velocity, ship_mesh_handle, ship_material_handle) =
             ship_query.get(*ship_entity).unwrap();
 
         let mesh = meshes
@@ -342,11 +660,13 @@ fn explode_ship(
 
         spawn_shattered_mesh(
             &mesh,
-            ship_assets.material.clone(),
+            ship_material_handle.clone(),
             ship_transform,
             ship_velocity.copied().unwrap_or_else(Velocity::zero),
+            Fracture::voronoi(&mesh, *impact_point),
             &mut commands,
             &mut meshes,
+            &mut rng.0,
         );
         explosion_events.send(ExplosionEvent {
             position: ship_transform.translation.xy(),
@@ -7,13 +7,16 @@ use bevy::{
     utils::HashSet,
 };
 use itertools::Itertools;
-use rand::seq::IteratorRandom;
+use rand::{seq::IteratorRandom, Rng};
 use smallvec::SmallVec;
 use tracing::instrument;
 
-use crate::mesh_utils::{
-    calculate_mesh_area, distance_to_plane, ensure_ccw, get_intersection_points_2d,
-    mesh_longest_axis, valid_mesh,
+use crate::{
+    mesh_cut::{cut_mesh_into_cells, voronoi_cells},
+    mesh_utils::{
+        calculate_mesh_area, distance_to_plane, ensure_ccw, get_intersection_points_2d,
+        mesh_longest_axis, splits_triangle, valid_mesh, validate_mesh,
+    },
 };
 
 #[instrument(skip(mesh, split_plane_direction, plane_point))]
@@ -95,12 +98,18 @@ pub fn split_mesh(
             return None;
         }
         remove_unused_vertices(vertices, indices);
-        merge_vertices(vertices, indices);
+        merge_vertices(vertices, indices, SPLIT_MERGE_DISTANCE);
+        // Recentering moves the vertices slightly, repair afterwards so the mesh is checked
+        // exactly as repaired
         let offset = recenter_mesh(vertices);
+        repair_mesh_geometry(vertices, indices);
+        if indices.is_empty() {
+            return None;
+        }
 
         let mesh = create_mesh_2d(vertices, indices);
 
-        if valid_mesh(&mesh) {
+        if valid_mesh(&mesh) && check_mesh(&mesh) {
             Some((mesh, offset))
         } else {
             None
@@ -108,8 +117,8 @@ pub fn split_mesh(
     })
 }
 
-#[instrument(skip(mesh))]
-pub fn trim_mesh(mesh: Mesh) -> ((Mesh, Vec2), Vec<(Mesh, Vec2)>) {
+#[instrument(skip(mesh, rng))]
+pub fn trim_mesh(mesh: Mesh, rng: &mut impl Rng) -> ((Mesh, Vec2), Vec<(Mesh, Vec2)>) {
     let mut main_mesh = mesh.clone();
     debug_assert!(valid_mesh(&main_mesh));
 
@@ -123,12 +132,10 @@ pub fn trim_mesh(mesh: Mesh) -> ((Mesh, Vec2), Vec<(Mesh, Vec2)>) {
         .as_float3()
         .expect("Only Float32x3 positions are supported");
 
-    let mut rng = rand::thread_rng();
-
     for vertex in vertices
         .iter()
         .filter(|v| v[0].abs() > 0. && v[1].abs() > 0.)
-        .choose_multiple(&mut rng, 5)
+        .choose_multiple(rng, 5)
     {
         let vertex = Vec2::new(vertex[0], vertex[1]);
         let vertex_direction = vertex.normalize(); // Assume (0, 0) is the center of the mesh
@@ -142,7 +149,8 @@ pub fn trim_mesh(mesh: Mesh) -> ((Mesh, Vec2), Vec<(Mesh, Vec2)>) {
 
         let [Some((mesh_a, offset_a)), trim] = split_mesh(&main_mesh, normal, vertex_position)
         else {
-            unreachable!("Mesh should be valid");
+            // Nothing usable is left on the main side of the cut, leave this corner untrimmed
+            continue;
         };
 
         if let Some((mesh_b, offset_b)) = trim {
@@ -157,6 +165,12 @@ pub fn trim_mesh(mesh: Mesh) -> ((Mesh, Vec2), Vec<(Mesh, Vec2)>) {
 }
 
 const SHATTER_MAX_RECURSION_DEPTH: u32 = 3;
+/// Vertices of the split halves closer than this are merged.
+const SPLIT_MERGE_DISTANCE: f32 = 0.5;
+/// Vertices closer than this are merged when repairing a mesh.
+const REPAIR_MERGE_DISTANCE: f32 = 0.01;
+/// Triangles thinner than this are dropped when repairing a mesh.
+const SLIVER_THICKNESS: f32 = 0.01;
 
 #[instrument(skip(mesh))]
 pub fn shatter_mesh(mesh: &Mesh, max_shard_area: f32) -> Vec<(Mesh, Vec2)> {
@@ -184,6 +198,49 @@ pub fn shatter_mesh(mesh: &Mesh, max_shard_area: f32) -> Vec<(Mesh, Vec2)> {
     result
 }
 
+const VORONOI_MAX_SHARDS: usize = 24;
+
+/// Fractures the mesh into Voronoi cells with seeds clustered around `impact_point`, so the shards
+/// are small near the impact and larger further away.
+#[instrument(skip(mesh, rng))]
+pub fn voronoi_fracture_mesh(
+    mesh: &Mesh,
+    impact_point: Vec2,
+    max_shard_area: f32,
+    rng: &mut impl Rng,
+) -> Vec<(Mesh, Vec2)> {
+    let shard_count =
+        ((calculate_mesh_area(mesh) / max_shard_area).ceil() as usize).clamp(1, VORONOI_MAX_SHARDS);
+    if shard_count == 1 {
+        return vec![(mesh.clone(), Vec2::ZERO)];
+    }
+
+    let vertices = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .unwrap()
+        .as_float3()
+        .expect("Only Float32x3 positions are supported");
+    let (min, max) = vertices.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), vertex| {
+            let vertex = Vec2::new(vertex[0], vertex[1]);
+            (min.min(vertex), max.max(vertex))
+        },
+    );
+    let spread = (max - min).length();
+
+    let seeds = (0..shard_count)
+        .map(|_| {
+            // Squaring the distance clusters the seeds around the impact point
+            let distance = rng.gen::<f32>().powi(2) * spread;
+            impact_point + Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)) * distance
+        })
+        .collect_vec();
+
+    let cells = voronoi_cells(&seeds, min - Vec2::ONE, max + Vec2::ONE);
+    cut_mesh_into_cells(mesh, &cells)
+}
+
 fn split_triangle(
     plane: Plane2d,
     plane_point: Vec2,
@@ -228,7 +285,7 @@ fn split_triangle(
     indices_b.push(new_indices_b2);
 }
 
-fn create_mesh_2d(vertices: &[Vec2], indices: &[[usize; 3]]) -> Mesh {
+pub(crate) fn create_mesh_2d(vertices: &[Vec2], indices: &[[usize; 3]]) -> Mesh {
     let mut mesh = Mesh::new(
         PrimitiveTopology::TriangleList,
         RenderAssetUsages::default(),
@@ -279,21 +336,39 @@ fn remove_unused_vertices(vertices: &mut Vec<Vec2>, indices: &mut [[usize; 3]])
     }
 }
 
+/// Merges vertices closer than `distance` and drops the triangles that collapse because of it.
+///
+/// Chains of close vertices are merged as a whole, splitting them between two merged vertices
+/// could fold the triangles in between over their neighbours.
 #[instrument(skip(vertices, indices))]
-fn merge_vertices(vertices: &mut Vec<Vec2>, indices: &mut Vec<[usize; 3]>) {
+fn merge_vertices(vertices: &mut Vec<Vec2>, indices: &mut Vec<[usize; 3]>, distance: f32) {
+    let mut parents = (0..vertices.len()).collect_vec();
+    fn root(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+        index
+    }
+    for (a, b) in (0..vertices.len()).tuple_combinations() {
+        if vertices[a].abs_diff_eq(vertices[b], distance) {
+            let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+            // The first vertex of a chain stays in place
+            parents[root_a.max(root_b)] = root_a.min(root_b);
+        }
+    }
+
     let mut new_indices = vec![0; vertices.len()];
 
     let mut unique_vertices = Vec::new();
 
     for (index, &vertex) in vertices.iter().enumerate() {
-        if let Some(existing_index) = unique_vertices
-            .iter()
-            .position(|v: &Vec2| v.abs_diff_eq(vertex, 0.5))
-        {
-            new_indices[index] = existing_index;
-        } else {
+        let cluster = root(&mut parents, index);
+        if cluster == index {
             new_indices[index] = unique_vertices.len();
             unique_vertices.push(vertex);
+        } else {
+            new_indices[index] = new_indices[cluster];
         }
     }
 
@@ -301,8 +376,10 @@ fn merge_vertices(vertices: &mut Vec<Vec2>, indices: &mut Vec<[usize; 3]>) {
     for index in indices.iter_mut() {
         let [a, b, c] = *index;
         let new_index = [new_indices[a], new_indices[b], new_indices[c]];
-        let same_index = new_index[0] == new_index[1] && new_index[1] == new_index[2];
-        if !same_index {
+        let collapsed = new_index[0] == new_index[1]
+            || new_index[1] == new_index[2]
+            || new_index[0] == new_index[2];
+        if !collapsed {
             filtered_indices.push(new_index);
         }
     }
@@ -311,6 +388,75 @@ fn merge_vertices(vertices: &mut Vec<Vec2>, indices: &mut Vec<[usize; 3]>) {
     *indices = filtered_indices;
 }
 
+/// Fixes up triangle soup left behind by cutting: merges near-duplicate vertices, fixes the
+/// winding, splits triangles at T-junctions and drops slivers.
+#[instrument(skip(vertices, indices))]
+pub(crate) fn repair_mesh_geometry(vertices: &mut Vec<Vec2>, indices: &mut Vec<[usize; 3]>) {
+    merge_vertices(vertices, indices, REPAIR_MERGE_DISTANCE);
+
+    for triangle in indices.iter_mut() {
+        ensure_ccw(vertices, triangle);
+    }
+
+    indices.retain(|triangle| !is_sliver(vertices, *triangle));
+    split_t_junctions(vertices, indices);
+
+    remove_unused_vertices(vertices, indices);
+}
+
+fn is_sliver(vertices: &[Vec2], [a, b, c]: [usize; 3]) -> bool {
+    let [a, b, c] = [vertices[a], vertices[b], vertices[c]];
+    let longest_edge = (b - a).length().max((c - b).length()).max((a - c).length());
+    (b - a).perp_dot(c - a).abs() / longest_edge <= SLIVER_THICKNESS
+}
+
+/// Splits every triangle with another vertex on one of its edges in two at that vertex.
+///
+/// Thin halves are fine, only junctions that would leave a half without any area or turned around
+/// are kept.
+fn split_t_junctions(vertices: &[Vec2], indices: &mut Vec<[usize; 3]>) {
+    let used_vertices = indices.iter().flatten().copied().unique().collect_vec();
+
+    let mut triangle_index = 0;
+    while triangle_index < indices.len() {
+        let triangle = indices[triangle_index];
+        let junction = (0..3).find_map(|edge| {
+            let [a, b, c] = [
+                triangle[edge],
+                triangle[(edge + 1) % 3],
+                triangle[(edge + 2) % 3],
+            ];
+            used_vertices
+                .iter()
+                .find(|&&vertex| {
+                    !triangle.contains(&vertex)
+                        && splits_triangle(vertices[vertex], [a, b, c].map(|index| vertices[index]))
+                })
+                .map(|&vertex| [a, b, c, vertex])
+        });
+
+        match junction {
+            // The halves are checked again, in case more vertices lie on the split edge
+            Some([a, b, c, vertex]) => {
+                indices[triangle_index] = [a, vertex, c];
+                indices.push([vertex, b, c]);
+            }
+            None => triangle_index += 1,
+        }
+    }
+}
+
+/// Whether the mesh is free of defects, logging any the repair could not fix.
+///
+/// Defective meshes are rejected, they can't be split or turned into a collider reliably.
+pub(crate) fn check_mesh(mesh: &Mesh) -> bool {
+    let defects = validate_mesh(mesh);
+    if !defects.is_empty() {
+        warn!(?defects, "Rejecting a mesh with defects after repair");
+    }
+    defects.is_empty()
+}
+
 fn vertices_center(vertices: &[Vec2]) -> Vec2 {
     let (min, max) = vertices.iter().fold(
         (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
@@ -319,7 +465,7 @@ fn vertices_center(vertices: &[Vec2]) -> Vec2 {
     (min + max) / 2.0
 }
 
-fn recenter_mesh(vertices: &mut [Vec2]) -> Vec2 {
+pub(crate) fn recenter_mesh(vertices: &mut [Vec2]) -> Vec2 {
     let center = vertices_center(vertices);
     for vertex in vertices.iter_mut() {
         *vertex -= center;
@@ -334,6 +480,63 @@ mod tests {
     use super::*;
     use assert_approx_eq::assert_approx_eq;
 
+    #[test]
+    fn test_repair_mesh_geometry() {
+        let mut vertices = vec![
+            Vec2::new(0., 0.),
+            Vec2::new(2., 0.),
+            Vec2::new(0., 2.),
+            Vec2::new(2., 2.),
+            Vec2::new(1., 0.),
+            Vec2::new(0.001, 2.),
+            Vec2::new(4., 0.),
+            Vec2::new(4., 0.001),
+            Vec2::new(1., -1.),
+        ];
+        let mut indices = vec![
+            // Clockwise, with vertex 4 on its edge
+            [0, 2, 1],
+            // Uses a near duplicate of vertex 2
+            [1, 3, 5],
+            // Sliver
+            [1, 6, 7],
+            [0, 8, 4],
+        ];
+
+        repair_mesh_geometry(&mut vertices, &mut indices);
+
+        let mesh = create_mesh_2d(&vertices, &indices);
+        assert_eq!(validate_mesh(&mesh), vec![]);
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices.len(), 4);
+        assert_approx_eq!(calculate_mesh_area(&mesh), 4.5);
+    }
+
+    #[test]
+    fn test_check_mesh_rejects_defects() {
+        let vertices = [Vec2::new(0., 0.), Vec2::new(2., 0.), Vec2::new(0., 2.)];
+
+        assert!(check_mesh(&create_mesh_2d(&vertices, &[[0, 1, 2]])));
+        // Clockwise
+        assert!(!check_mesh(&create_mesh_2d(&vertices, &[[0, 2, 1]])));
+    }
+
+    #[test]
+    fn test_voronoi_fracture_mesh() {
+        let mesh = Mesh::from(Rectangle::new(100., 100.));
+
+        let shards =
+            voronoi_fracture_mesh(&mesh, Vec2::new(50., 0.), 1000., &mut rand::thread_rng());
+
+        assert!(shards.len() > 1);
+        let area: f32 = shards
+            .iter()
+            .map(|(shard, _)| calculate_mesh_area(shard))
+            .sum();
+        assert_approx_eq!(area, 10000., 1.);
+        assert!(shards.iter().all(|(shard, _)| valid_mesh(shard)));
+    }
+
     #[test]
     fn test_split_triangle() {
         let plane = Plane2d {
@@ -411,7 +614,7 @@ mod tests {
         let [Some((mesh_a, offset_a)), Some((_mesh_b, _offset_b))] =
             split_mesh(&mesh, split_direction, Vec2::ZERO)
         else {
-            unreachable!("Mesh should be valid");
+            panic!("Splitting the rectangle should leave two halves");
         };
 
         // Validate the results
@@ -473,6 +676,6 @@ mod tests {
         ]));
 
         // Trim the mesh
-        let ((_main_mesh, _offset), _shards) = trim_mesh(mesh);
+        let ((_main_mesh, _offset), _shards) = trim_mesh(mesh, &mut rand::thread_rng());
     }
 }
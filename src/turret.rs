@@ -10,20 +10,29 @@ use bevy::{
         schedule::{IntoSystemConfigs, SystemSet},
         system::{Commands, Query, Res, ResMut, Resource},
     },
-    math::{Vec3, Vec3Swizzles},
+    math::{Vec2, Vec3, Vec3Swizzles},
     render::mesh::Mesh,
     sprite::ColorMaterial,
     time::{Time, Timer, TimerMode},
     transform::components::Transform,
+    utils::default,
 };
+use bevy_rapier2d::dynamics::{ExternalImpulse, Velocity};
 
-use crate::projectile::spawn_projectile;
+use crate::{
+    game_state::GameMode,
+    player::PlayerId,
+    projectile::{projectile_targets, spawn_projectile},
+    rollback::RollbackAppExt,
+};
 
 pub struct TurretPlugin;
 
 impl Plugin for TurretPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<FireEvent>()
+            .rollback_component::<Weapon>()
+            .rollback_component::<ReloadTimer>()
             .add_systems(Startup, load_turret_assets)
             .add_systems(Update, (reload, fire_projectile).chain().in_set(TurretSet));
     }
@@ -37,7 +46,27 @@ pub struct FireEvent {
     pub turret_entity: Entity,
 }
 
-#[derive(Component)]
+/// Ballistics of the projectiles fired by a turret.
+#[derive(Component, Debug, Clone)]
+pub struct Weapon {
+    pub muzzle_speed: f32,
+    /// Fraction of the turret's velocity that is added to the projectile.
+    pub velocity_inheritance: f32,
+    /// Impulse applied to the turret, opposite to the firing direction.
+    pub recoil: f32,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self {
+            muzzle_speed: 500.,
+            velocity_inheritance: 1.,
+            recoil: 0.,
+        }
+    }
+}
+
+#[derive(Component, Clone)]
 pub struct ReloadTimer(Timer);
 
 const RELOAD_DURATION: f32 = 0.3;
@@ -79,10 +108,19 @@ fn fire_projectile(
     mut fire_event_reader: EventReader<FireEvent>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    transform_query: Query<&Transform>,
+    mut turret_query: Query<(
+        &Transform,
+        Option<&Weapon>,
+        Option<&Velocity>,
+        Option<&mut ExternalImpulse>,
+        Option<&PlayerId>,
+    )>,
     reload_timer_query: Query<&ReloadTimer>,
     turret_assets: Res<TurretAssets>,
+    game_mode: Res<GameMode>,
 ) {
+    let default_weapon = Weapon::default();
+
     for FireEvent { turret_entity } in fire_event_reader.read() {
         if reload_timer_query.contains(*turret_entity) {
             continue;
@@ -92,7 +130,9 @@ fn fire_projectile(
         } else {
             continue;
         }
-        let turret_transform = transform_query.get(*turret_entity).unwrap();
+        let (turret_transform, opt_weapon, opt_turret_velocity, opt_turret_impulse, opt_player_id) =
+            turret_query.get_mut(*turret_entity).unwrap();
+        let weapon = opt_weapon.unwrap_or(&default_weapon);
 
         let position = turret_transform.translation.xy()
             + turret_transform
@@ -100,18 +140,39 @@ fn fire_projectile(
                 .mul_vec3(Vec3::new(0., 10., 0.))
                 .xy();
 
-        let velocity = turret_transform
+        let direction = turret_transform
             .rotation
             .mul_vec3(Vec3::new(0., 1., 0.))
-            .xy()
-            * 500.;
-        spawn_projectile(
+            .xy();
+        let turret_velocity = opt_turret_velocity.map_or(Vec2::ZERO, |velocity| velocity.linvel);
+        let velocity =
+            direction * weapon.muzzle_speed + turret_velocity * weapon.velocity_inheritance;
+
+        if weapon.recoil > 0. {
+            let recoil_impulse = -direction * weapon.recoil;
+            // Add to the thrust impulse if the turret is also moving
+            if let Some(mut external_impulse) = opt_turret_impulse {
+                external_impulse.impulse += recoil_impulse;
+            } else {
+                commands.entity(*turret_entity).insert(ExternalImpulse {
+                    impulse: recoil_impulse,
+                    ..default()
+                });
+            }
+        }
+
+        let mut projectile_cmd = spawn_projectile(
             &mut commands,
             &mut meshes,
             &mut materials,
+            *turret_entity,
             position,
             velocity,
+            projectile_targets(*game_mode),
         );
+        if let Some(&player_id) = opt_player_id {
+            projectile_cmd.insert(player_id);
+        }
         commands.spawn((
             Name::from("Turret fire sound"),
             TurretFireSound,
@@ -1,4 +1,5 @@
 mod movement;
+mod steering;
 mod tractor_beam;
 
 use bevy::{
@@ -6,14 +7,15 @@ use bevy::{
     asset::{Asset, AssetServer, Assets, Handle},
     ecs::{
         component::Component,
-        entity::Entity,
+        entity::{Entity, EntityMapper, MapEntities},
         event::{Event, EventReader, EventWriter},
         query::With,
         schedule::{
             common_conditions::{in_state, not, resource_exists},
             Condition, IntoSystemConfigs, OnEnter, SystemSet,
         },
-        system::{Commands, Query, Res, ResMut, Resource},
+        system::{Commands, EntityCommand, EntityCommands, Query, Res, ResMut, Resource},
+        world::World,
     },
     hierarchy::DespawnRecursiveExt,
     input::{keyboard::KeyCode, ButtonInput},
@@ -30,20 +32,26 @@ use bevy_rapier2d::{
     dynamics::{LockedAxes, RigidBody, Velocity},
     geometry::{CollisionGroups, Group},
 };
-use movement::{move_ufo, AvoidanceWeights};
-use rand::Rng;
+use movement::move_ufo;
+use rand::{seq::IteratorRandom, Rng};
 use serde::Deserialize;
+use steering::SteeringProfile;
 use tracing::info;
-use tractor_beam::{throw_asteroid, TractorBeam};
+use tractor_beam::throw_asteroid;
+pub use tractor_beam::TractorBeam;
 
 use crate::{
     asteroid::SplitAsteroidEvent,
     edge_wrap::{Bounds, Duplicable},
     explosion,
     game_state::GameState,
+    outline::Outlined,
     player::Player,
     projectile::PROJECTILE_GROUP,
-    shatter::spawn_shattered_mesh,
+    rng::GameRng,
+    rollback::RollbackAppExt,
+    save::is_new_game,
+    shatter::{spawn_shattered_mesh, Fracture},
     utils::mesh_to_collider,
 };
 
@@ -56,10 +64,20 @@ impl Plugin for UfoPlugin {
             .add_plugins(RonAssetPlugin::<UfoSettings>::new(&["ufo_settings.ron"]))
             .add_systems(Startup, load_ufo_settings)
             .init_resource::<SpawnTimer>()
-            .add_systems(OnEnter(GameState::Playing), reset_spawn_timer)
+            .rollback_component::<Ufo>()
+            .rollback_component_with_entities::<KillTarget>()
+            .rollback_component::<InsideBounds>()
+            .rollback_component::<TractorBeam>()
+            .rollback_component::<SteeringProfile>()
+            .rollback_resource::<SpawnTimer>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                reset_spawn_timer.run_if(is_new_game),
+            )
             .add_systems(
                 Update,
                 (
+                    select_kill_target,
                     move_ufo,
                     ufo_inside_bounds,
                     throw_asteroid,
@@ -67,7 +85,8 @@ impl Plugin for UfoPlugin {
                     spawn_ufo,
                 )
                     .run_if(not(in_state(GameState::Menu)))
-                    .chain(),
+                    .chain()
+                    .in_set(UfoSet),
             )
             .add_systems(
                 Update,
@@ -85,18 +104,24 @@ impl Plugin for UfoPlugin {
 #[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
 pub struct UfoSet;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Ufo;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct KillTarget(Entity);
 
+impl MapEntities for KillTarget {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.0 = entity_mapper.map_entity(self.0);
+    }
+}
+
 #[derive(Resource, Debug, Default, Deserialize, Asset, TypePath, Clone)]
 struct UfoSettings {
     debug_enabled: bool,
     max_acceleration: f32,
     max_velocity: f32,
-    avoidance_weights: AvoidanceWeights,
+    steering_profile: SteeringProfile,
 }
 
 #[derive(Resource)]
@@ -141,9 +166,10 @@ fn load_ufo_assets(
 
 pub const UFO_GROUP: Group = Group::GROUP_5;
 
-#[derive(Resource)]
-struct SpawnTimer {
-    timer: Timer,
+/// Counts down to the next UFO once the previous one is gone.
+#[derive(Resource, Clone)]
+pub struct SpawnTimer {
+    pub timer: Timer,
 }
 
 impl Default for SpawnTimer {
@@ -161,15 +187,13 @@ fn reset_spawn_timer(mut spawn_timer: ResMut<SpawnTimer>) {
 
 fn spawn_ufo(
     mut commands: Commands,
-    ufo_assets: Res<UfoAssets>,
-    meshes: Res<Assets<Mesh>>,
     ufo_query: Query<Entity, With<Ufo>>,
     player_query: Query<Entity, With<Player>>,
     mut split_asteroid_events: EventReader<SplitAsteroidEvent>,
     bounds: Res<Bounds>,
     mut spawn_timer: ResMut<SpawnTimer>,
-    ufo_settings: Res<UfoSettings>,
     time: Res<Time>,
+    mut rng: ResMut<GameRng>,
 ) {
     if !ufo_query.is_empty() {
         return;
@@ -183,45 +207,109 @@ fn spawn_ufo(
         return;
     }
 
-    let Ok(player_entity) = player_query.get_single() else {
+    let rng = &mut rng.0;
+
+    let Some(player_entity) = player_query.iter().choose(rng) else {
         return;
     };
 
-    let mut rng = rand::thread_rng();
-
     for _event in split_asteroid_events.read() {
         if rng.gen_bool(0.3) {
             continue;
         }
         info!("Spawning UFO");
-        let mesh = meshes
-            .get(&ufo_assets.ufo_mesh)
-            .expect("Failed to load mesh");
-        let collider = mesh_to_collider(mesh).expect("Failed to create collider");
         let direction = Quat::from_rotation_z(rng.gen_range(0.0..std::f32::consts::PI * 2.));
         let spawn_distance = Vec3::new(bounds.0.x * 2., bounds.0.y * 2., 0.);
         let translation = direction.mul_vec3(spawn_distance);
-        commands.spawn((
+        commands
+            .spawn_ufo(Transform::from_translation(translation))
+            .insert(KillTarget(player_entity));
+        return;
+    }
+}
+
+struct SpawnUfo {
+    transform: Transform,
+}
+
+impl EntityCommand for SpawnUfo {
+    fn apply(self, entity: Entity, world: &mut World) {
+        let ufo_assets = world.resource::<UfoAssets>();
+        let mesh = world
+            .resource::<Assets<Mesh>>()
+            .get(&ufo_assets.ufo_mesh)
+            .expect("Failed to load mesh");
+        let collider = mesh_to_collider(mesh).expect("Failed to create collider");
+        let bundle = (
             Ufo,
+            Outlined,
             MaterialMesh2dBundle {
                 mesh: ufo_assets.ufo_mesh.clone().into(),
                 material: ufo_assets.ufo_material.clone(),
-                transform: Transform::from_translation(translation),
+                transform: self.transform,
                 ..default()
             },
             collider,
             CollisionGroups::new(UFO_GROUP, PROJECTILE_GROUP),
             RigidBody::KinematicVelocityBased,
             LockedAxes::ROTATION_LOCKED,
-            KillTarget(player_entity),
             TractorBeam::default(),
-            ufo_settings.avoidance_weights.clone(),
-        ));
-        return;
+            world.resource::<UfoSettings>().steering_profile.clone(),
+        );
+        world.entity_mut(entity).insert(bundle);
+    }
+}
+
+pub trait SpawnUfoExt {
+    fn spawn_ufo(&mut self, transform: Transform) -> EntityCommands<'_>;
+}
+
+impl<'w, 's> SpawnUfoExt for Commands<'w, 's> {
+    fn spawn_ufo(&mut self, transform: Transform) -> EntityCommands<'_> {
+        let mut e = self.spawn_empty();
+        e.add(SpawnUfo { transform });
+        e
+    }
+}
+
+/// Hunts down the closest player once the targeted one is gone, e.g. while their ship respawns.
+fn select_kill_target(
+    mut commands: Commands,
+    ufo_query: Query<(Entity, &GlobalTransform, Option<&KillTarget>), With<Ufo>>,
+    player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+    bounds: Res<Bounds>,
+) {
+    for (ufo_entity, ufo_transform, opt_target) in &ufo_query {
+        if opt_target.is_some_and(|KillTarget(target_entity)| player_query.contains(*target_entity))
+        {
+            continue;
+        }
+
+        let ufo_position = ufo_transform.translation().xy();
+        let closest_player = player_query
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                let distance_a = bounds.wrapped_distance(ufo_position, a.translation().xy());
+                let distance_b = bounds.wrapped_distance(ufo_position, b.translation().xy());
+                distance_a.total_cmp(&distance_b)
+            })
+            .map(|(player_entity, _)| player_entity);
+
+        match closest_player {
+            Some(player_entity) => {
+                commands
+                    .entity(ufo_entity)
+                    .insert(KillTarget(player_entity));
+            }
+            None if opt_target.is_some() => {
+                commands.entity(ufo_entity).remove::<KillTarget>();
+            }
+            None => {}
+        }
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 struct InsideBounds;
 
 fn ufo_inside_bounds(
@@ -244,6 +332,8 @@ fn ufo_inside_bounds(
 #[derive(Event)]
 pub struct UfoDestroyedEvent {
     pub ufo_entity: Entity,
+    /// Point of impact in the local space of the UFO, if it was destroyed by a collision.
+    pub impact_point: Option<Vec2>,
 }
 
 fn ufo_destroyed(
@@ -254,8 +344,13 @@ fn ufo_destroyed(
     ufo_query: Query<(&Transform, Option<&Velocity>)>,
     mut spawn_timer: ResMut<SpawnTimer>,
     mut explosion_events: EventWriter<explosion::ExplosionEvent>,
+    mut rng: ResMut<GameRng>,
 ) {
-    for UfoDestroyedEvent { ufo_entity } in ufo_destroyed_events.read() {
+    for UfoDestroyedEvent {
+        ufo_entity,
+        impact_point,
+    } in ufo_destroyed_events.read()
+    {
         let mesh = meshes
             .get(&ufo_assets.ufo_mesh)
             .expect("Failed to load mesh")
@@ -268,8 +363,10 @@ fn ufo_destroyed(
             ufo_assets.ufo_material.clone(),
             ufo_transform,
             opt_ufo_velocity.copied().unwrap_or(Velocity::zero()),
+            Fracture::voronoi(&mesh, *impact_point),
             &mut commands,
             &mut meshes,
+            &mut rng.0,
         );
 
         info!("UFO destroyed");
@@ -0,0 +1,414 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::component::Component, gizmos::gizmos::Gizmos, math::Vec2, render::color::Color};
+use serde::Deserialize;
+
+/// Number of directions sampled by the context map.
+pub const STEERING_DIRECTIONS: usize = 16;
+
+/// Danger values within this margin of the least dangerous direction are not masked.
+const DANGER_MASK_TOLERANCE: f32 = 0.05;
+
+/// Interest and danger sampled over [`STEERING_DIRECTIONS`] evenly spaced directions.
+///
+/// Behaviours write into the map and [`ContextMap::resolve`] picks the most interesting direction
+/// that is not noticeably more dangerous than the safest one.
+#[derive(Debug, Clone, Default)]
+pub struct ContextMap {
+    pub interest: [f32; STEERING_DIRECTIONS],
+    pub danger: [f32; STEERING_DIRECTIONS],
+}
+
+impl ContextMap {
+    pub fn slot_direction(slot: usize) -> Vec2 {
+        Vec2::from_angle(slot as f32 / STEERING_DIRECTIONS as f32 * TAU)
+    }
+
+    pub fn add_interest(&mut self, direction: Vec2, weight: f32) {
+        Self::add(&mut self.interest, direction, weight);
+    }
+
+    pub fn add_danger(&mut self, direction: Vec2, weight: f32) {
+        Self::add(&mut self.danger, direction, weight);
+    }
+
+    fn add(slots: &mut [f32; STEERING_DIRECTIONS], direction: Vec2, weight: f32) {
+        let Some(direction) = direction.try_normalize() else {
+            return;
+        };
+
+        for (slot, value) in slots.iter_mut().enumerate() {
+            let alignment = Self::slot_direction(slot).dot(direction).max(0.);
+            *value = value.max(alignment * weight);
+        }
+    }
+
+    /// Interest with every direction that is more dangerous than the safest one zeroed out.
+    pub fn masked_interest(&self) -> [f32; STEERING_DIRECTIONS] {
+        let min_danger = self.danger.iter().copied().fold(f32::INFINITY, f32::min);
+
+        let mut masked = self.interest;
+        for (value, danger) in masked.iter_mut().zip(self.danger) {
+            if danger > min_danger + DANGER_MASK_TOLERANCE {
+                *value = 0.;
+            }
+        }
+        masked
+    }
+
+    /// Returns the chosen steering direction, scaled by the interest in it.
+    ///
+    /// The best slot is blended with its neighbours so the result is not snapped to the sampled
+    /// directions.
+    pub fn resolve(&self) -> Vec2 {
+        let masked = self.masked_interest();
+
+        let Some((best_slot, best_interest)) = masked
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            return Vec2::ZERO;
+        };
+
+        if best_interest <= 0. {
+            return Vec2::ZERO;
+        }
+
+        let previous_slot = (best_slot + STEERING_DIRECTIONS - 1) % STEERING_DIRECTIONS;
+        let next_slot = (best_slot + 1) % STEERING_DIRECTIONS;
+
+        let direction = [previous_slot, best_slot, next_slot]
+            .into_iter()
+            .map(|slot| Self::slot_direction(slot) * masked[slot])
+            .sum::<Vec2>()
+            .normalize_or_zero();
+
+        direction * best_interest
+    }
+
+    pub fn draw_gizmos(&self, gizmos: &mut Gizmos, position: Vec2, steering: Vec2) {
+        for slot in 0..STEERING_DIRECTIONS {
+            let direction = Self::slot_direction(slot);
+            gizmos.line_2d(
+                position,
+                position + direction * self.interest[slot] * 50.,
+                Color::GREEN,
+            );
+            gizmos.line_2d(
+                position,
+                position + direction * self.danger[slot] * 50.,
+                Color::RED,
+            );
+        }
+        gizmos.line_2d(position, position + steering * 100., Color::ORANGE);
+    }
+}
+
+/// A nearby collider the UFO should keep away from.
+#[derive(Debug, Clone, Copy)]
+pub struct Threat {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub radius: f32,
+    /// Whether the threat's current trajectory passes through the UFO.
+    pub incoming: bool,
+}
+
+/// Everything the behaviours know about the UFO's surroundings.
+///
+/// Positions of the target and threats are relative to the UFO.
+pub struct SteeringContext<'a> {
+    pub velocity: Vec2,
+    pub target: Option<Vec2>,
+    pub threats: &'a [Threat],
+    pub elapsed_seconds: f32,
+}
+
+pub trait SteeringBehaviour {
+    fn apply(&self, context: &SteeringContext, map: &mut ContextMap);
+}
+
+/// Moves towards the target while it is further away than `min_distance`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Seek {
+    pub weight: f32,
+    pub min_distance: f32,
+}
+
+impl SteeringBehaviour for Seek {
+    fn apply(&self, context: &SteeringContext, map: &mut ContextMap) {
+        let Some(target) = context.target else {
+            return;
+        };
+
+        if target.length() > self.min_distance {
+            map.add_interest(target, self.weight);
+        }
+    }
+}
+
+/// Moves away from the target while it is closer than `radius`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Flee {
+    pub weight: f32,
+    pub radius: f32,
+}
+
+impl SteeringBehaviour for Flee {
+    fn apply(&self, context: &SteeringContext, map: &mut ContextMap) {
+        let Some(target) = context.target else {
+            return;
+        };
+
+        let distance = target.length();
+        if distance < self.radius {
+            let urgency = 1. - distance / self.radius;
+            map.add_interest(-target, self.weight * urgency);
+            map.add_danger(target, self.weight * urgency);
+        }
+    }
+}
+
+/// Circles the target while it is between `min_distance` and `max_distance`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Orbit {
+    pub weight: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl SteeringBehaviour for Orbit {
+    fn apply(&self, context: &SteeringContext, map: &mut ContextMap) {
+        let Some(target) = context.target else {
+            return;
+        };
+
+        let distance = target.length();
+        if distance < self.min_distance || distance > self.max_distance {
+            return;
+        }
+
+        // Keep circling in the direction the UFO is already moving to avoid flip-flopping
+        let tangent = target.perp();
+        let tangent = if tangent.dot(context.velocity) < 0. {
+            -tangent
+        } else {
+            tangent
+        };
+        map.add_interest(tangent, self.weight);
+    }
+}
+
+/// Drifts in a slowly rotating direction, so the UFO never stands still.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Wander {
+    pub weight: f32,
+    pub rate: f32,
+}
+
+impl SteeringBehaviour for Wander {
+    fn apply(&self, context: &SteeringContext, map: &mut ContextMap) {
+        let t = context.elapsed_seconds * self.rate;
+        let angle = t + (t * 0.7).sin() * std::f32::consts::PI;
+        map.add_interest(Vec2::from_angle(angle), self.weight);
+    }
+}
+
+/// Marks directions towards nearby threats and into the path of incoming threats as dangerous.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Avoid {
+    pub weight: f32,
+    pub radius: f32,
+    pub incoming_weight: f32,
+}
+
+impl SteeringBehaviour for Avoid {
+    fn apply(&self, context: &SteeringContext, map: &mut ContextMap) {
+        for threat in context.threats {
+            let distance = (threat.position.length() - threat.radius).max(0.);
+            let proximity = (1. - distance / self.radius).clamp(0., 1.);
+            map.add_danger(threat.position, self.weight * proximity);
+
+            if !threat.incoming {
+                continue;
+            }
+
+            // Step sideways out of the threat's trajectory
+            let Some(threat_direction) = threat.velocity.try_normalize() else {
+                continue;
+            };
+            let closest_point =
+                threat.position + threat_direction * (-threat.position).dot(threat_direction);
+            let away_from_path = -closest_point;
+            let away_from_path = if away_from_path.length_squared() > f32::EPSILON {
+                away_from_path
+            } else {
+                threat_direction.perp()
+            };
+
+            map.add_danger(-away_from_path, self.incoming_weight);
+            map.add_interest(away_from_path, self.incoming_weight);
+        }
+    }
+}
+
+/// Per-UFO weighting of the steering behaviours, loaded from the UFO settings.
+#[derive(Component, Debug, Clone, Default, Deserialize)]
+pub struct SteeringProfile {
+    pub seek: Seek,
+    pub flee: Flee,
+    pub orbit: Orbit,
+    pub wander: Wander,
+    pub avoid: Avoid,
+}
+
+impl SteeringProfile {
+    pub fn behaviours(&self) -> [&dyn SteeringBehaviour; 5] {
+        [
+            &self.seek,
+            &self.flee,
+            &self.orbit,
+            &self.wander,
+            &self.avoid,
+        ]
+    }
+
+    pub fn context_map(&self, context: &SteeringContext) -> ContextMap {
+        let mut map = ContextMap::default();
+        for behaviour in self.behaviours() {
+            behaviour.apply(context, &mut map);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use bevy::utils::default;
+
+    use super::*;
+
+    fn context(target: Option<Vec2>, threats: &[Threat]) -> SteeringContext<'_> {
+        SteeringContext {
+            velocity: Vec2::ZERO,
+            target,
+            threats,
+            elapsed_seconds: 0.,
+        }
+    }
+
+    #[test]
+    fn test_resolve_empty_map() {
+        assert_eq!(ContextMap::default().resolve(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_resolve_follows_interest() {
+        let mut map = ContextMap::default();
+        map.add_interest(Vec2::Y, 1.);
+
+        let steering = map.resolve();
+
+        assert_approx_eq!(steering.x, 0., 0.0001);
+        assert_approx_eq!(steering.y, 1., 0.0001);
+    }
+
+    #[test]
+    fn test_resolve_masks_danger() {
+        let mut map = ContextMap::default();
+        map.add_interest(Vec2::X, 1.);
+        map.add_interest(Vec2::Y, 0.5);
+        map.add_danger(Vec2::X, 1.);
+
+        let steering = map.resolve();
+
+        assert!(steering.x < 0.5, "Steering {steering:?} should avoid +x");
+        assert!(
+            steering.y > 0.,
+            "Steering {steering:?} should head towards +y"
+        );
+    }
+
+    #[test]
+    fn test_seek_and_flee_band() {
+        let profile = SteeringProfile {
+            seek: Seek {
+                weight: 1.,
+                min_distance: 600.,
+            },
+            flee: Flee {
+                weight: 1.,
+                radius: 400.,
+            },
+            ..default()
+        };
+
+        let far = profile
+            .context_map(&context(Some(Vec2::new(1000., 0.)), &[]))
+            .resolve();
+        assert!(far.x > 0.);
+
+        let near = profile
+            .context_map(&context(Some(Vec2::new(100., 0.)), &[]))
+            .resolve();
+        assert!(near.x < 0.);
+    }
+
+    #[test]
+    fn test_avoid_steers_around_threat() {
+        let profile = SteeringProfile {
+            seek: Seek {
+                weight: 1.,
+                min_distance: 0.,
+            },
+            avoid: Avoid {
+                weight: 1.,
+                radius: 300.,
+                incoming_weight: 1.,
+            },
+            ..default()
+        };
+        let threats = [Threat {
+            position: Vec2::new(50., 0.),
+            velocity: Vec2::ZERO,
+            radius: 20.,
+            incoming: false,
+        }];
+
+        let steering = profile
+            .context_map(&context(Some(Vec2::new(1000., 0.)), &threats))
+            .resolve();
+
+        assert!(steering.length() > 0.);
+        assert!(
+            steering.normalize().dot(Vec2::X) < 0.9,
+            "Steering {steering:?} should not head straight into the threat"
+        );
+    }
+
+    #[test]
+    fn test_avoid_incoming_threat_sidesteps() {
+        let profile = SteeringProfile {
+            avoid: Avoid {
+                weight: 0.,
+                radius: 300.,
+                incoming_weight: 1.,
+            },
+            ..default()
+        };
+        // Threat coming from the right, passing slightly above the UFO
+        let threats = [Threat {
+            position: Vec2::new(200., 10.),
+            velocity: Vec2::new(-100., 0.),
+            radius: 20.,
+            incoming: true,
+        }];
+
+        let steering = profile.context_map(&context(None, &threats)).resolve();
+
+        assert!(steering.y < 0., "Steering {steering:?} should move down");
+    }
+}
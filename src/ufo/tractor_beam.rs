@@ -3,7 +3,7 @@ use bevy::{
         component::Component,
         entity::Entity,
         query::With,
-        system::{Commands, Query, Res},
+        system::{Commands, Query, Res, ResMut},
     },
     gizmos::gizmos::Gizmos,
     math::{Vec2, Vec3Swizzles},
@@ -15,20 +15,21 @@ use bevy::{
 use bevy_rapier2d::dynamics::{ExternalImpulse, ReadMassProperties};
 use rand::Rng;
 
-use crate::{asteroid::Asteroid, player::Player};
+use crate::{asteroid::Asteroid, edge_wrap::Bounds, player::Player, rng::GameRng};
 
-use super::{InsideBounds, Ufo};
+use super::{InsideBounds, KillTarget, Ufo};
 
 const TRACTOR_BEAM_RELOAD_TIME: f32 = 4.;
 const TRACTOR_BEAM_ARMED_TIME: f32 = 2.;
 const TRACTOR_BEAM_FORCE: f32 = 250000.;
 
+#[derive(Debug, Clone)]
 enum TractorBeamState {
     Armed(Timer),
     Reloading(Timer),
 }
 
-#[derive(Component)]
+#[derive(Component, Debug, Clone)]
 pub struct TractorBeam {
     state: TractorBeamState,
 }
@@ -44,36 +45,64 @@ impl Default for TractorBeam {
     }
 }
 
+impl TractorBeam {
+    /// Whether the beam is armed, and the timer of the current state.
+    pub fn state(&self) -> (bool, &Timer) {
+        match &self.state {
+            TractorBeamState::Armed(timer) => (true, timer),
+            TractorBeamState::Reloading(timer) => (false, timer),
+        }
+    }
+
+    pub fn from_state(armed: bool, timer: Timer) -> Self {
+        Self {
+            state: if armed {
+                TractorBeamState::Armed(timer)
+            } else {
+                TractorBeamState::Reloading(timer)
+            },
+        }
+    }
+}
+
 pub fn throw_asteroid(
     mut commands: Commands,
-    mut ufo_query: Query<(&mut TractorBeam, &GlobalTransform), (With<Ufo>, With<InsideBounds>)>,
+    mut ufo_query: Query<
+        (&mut TractorBeam, &GlobalTransform, Option<&KillTarget>),
+        (With<Ufo>, With<InsideBounds>),
+    >,
     asteroid_query: Query<(Entity, &GlobalTransform, &ReadMassProperties), With<Asteroid>>,
     player_query: Query<&GlobalTransform, With<Player>>,
+    bounds: Res<Bounds>,
     mut gizmos: Gizmos,
     time: Res<Time>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let Ok(player_transform) = player_query.get_single() else {
-        return;
-    };
-
-    for (mut tractor_beam, ufo_transform) in ufo_query.iter_mut() {
-        update_tractor_beam_state(&mut tractor_beam, &time);
+    for (mut tractor_beam, ufo_transform, opt_target) in ufo_query.iter_mut() {
+        update_tractor_beam_state(&mut tractor_beam, &time, &mut rng.0);
+        let Some(player_transform) =
+            opt_target.and_then(|KillTarget(target_entity)| player_query.get(*target_entity).ok())
+        else {
+            continue;
+        };
         if matches!(tractor_beam.state, TractorBeamState::Reloading(_)) {
             continue;
         }
         let closest_asteroid =
-            find_suitable_asteroid(&asteroid_query, ufo_transform, player_transform);
+            find_suitable_asteroid(&asteroid_query, ufo_transform, player_transform, &bounds);
 
         if let Some((asteroid_entity, asteroid_position)) = closest_asteroid {
-            let direction_to_player = player_transform.translation().xy() - asteroid_position;
+            let direction_to_player =
+                bounds.wrapped_displacement(asteroid_position, player_transform.translation().xy());
 
             if direction_to_player.length() < 100. {
                 return;
             }
 
+            let ufo_position = ufo_transform.translation().xy();
             gizmos.line_2d(
-                ufo_transform.translation().xy(),
-                asteroid_position,
+                ufo_position,
+                ufo_position + bounds.wrapped_displacement(ufo_position, asteroid_position),
                 Color::BLUE,
             );
 
@@ -91,31 +120,25 @@ fn find_suitable_asteroid(
     asteroid_query: &Query<(Entity, &GlobalTransform, &ReadMassProperties), With<Asteroid>>,
     ufo_transform: &GlobalTransform,
     player_transform: &GlobalTransform,
+    bounds: &Bounds,
 ) -> Option<(Entity, Vec2)> {
+    let ufo_position = ufo_transform.translation().xy();
+    let player_position = player_transform.translation().xy();
+
     asteroid_query
         .iter()
         .filter(|(_, asteroid_transform, _)| {
-            let asteroid_ufo_distance = asteroid_transform
-                .translation()
-                .xy()
-                .distance(ufo_transform.translation().xy());
-
-            let asteroid_player_distance = asteroid_transform
-                .translation()
-                .xy()
-                .distance(player_transform.translation().xy());
+            let asteroid_position = asteroid_transform.translation().xy();
+            let asteroid_ufo_distance = bounds.wrapped_distance(asteroid_position, ufo_position);
+            let asteroid_player_distance =
+                bounds.wrapped_distance(asteroid_position, player_position);
             asteroid_ufo_distance < 500. && asteroid_player_distance > 100.
         })
         .min_by_key(|(_, asteroid_transform, mass_properties)| {
-            let asteroid_ufo_distance = asteroid_transform
-                .translation()
-                .xy()
-                .distance(ufo_transform.translation().xy());
-
-            let asteroid_player_distance = asteroid_transform
-                .translation()
-                .xy()
-                .distance(player_transform.translation().xy());
+            let asteroid_position = asteroid_transform.translation().xy();
+            let asteroid_ufo_distance = bounds.wrapped_distance(asteroid_position, ufo_position);
+            let asteroid_player_distance =
+                bounds.wrapped_distance(asteroid_position, player_position);
             asteroid_ufo_distance as i32 * 2
                 + asteroid_player_distance as i32
                 + (mass_properties.get().mass * 0.5) as i32
@@ -123,12 +146,12 @@ fn find_suitable_asteroid(
         .map(|(entity, asteroid_transform, _)| (entity, asteroid_transform.translation().xy()))
 }
 
-fn update_tractor_beam_state(tractor_beam: &mut TractorBeam, time: &Res<Time>) {
+fn update_tractor_beam_state(tractor_beam: &mut TractorBeam, time: &Res<Time>, rng: &mut impl Rng) {
     match tractor_beam.state {
         TractorBeamState::Armed(ref mut timer) => {
             if timer.tick(time.delta()).just_finished() {
                 tractor_beam.state = TractorBeamState::Reloading(Timer::from_seconds(
-                    TRACTOR_BEAM_RELOAD_TIME + rand::thread_rng().gen_range(0.0..1.0),
+                    TRACTOR_BEAM_RELOAD_TIME + rng.gen_range(0.0..1.0),
                     TimerMode::Once,
                 ));
             }
@@ -136,7 +159,7 @@ fn update_tractor_beam_state(tractor_beam: &mut TractorBeam, time: &Res<Time>) {
         TractorBeamState::Reloading(ref mut timer) => {
             if timer.tick(time.delta()).just_finished() {
                 tractor_beam.state = TractorBeamState::Armed(Timer::from_seconds(
-                    TRACTOR_BEAM_ARMED_TIME + rand::thread_rng().gen_range(0.0..1.0),
+                    TRACTOR_BEAM_ARMED_TIME + rng.gen_range(0.0..1.0),
                     TimerMode::Once,
                 ));
             }
@@ -1,24 +1,31 @@
 use crate::{
-    game_state::{GameResult, GameState},
+    edge_wrap::{BoundaryMode, BoundaryModeSettings},
+    game_state::{GameMode, GameResult, GameState},
     input::InputMode,
+    net::{is_online_client, Lockstep},
+    player::{PlayerId, Players},
+    playfield::Playfield,
+    save::SavedGame,
     utils::cleanup_component,
+    versus::VersusSettings,
 };
 use bevy::{
     app::{App, Plugin, Update},
     ecs::{
+        change_detection::DetectChangesMut,
         entity::Entity,
         query::With,
         schedule::{
-            common_conditions::in_state, Condition, IntoSystemConfigs, NextState, OnEnter, OnExit,
-            States,
+            common_conditions::{in_state, not, resource_changed, resource_exists_and_changed},
+            Condition, IntoSystemConfigs, NextState, OnEnter, OnExit, States,
         },
         system::{Query, ResMut},
     },
-    input::{mouse::MouseButton, touch::Touches, ButtonInput},
+    input::{keyboard::KeyCode, mouse::MouseButton, touch::Touches, ButtonInput},
     log::info,
     prelude::{
         default, AlignItems, AssetServer, BuildChildren, Color, Commands, Component, FlexDirection,
-        JustifyContent, Name, NodeBundle, Res, Style, TextBundle, TextStyle, Val,
+        JustifyContent, Name, NodeBundle, Res, Style, Text, TextBundle, TextStyle, Val,
     },
     time::{Time, Timer, TimerMode},
     ui::UiRect,
@@ -40,11 +47,35 @@ impl Plugin for StartScreenPlugin {
                 set_input_mode
                     .run_if(in_state(GameState::Menu).and_then(in_state(StartScreenState::Start))),
             )
+            .add_systems(
+                Update,
+                update_world_size_text
+                    .run_if(in_state(GameState::Menu).and_then(resource_changed::<Playfield>)),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_game_mode,
+                    toggle_boundary_mode,
+                    apply_boundary_mode.run_if(
+                        resource_changed::<GameMode>
+                            .or_else(resource_changed::<BoundaryModeSettings>),
+                    ),
+                    update_boundary_mode_text.run_if(resource_changed::<BoundaryMode>),
+                    update_game_mode_text.run_if(
+                        resource_changed::<GameMode>.or_else(resource_changed::<VersusSettings>),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            )
             .add_systems(OnEnter(StartScreenState::Instructions), spawn_instructions)
             .add_systems(
                 Update,
                 start_game.run_if(
-                    in_state(GameState::Menu).and_then(in_state(StartScreenState::Instructions)),
+                    in_state(GameState::Menu)
+                        .and_then(in_state(StartScreenState::Instructions))
+                        .and_then(not(is_online_client)),
                 ),
             )
             .add_systems(
@@ -63,6 +94,15 @@ struct ClickOrTap;
 #[derive(Component)]
 struct Instructions;
 
+#[derive(Component)]
+struct WorldSizeText;
+
+#[derive(Component)]
+struct BoundaryModeText;
+
+#[derive(Component)]
+struct GameModeText;
+
 #[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum StartScreenState {
     #[default]
@@ -72,7 +112,16 @@ enum StartScreenState {
 
 const FONT_PATH: &str = "fonts/TurretRoad-ExtraLight.ttf";
 
-fn spawn_start_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_start_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    playfield: Res<Playfield>,
+    boundary_mode: Res<BoundaryMode>,
+    game_mode: Res<GameMode>,
+    versus_settings: Res<VersusSettings>,
+    saved_game: Option<Res<SavedGame>>,
+    session: Option<Res<Lockstep>>,
+) {
     commands
         .spawn((
             Name::new("Start screen"),
@@ -103,9 +152,158 @@ fn spawn_start_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
             ));
 
             spawn_click_or_tap(parent, &asset_server);
+
+            if saved_game.is_some() && session.is_none() {
+                parent.spawn((
+                    Name::new("Continue text"),
+                    TextBundle::from_section(
+                        "Press C to continue the saved game",
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: 40.,
+                            color: Color::WHITE,
+                        },
+                    ),
+                ));
+            }
+
+            parent.spawn((
+                Name::new("World size text"),
+                WorldSizeText,
+                TextBundle::from_section(
+                    world_size_text(&playfield),
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 30.,
+                        color: Color::GRAY,
+                    },
+                ),
+            ));
+
+            parent.spawn((
+                Name::new("Boundary mode text"),
+                BoundaryModeText,
+                TextBundle::from_section(
+                    boundary_mode_text(*boundary_mode),
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 30.,
+                        color: Color::GRAY,
+                    },
+                ),
+            ));
+
+            parent.spawn((
+                Name::new("Game mode text"),
+                GameModeText,
+                TextBundle::from_section(
+                    game_mode_text(*game_mode, &versus_settings),
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 30.,
+                        color: Color::GRAY,
+                    },
+                ),
+            ));
         });
 }
 
+fn world_size_text(playfield: &Playfield) -> String {
+    format!(
+        "World: {} x {} screens (F8 to change)",
+        playfield.world_screens.x, playfield.world_screens.y
+    )
+}
+
+fn update_world_size_text(
+    mut text_query: Query<&mut Text, With<WorldSizeText>>,
+    playfield: Res<Playfield>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = world_size_text(&playfield);
+    }
+}
+
+fn boundary_mode_text(boundary_mode: BoundaryMode) -> String {
+    let edges = match boundary_mode {
+        BoundaryMode::Wrap => "Wrap",
+        BoundaryMode::Walls => "Walls",
+        BoundaryMode::Void => "Void",
+    };
+    format!("Edges: {edges} (F9 to change)")
+}
+
+/// Changes the boundary mode of the selected game mode.
+fn toggle_boundary_mode(
+    mut boundary_mode_settings: ResMut<BoundaryModeSettings>,
+    game_mode: Res<GameMode>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        let boundary_mode = boundary_mode_settings.get_mut(*game_mode);
+        *boundary_mode = boundary_mode.next();
+        info!(game_mode = ?*game_mode, boundary_mode = ?*boundary_mode, "Boundary mode changed");
+    }
+}
+
+fn apply_boundary_mode(
+    mut boundary_mode: ResMut<BoundaryMode>,
+    boundary_mode_settings: Res<BoundaryModeSettings>,
+    game_mode: Res<GameMode>,
+) {
+    boundary_mode.set_if_neq(boundary_mode_settings.get(*game_mode));
+}
+
+fn update_boundary_mode_text(
+    mut text_query: Query<&mut Text, With<BoundaryModeText>>,
+    boundary_mode: Res<BoundaryMode>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = boundary_mode_text(*boundary_mode);
+    }
+}
+
+fn game_mode_text(game_mode: GameMode, versus_settings: &VersusSettings) -> String {
+    match game_mode {
+        GameMode::Solo => "Mode: Solo (F2 to change)".to_string(),
+        GameMode::Coop => "Mode: Co-op (F2 to change)".to_string(),
+        GameMode::Versus => format!(
+            "Mode: Versus, first to {} rounds (F2 to change, -/+ for rounds)",
+            versus_settings.score_limit
+        ),
+    }
+}
+
+fn toggle_game_mode(
+    mut game_mode: ResMut<GameMode>,
+    mut versus_settings: ResMut<VersusSettings>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        *game_mode = game_mode.next();
+        info!(game_mode = ?*game_mode, "Game mode changed");
+    }
+
+    if *game_mode == GameMode::Versus {
+        if keyboard_input.just_pressed(KeyCode::Minus) {
+            versus_settings.change_score_limit(-1);
+        }
+        if keyboard_input.just_pressed(KeyCode::Equal) {
+            versus_settings.change_score_limit(1);
+        }
+    }
+}
+
+fn update_game_mode_text(
+    mut text_query: Query<&mut Text, With<GameModeText>>,
+    game_mode: Res<GameMode>,
+    versus_settings: Res<VersusSettings>,
+) {
+    for mut text in &mut text_query {
+        text.sections[0].value = game_mode_text(*game_mode, &versus_settings);
+    }
+}
+
 fn spawn_click_or_tap(parent: &mut bevy::prelude::ChildBuilder, asset_server: &AssetServer) {
     parent.spawn((
         Name::new("Click or tap text"),
@@ -143,6 +341,8 @@ fn spawn_instructions(
     start_screen_query: Query<Entity, With<StartScreen>>,
     asset_server: Res<AssetServer>,
     input_mode: Res<InputMode>,
+    game_mode: Res<GameMode>,
+    session: Option<Res<Lockstep>>,
 ) {
     let start_screen = start_screen_query.single();
     commands.entity(start_screen).with_children(|parent| {
@@ -186,6 +386,42 @@ fn spawn_instructions(
                     },
                     instruction_style.clone(),
                 ));
+                parent.spawn(TextBundle::from_section(
+                    match *input_mode {
+                        InputMode::Mouse => "Middle click to launch a homing missile",
+                        InputMode::Touch => "Tap with a second finger to launch a homing missile",
+                    },
+                    instruction_style.clone(),
+                ));
+                if game_mode.player_count() > 1 {
+                    parent.spawn(TextBundle::from_section(
+                        "Player 2: arrow keys or gamepad stick to steer, up or A to thrust",
+                        TextStyle {
+                            color: PlayerId::Two.color(),
+                            ..instruction_style.clone()
+                        },
+                    ));
+                    parent.spawn(TextBundle::from_section(
+                        "Enter or right trigger to fire, right shift or X for a missile",
+                        TextStyle {
+                            color: PlayerId::Two.color(),
+                            ..instruction_style.clone()
+                        },
+                    ));
+                }
+                if *game_mode == GameMode::Versus {
+                    parent.spawn(TextBundle::from_section(
+                        "Shoot the other ship, the last one flying wins the round",
+                        instruction_style.clone(),
+                    ));
+                }
+                // Saving needs a file system and isn't possible online
+                if !cfg!(target_arch = "wasm32") && session.is_none() {
+                    parent.spawn(TextBundle::from_section(
+                        "Escape to save and quit, continue next time",
+                        instruction_style.clone(),
+                    ));
+                }
             });
 
         parent.spawn((
@@ -230,7 +466,10 @@ impl Plugin for FinishedScreenPlugin {
                 Update,
                 (
                     finished_screen_timer,
-                    restart_game.run_if(in_state(FinishedScreenState::PromptRestart)),
+                    restart_game.run_if(
+                        in_state(FinishedScreenState::PromptRestart)
+                            .and_then(not(is_online_client)),
+                    ),
                 )
                     .run_if(in_state(GameState::Finished)),
             );
@@ -261,6 +500,8 @@ fn spawn_game_finished_screen(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     game_result: Res<GameResult>,
+    players: Res<Players>,
+    game_mode: Res<GameMode>,
     mut next_finished_screen_state: ResMut<NextState<FinishedScreenState>>,
 ) {
     commands
@@ -284,8 +525,11 @@ fn spawn_game_finished_screen(
                 Name::new("Game result text"),
                 TextBundle::from_section(
                     match *game_result {
-                        GameResult::Win => "You win!",
-                        GameResult::Lose => "Game over!",
+                        GameResult::Win => "You win!".to_string(),
+                        GameResult::Lose => "Game over!".to_string(),
+                        GameResult::Winner(id) => format!("{} wins!", id.name()),
+                        GameResult::Disconnected => "Connection lost".to_string(),
+                        GameResult::Desynced => "Out of sync".to_string(),
                     },
                     TextStyle {
                         font: asset_server.load(FONT_PATH),
@@ -294,6 +538,27 @@ fn spawn_game_finished_screen(
                     },
                 ),
             ));
+
+            for stats in &players.0 {
+                parent.spawn((
+                    Name::new("Player result text"),
+                    TextBundle::from_section(
+                        match *game_mode {
+                            GameMode::Versus => {
+                                format!("{}: {} rounds", stats.id.name(), stats.rounds_won)
+                            }
+                            GameMode::Solo | GameMode::Coop => {
+                                format!("{}: {} points", stats.id.name(), stats.score)
+                            }
+                        },
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: 40.,
+                            color: stats.id.color(),
+                        },
+                    ),
+                ));
+            }
         });
 
     next_finished_screen_state.set(FinishedScreenState::Locked);
@@ -349,3 +614,81 @@ fn restart_game(
         info!("Restarting game");
     }
 }
+
+/// Score and lives of every player in the top left corner while playing.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), spawn_hud)
+            .add_systems(OnExit(GameState::Finished), cleanup_component::<Hud>)
+            .add_systems(
+                Update,
+                update_hud.run_if(resource_exists_and_changed::<Players>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component)]
+struct PlayerHudText(PlayerId);
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>, game_mode: Res<GameMode>) {
+    commands
+        .spawn((
+            Name::new("HUD"),
+            Hud,
+            NodeBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(10.)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for &id in &PlayerId::ALL[..game_mode.player_count()] {
+                parent.spawn((
+                    Name::new("Player HUD text"),
+                    PlayerHudText(id),
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: 30.,
+                            color: id.color(),
+                        },
+                    ),
+                ));
+            }
+        });
+}
+
+fn update_hud(
+    mut text_query: Query<(&mut Text, &PlayerHudText)>,
+    players: Res<Players>,
+    game_mode: Res<GameMode>,
+    versus_settings: Res<VersusSettings>,
+) {
+    for (mut text, PlayerHudText(id)) in &mut text_query {
+        if let Some(stats) = players.get(*id) {
+            text.sections[0].value = match *game_mode {
+                GameMode::Versus => format!(
+                    "{}  Rounds {}/{}",
+                    id.name(),
+                    stats.rounds_won,
+                    versus_settings.score_limit
+                ),
+                GameMode::Solo | GameMode::Coop => format!(
+                    "{}  Score {}  Lives {}",
+                    id.name(),
+                    stats.score,
+                    stats.lives
+                ),
+            };
+        }
+    }
+}
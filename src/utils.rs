@@ -13,6 +13,12 @@ use bevy::{
 use bevy_rapier2d::{geometry::Collider, plugin::RapierContext};
 use itertools::Itertools;
 
+use crate::mesh_utils::convex_decomposition;
+
+/// Builds a collider out of convex pieces of the mesh.
+///
+/// Unlike a trimesh, convex shapes have volume, which gives the bodies proper mass properties and
+/// keeps fast objects from tunneling into them.
 pub fn mesh_to_collider(mesh: &Mesh) -> Result<Collider, String> {
     let vertices = mesh
         .attribute(Mesh::ATTRIBUTE_POSITION)
@@ -23,16 +29,31 @@ pub fn mesh_to_collider(mesh: &Mesh) -> Result<Collider, String> {
         .map(|pos| Vec2::new(pos[0], pos[1])) // Ensure 2D is intended
         .collect::<Vec<_>>();
 
-    let indices_vec = mesh
+    let triangles = mesh
         .indices()
         .ok_or("Failed to get indices")?
         .iter()
-        .map(|i| i as u32)
         .tuples()
         .map(|(i0, i1, i2)| [i0, i1, i2])
         .collect::<Vec<_>>();
 
-    Ok(Collider::trimesh(vertices, indices_vec))
+    let mut convex_shapes = convex_decomposition(&vertices, &triangles)
+        .into_iter()
+        .filter_map(|polygon| Collider::convex_hull(&polygon))
+        .collect::<Vec<_>>();
+
+    match convex_shapes.len() {
+        0 => Err("Mesh has no area".to_string()),
+        1 => Ok(convex_shapes.pop().unwrap()),
+        // The parts keep their vertices in the space of the mesh, so contact points on the
+        // compound stay in mesh space as well
+        _ => Ok(Collider::compound(
+            convex_shapes
+                .into_iter()
+                .map(|shape| (Vec2::ZERO, 0., shape))
+                .collect(),
+        )),
+    }
 }
 
 pub fn cleanup_component<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
@@ -59,3 +80,23 @@ pub fn contact_position_and_normal(
 
     Some((contact_manifold.normal(), contact_view.local_p2()))
 }
+
+/// Deepest contact point between the two entities, in the local space of `entity_a`'s collider.
+pub fn local_contact_point(
+    rapier_context: &Res<RapierContext>,
+    entity_a: Entity,
+    entity_b: Entity,
+) -> Option<Vec2> {
+    let contact = rapier_context.contact_pair(entity_a, entity_b)?;
+    if !contact.has_any_active_contacts() {
+        return None;
+    }
+
+    let (_, contact_view) = contact.find_deepest_contact()?;
+
+    if contact.collider1() == entity_a {
+        Some(contact_view.local_p1())
+    } else {
+        Some(contact_view.local_p2())
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        entity::Entity,
+        query::With,
+        schedule::{
+            common_conditions::{in_state, resource_equals},
+            Condition, IntoSystemConfigs, NextState, OnEnter, SystemSet,
+        },
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    hierarchy::DespawnRecursiveExt,
+    log::info,
+    time::{Time, Timer, TimerMode},
+};
+
+use crate::{
+    asteroid::{spawn_asteroids, Asteroid},
+    edge_wrap::Bounds,
+    game_state::{GameMode, GameResult, GameState},
+    player::{spawn_player_ship, Player, PlayerId, Players},
+    rng::GameRng,
+    rollback::RollbackAppExt,
+    ship::Ship,
+};
+
+/// Rounds of [`GameMode::Versus`]: the last ship standing wins the round, the first player to win
+/// [`VersusSettings::score_limit`] rounds wins the match.
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VersusSettings>()
+            .init_resource::<Round>()
+            .rollback_resource::<Round>()
+            .add_systems(OnEnter(GameState::Playing), reset_round)
+            .add_systems(
+                Update,
+                (end_round, replenish_asteroids)
+                    .run_if(
+                        in_state(GameState::Playing).and_then(resource_equals(GameMode::Versus)),
+                    )
+                    .in_set(VersusSet),
+            );
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone)]
+pub struct VersusSet;
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct VersusSettings {
+    /// Rounds a player has to win to take the match.
+    pub score_limit: u32,
+}
+
+impl Default for VersusSettings {
+    fn default() -> Self {
+        Self {
+            score_limit: DEFAULT_SCORE_LIMIT,
+        }
+    }
+}
+
+impl VersusSettings {
+    pub fn change_score_limit(&mut self, delta: i32) {
+        self.score_limit = self
+            .score_limit
+            .saturating_add_signed(delta)
+            .clamp(1, MAX_SCORE_LIMIT);
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone)]
+struct Round {
+    /// Runs once a round is decided, the next one starts when it finishes.
+    end_timer: Option<Timer>,
+}
+
+const DEFAULT_SCORE_LIMIT: u32 = 3;
+const MAX_SCORE_LIMIT: u32 = 9;
+/// Seconds between the end of a round and the start of the next one.
+const ROUND_END_DELAY: f32 = 3.;
+
+fn reset_round(mut round: ResMut<Round>) {
+    round.end_timer = None;
+}
+
+fn end_round(
+    mut commands: Commands,
+    mut round: ResMut<Round>,
+    mut players: ResMut<Players>,
+    ship_query: Query<(Entity, &PlayerId), (With<Player>, With<Ship>)>,
+    settings: Res<VersusSettings>,
+    mut next_gamestate: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+) {
+    let Some(end_timer) = &mut round.end_timer else {
+        if ship_query.iter().count() > 1 {
+            return;
+        }
+
+        let survivor = ship_query.iter().next().map(|(_, &id)| id);
+        match survivor.and_then(|id| players.get_mut(id)) {
+            Some(stats) => {
+                stats.rounds_won += 1;
+                info!(
+                    player = stats.id.name(),
+                    rounds_won = stats.rounds_won,
+                    "Round won"
+                );
+            }
+            None => info!("Round drawn"),
+        }
+
+        round.end_timer = Some(Timer::from_seconds(ROUND_END_DELAY, TimerMode::Once));
+        return;
+    };
+
+    if !end_timer.tick(time.delta()).finished() {
+        return;
+    }
+    round.end_timer = None;
+
+    if let Some(winner) = players
+        .0
+        .iter()
+        .find(|stats| stats.rounds_won >= settings.score_limit)
+    {
+        info!(player = winner.id.name(), "Match won");
+        commands.insert_resource(GameResult::Winner(winner.id));
+        next_gamestate.set(GameState::Finished);
+        return;
+    }
+
+    // Everyone starts the next round from their spawn point with a fresh ship
+    for (ship_entity, _) in &ship_query {
+        commands.entity(ship_entity).despawn_recursive();
+    }
+    let player_count = players.0.len();
+    for stats in &mut players.0 {
+        stats.lives = 1;
+        spawn_player_ship(&mut commands, stats.id, player_count);
+    }
+    info!("Next round");
+}
+
+/// Keeps the asteroids coming, clearing them doesn't end a versus match.
+fn replenish_asteroids(
+    commands: Commands,
+    asteroid_query: Query<(), With<Asteroid>>,
+    bounds: Res<Bounds>,
+    rng: ResMut<GameRng>,
+) {
+    if asteroid_query.is_empty() {
+        spawn_asteroids(commands, bounds, rng);
+    }
+}